@@ -8,7 +8,7 @@ fn main() {
         schema_dir: PathBuf::from("inner/schema"),
         src_gen_dir: PathBuf::from(env::var("OUT_DIR").unwrap()),
         protobuf_gen_dir: PathBuf::from("../protocol/inner/schema"),
-        sql_gen_dir: PathBuf::from("../db/schema/types"),
+        sql_gen_dir: PathBuf::from("../db/migrations/types"),
 
         target: Target::Server,
         header_rows: 2,
@@ -239,7 +239,11 @@ impl Into<{enum_type_name}> for protocol::{enum_type_name} {{
         let mut from_sql_matches = Vec::new();
 
         for e in &schema.enums {
-            sql_enums.push(format!("{TAB}'{}'", e.to_snake_case()));
+            sql_enums.push(format!(
+                "alter type {} add value if not exists '{}';",
+                name.name,
+                e.to_snake_case(),
+            ));
 
             to_sql_matches.push(format!(
                 "{TAB}{TAB}{TAB}Self::{e} => out.write_all(b\"{}\")?,",
@@ -258,14 +262,19 @@ impl Into<{enum_type_name}> for protocol::{enum_type_name} {{
             .join(format!("{}.gen.sql", name.name));
         println!("Generating enumeration sql `{}`", sql_file.display());
 
+        // Applied again whenever it changes, so values are only ever added.
         let sql = format!(r#"-- {GENERATED_FILE_WARNING}
 
-create type {} as enum (
+do $$ begin
+    create type {} as enum ();
+exception
+    when duplicate_object then null;
+end $$;
+
 {enums_code}
-);
 "#,
                           name.name,
-                          enums_code = sql_enums.join(",\n"),
+                          enums_code = sql_enums.join("\n"),
         );
         fs::write(sql_file, sql)?;
 
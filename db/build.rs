@@ -0,0 +1,68 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+/// Embed `migrations/*.sql` in order of their version, and `migrations/types/*.sql`.
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+
+    let migrations = match collect(Path::new("migrations")) {
+        Ok(migrations) => migrations,
+        Err(e) => {
+            eprintln!("Failed to collect migrations: {}", e);
+            exit(1);
+        }
+    };
+    let types = match collect(Path::new("migrations/types")) {
+        Ok(types) => types,
+        Err(e) => {
+            eprintln!("Failed to collect generated types: {}", e);
+            exit(1);
+        }
+    };
+
+    for name in &migrations {
+        let version = name.split_once('_').map(|(version, _)| version);
+        if !version.is_some_and(|version| !version.is_empty() && version.bytes().all(|b| b.is_ascii_digit())) {
+            eprintln!("Migration \"{}\" must be named `<version>_<name>.sql`", name);
+            exit(1);
+        }
+    }
+
+    let code = format!(
+        "pub static MIGRATIONS: &[Migration] = &[\n{}];\n\npub static TYPES: &[Migration] = &[\n{}];\n",
+        entries(&migrations, "migrations"),
+        entries(&types, "migrations/types"),
+    );
+
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("migrations.rs");
+    if let Err(e) = fs::write(&out, code) {
+        eprintln!("Failed to write \"{}\": {}", out.display(), e);
+        exit(1);
+    }
+}
+
+/// Names of the `.sql` files of the directory, sorted.
+fn collect(dir: &Path) -> std::io::Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "sql")
+            && let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+            names.push(stem.to_string());
+        }
+    }
+    names.sort();
+
+    Ok(names)
+}
+
+fn entries(names: &[String], dir: &str) -> String {
+    names
+        .iter()
+        .map(|name| format!(
+            "    Migration {{ name: \"{name}\", sql: include_str!(concat!(env!(\"CARGO_MANIFEST_DIR\"), \"/{dir}/{name}.sql\")) }},\n",
+        ))
+        .collect()
+}
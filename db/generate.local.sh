@@ -10,4 +10,5 @@ url="postgres://${SPIRE_DB_USER}:${password}@${SPIRE_DB_HOST}:${SPIRE_DB_PORT}/$
 
 diesel print-schema \
     --database-url "$url" \
+    --except-tables schema_migration \
     > src/schema.rs
@@ -0,0 +1,115 @@
+-- Initial schema
+
+create type location as (
+    "floor" smallint,
+    id bigint
+);
+
+create type vector2 as (
+    x real,
+    y real
+);
+
+create type vector3 as (
+    x real,
+    y real,
+    z real
+);
+
+create table account (
+    id bigint not null,
+    created_at timestamptz default now() not null,
+
+    primary key (id)
+);
+
+create table dev_account (
+    id varchar(16) not null,
+    account_id bigint not null,
+    created_at timestamptz default now() not null,
+
+    primary key (id),
+    foreign key (account_id) references account (id) on delete cascade
+);
+
+create table character (
+    id bigint not null,
+    account_id bigint not null,
+    created_at timestamptz not null default now(),
+
+    -- Identity
+    name varchar(16) not null,
+    race Race not null,
+
+    -- Growth
+    level smallint not null default 0,
+    exp bigint not null default 0,
+    karma bigint not null default 0,
+
+    -- World
+    "location" location not null default (0, 0),
+    position vector3 not null default row(0, 0, 0),
+
+    -- Resource
+    health bigint not null default 0,
+    mana bigint,
+
+    -- Asset
+    gold bigint not null default 0,
+
+    primary key (id),
+    foreign key (account_id) references account (id) on delete cascade
+);
+
+create table character_path (
+    character_id bigint not null,
+    data_id integer not null,
+    is_active boolean not null default true,
+    level smallint not null default 0,
+    exp integer not null default 0,
+
+    primary key (character_id, data_id),
+    foreign key (character_id) references character (id) on delete cascade
+);
+
+create table character_talent (
+    character_id bigint not null,
+    data_id integer not null,
+    is_active boolean not null default true,
+    level smallint not null default 0,
+    exp integer not null default 0,
+
+    primary key (character_id, data_id),
+    foreign key (character_id) references character (id) on delete cascade
+);
+
+create table item (
+    id bigint not null,
+    character_id bigint not null,
+    data_id integer not null,
+    count integer not null,
+    level smallint not null,
+    is_bound boolean not null default false,
+    attributes jsonb,
+
+    primary key (id, character_id),
+    foreign key (character_id) references character (id) on delete cascade
+);
+
+create table cluster_node (
+    id smallint not null,
+    cluster_address varchar(64) not null,
+    game_address varchar(64) not null,
+    heartbeat_at timestamptz not null default now(),
+
+    primary key (id)
+);
+
+create table cluster_zone (
+    zone_id bigint not null,
+    node_id smallint not null,
+    claimed_at timestamptz not null default now(),
+
+    primary key (zone_id),
+    foreign key (node_id) references cluster_node (id) on delete cascade
+);
@@ -0,0 +1,42 @@
+-- Append-only audit of item and gold changes
+
+create table ledger_operation (
+    id bigint not null,
+    reason varchar(32) not null,
+    source varchar(64),
+    created_at timestamptz not null default now(),
+
+    primary key (id)
+);
+
+create table ledger (
+    id bigserial not null,
+    operation_id bigint not null,
+    character_id bigint not null,
+    -- Gold without item
+    item_id bigint,
+    item_data_id integer,
+    item_level smallint,
+    item_is_bound boolean,
+    delta bigint not null,
+    balance bigint not null,
+    created_at timestamptz not null default now(),
+
+    primary key (id),
+    foreign key (operation_id) references ledger_operation (id)
+);
+
+create index ledger_operation_id on ledger (operation_id);
+create index ledger_character_id on ledger (character_id, created_at);
+create index ledger_item_id on ledger (item_id);
+
+create function ledger_append_only() returns trigger as $$
+begin
+    raise exception '% is append-only', tg_table_name;
+end
+$$ language plpgsql;
+
+create trigger ledger_operation_append_only before update or delete on ledger_operation
+    for each row execute function ledger_append_only();
+create trigger ledger_append_only before update or delete on ledger
+    for each row execute function ledger_append_only();
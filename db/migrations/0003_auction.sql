@@ -0,0 +1,25 @@
+-- Auction house listings, holding their items in escrow until bought, cancelled or expired
+
+create table auction_listing (
+    id bigint not null,
+    seller_id bigint not null,
+    item_id bigint not null,
+    item_data_id integer not null,
+    item_level smallint not null,
+    item_count integer not null,
+    -- Denormalized from the item data, for searching without it
+    item_category varchar(32) not null,
+    price bigint not null,
+    -- Unix milliseconds
+    expire_at bigint not null,
+    created_at timestamptz not null default now(),
+
+    primary key (id),
+    -- Escrowed items must be returned before the seller is deleted.
+    foreign key (seller_id) references character (id)
+);
+
+create index auction_listing_category on auction_listing (item_category, price);
+create index auction_listing_data_id on auction_listing (item_data_id, price);
+create index auction_listing_seller_id on auction_listing (seller_id);
+create index auction_listing_expire_at on auction_listing (expire_at);
@@ -0,0 +1,40 @@
+-- Mailboxes, holding attached items and gold in escrow until claimed
+
+create table mail (
+    id bigint not null,
+    recipient_id bigint not null,
+    -- System mail without sender
+    sender_id bigint,
+    sender_name varchar(16) not null,
+    subject varchar(64) not null,
+    body text not null,
+    gold bigint not null default 0,
+    is_read boolean not null default false,
+    is_claimed boolean not null default false,
+    -- Attachments belonging to the recipient, e.g. auction proceeds, sent again on expiry
+    is_kept boolean not null default false,
+    -- Unix milliseconds
+    expire_at bigint not null,
+    created_at timestamptz not null default now(),
+
+    primary key (id),
+    foreign key (recipient_id) references character (id) on delete cascade,
+    -- Attachments of a deleted sender are not returned
+    foreign key (sender_id) references character (id) on delete set null
+);
+
+create index mail_recipient_id on mail (recipient_id, created_at);
+create index mail_sender_id on mail (sender_id);
+create index mail_expire_at on mail (expire_at);
+
+create table mail_attachment (
+    mail_id bigint not null,
+    item_id bigint not null,
+    item_data_id integer not null,
+    item_level smallint not null,
+    item_is_bound boolean not null,
+    item_count integer not null,
+
+    primary key (mail_id, item_id),
+    foreign key (mail_id) references mail (id) on delete cascade
+);
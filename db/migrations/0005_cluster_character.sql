@@ -0,0 +1,12 @@
+-- Node of each character in the game, to deliver protocols across the cluster
+
+create table cluster_character (
+    character_id bigint not null,
+    node_id smallint not null,
+    located_at timestamptz not null default now(),
+
+    primary key (character_id),
+    foreign key (node_id) references cluster_node (id) on delete cascade
+);
+
+create index cluster_character_node_id on cluster_character (node_id);
@@ -0,0 +1,11 @@
+-- // This file is @generated by data-generator, DO NOT EDIT MANUALLY.
+
+do $$ begin
+    create type race as enum ();
+exception
+    when duplicate_object then null;
+end $$;
+
+alter type race add value if not exists 'none';
+alter type race add value if not exists 'human';
+alter type race add value if not exists 'orc';
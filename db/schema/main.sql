@@ -1,10 +0,0 @@
-\i types/location.sql
-\i types/vector2.sql
-\i types/vector3.sql
-\i types/race.gen.sql
-
-\i tables/account.sql
-\i tables/dev_account.sql
-\i tables/character.sql
-\i tables/character_path.sql
-\i tables/character_talent.sql
@@ -1,6 +0,0 @@
-create table account (
-    id bigint not null,
-    created_at timestamptz default now() not null,
-
-    primary key (id)
-);
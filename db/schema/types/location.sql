@@ -1,4 +0,0 @@
-create type location as (
-    "floor" smallint,
-    id bigint
-);
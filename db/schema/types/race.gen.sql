@@ -1,7 +0,0 @@
--- // This file is @generated by data-generator, DO NOT EDIT MANUALLY.
-
-create type race as enum (
-    'none',
-    'human',
-    'orc'
-);
@@ -1,4 +0,0 @@
-create type vector2 as (
-    x real,
-    y real
-);
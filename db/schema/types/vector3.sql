@@ -1,5 +0,0 @@
-create type vector3 as (
-    x real,
-    y real,
-    z real
-);
@@ -0,0 +1,133 @@
+use crate::QueryError;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+/// Kind of the item data, stored with the listing to search without the data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Equipment,
+    RandomBox,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Sort {
+    #[default]
+    PriceAscending,
+    PriceDescending,
+    /// Ending soonest first.
+    ExpireAt,
+}
+
+/// Listings to search for. `None` matches anything.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    pub category: Option<Category>,
+    pub data_id: Option<i32>,
+    pub min_level: Option<i16>,
+    pub max_level: Option<i16>,
+    pub max_price: Option<i64>,
+    pub seller_id: Option<i64>,
+    pub sort: Sort,
+    pub offset: i64,
+    pub limit: i64,
+}
+
+/// A row of `auction_listing`, searched by both the game server and the lobby.
+#[derive(Debug, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::auction_listing)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Listing {
+    pub id: i64,
+    pub seller_id: i64,
+    pub item_id: i64,
+    pub item_data_id: i32,
+    pub item_level: i16,
+    pub item_count: i32,
+    pub item_category: String,
+    pub price: i64,
+    /// Unix milliseconds.
+    pub expire_at: i64,
+}
+
+impl Category {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Category::Equipment => "equipment",
+            Category::RandomBox => "random_box",
+            Category::Other => "other",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "equipment" => Some(Category::Equipment),
+            "random_box" => Some(Category::RandomBox),
+            "other" => Some(Category::Other),
+            _ => None,
+        }
+    }
+}
+
+impl Listing {
+    /// Unknown categories are searched as `Other`.
+    pub fn category(&self) -> Category {
+        Category::parse(&self.item_category).unwrap_or(Category::Other)
+    }
+}
+
+/// Listings not expired at `now`, in Unix milliseconds, matching the filter.
+pub async fn search(
+    conn: &mut AsyncPgConnection,
+    filter: &Filter,
+    now: i64,
+) -> Result<Vec<Listing>, QueryError> {
+    use crate::schema::auction_listing::dsl::*;
+
+    let mut query = auction_listing
+        .filter(expire_at.gt(now))
+        .into_boxed();
+    if let Some(category) = filter.category {
+        query = query.filter(item_category.eq(category.as_str()));
+    }
+    if let Some(data_id) = filter.data_id {
+        query = query.filter(item_data_id.eq(data_id));
+    }
+    if let Some(level) = filter.min_level {
+        query = query.filter(item_level.ge(level));
+    }
+    if let Some(level) = filter.max_level {
+        query = query.filter(item_level.le(level));
+    }
+    if let Some(max_price) = filter.max_price {
+        query = query.filter(price.le(max_price));
+    }
+    if let Some(seller) = filter.seller_id {
+        query = query.filter(seller_id.eq(seller));
+    }
+    query = match filter.sort {
+        Sort::PriceAscending => query.order((price.asc(), id.asc())),
+        Sort::PriceDescending => query.order((price.desc(), id.asc())),
+        Sort::ExpireAt => query.order((expire_at.asc(), id.asc())),
+    };
+
+    query
+        .offset(filter.offset)
+        .limit(filter.limit)
+        .select(Listing::as_select())
+        .load(conn)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn category_round_trip() {
+        for category in [Category::Equipment, Category::RandomBox, Category::Other] {
+            assert_eq!(Category::parse(category.as_str()), Some(category));
+        }
+        assert_eq!(Category::parse("unknown"), None);
+    }
+}
@@ -10,6 +10,18 @@ pub enum Error {
     
     #[error("Query error: {0}")]
     Query(#[from] QueryError),
+
+    #[error("Connection error: {0}")]
+    Connection(#[from] diesel::ConnectionError),
+
+    #[error("Migration {0} was modified after being applied")]
+    ModifiedMigration(String),
+
+    #[error("Pending migrations: {}", .0.join(", "))]
+    PendingMigrations(Vec<String>),
+
+    #[error("schema.rs does not match the database: {}", .0.join(", "))]
+    SchemaMismatch(Vec<String>),
 }
 
 pub type QueryError = diesel::result::Error;
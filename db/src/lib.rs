@@ -1,4 +1,6 @@
+pub mod auction;
 pub mod error;
+pub mod migration;
 pub mod schema;
 
 pub use error::{Error, QueryError};
@@ -0,0 +1,93 @@
+use clap::Parser;
+use db::migration::{self, State};
+use diesel_async::{AsyncConnection, AsyncPgConnection};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+/// Migrations of the database
+#[derive(clap::Parser, Debug)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+
+    /// Use local environment file
+    #[arg(long)]
+    local_env: Option<PathBuf>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Apply the pending migrations and changed generated types
+    Migrate,
+    /// List the migrations and generated types with their state
+    Status,
+    /// Fail on pending migrations or a schema.rs out of date
+    Check,
+    /// Record the migrations as applied, for a database created before them
+    Baseline,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+
+    if let Err(e) = run(&args).await {
+        eprintln!("Failed to {:?}: {}", args.command, e);
+        exit(1);
+    }
+}
+
+async fn run(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(local_env) = &args.local_env {
+        dotenvy::from_filename(local_env)?;
+    }
+
+    let mut conn = AsyncPgConnection::establish(&url()?).await?;
+
+    match args.command {
+        Command::Migrate => {
+            let applied = migration::run(&mut conn).await?;
+            for name in &applied {
+                println!("Applied {}", name);
+            }
+            if applied.is_empty() {
+                println!("Already up to date");
+            }
+        }
+        Command::Status => {
+            for (migration, state) in migration::status(&mut conn).await? {
+                let state = match state {
+                    State::Applied => "applied",
+                    State::Pending => "pending",
+                    State::Modified => "modified",
+                };
+                println!("{:<8} {}", state, migration.name);
+            }
+        }
+        Command::Baseline => {
+            migration::baseline(&mut conn).await?;
+            println!("Recorded the migrations as applied");
+        }
+        Command::Check => {
+            migration::check(&mut conn).await?;
+            println!("Up to date");
+        }
+    }
+
+    Ok(())
+}
+
+/// Database URL from the `SPIRE_DB_*` variables, as used by the servers.
+fn url() -> Result<String, Box<dyn std::error::Error>> {
+    let var = |name: &str| std::env::var(name).map_err(|_| format!("{name} is not set"));
+    let password = util::io::read_file(Path::new(&var("SPIRE_DB_PASSWORD_FILE")?))?;
+
+    Ok(format!(
+        "postgres://{}:{}@{}:{}/{}",
+        var("SPIRE_DB_USER")?,
+        password,
+        std::env::var("SPIRE_DB_HOST").unwrap_or_else(|_| "127.0.0.1".to_string()),
+        var("SPIRE_DB_PORT")?,
+        var("SPIRE_DB_NAME")?,
+    ))
+}
@@ -0,0 +1,190 @@
+mod check;
+
+pub use check::check_schema;
+
+use crate::Error;
+use diesel::sql_types::{BigInt, Varchar};
+use diesel::{QueryableByName, sql_query};
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl, SimpleAsyncConnection};
+use std::collections::HashMap;
+
+include!(concat!(env!("OUT_DIR"), "/migrations.rs"));
+
+/// Held while migrating, so nodes starting together migrate one at a time.
+const LOCK_KEY: i64 = 0x5350_4952_455f_4d47;
+
+const CREATE_TABLE: &str = "
+create table if not exists schema_migration (
+    name varchar(128) not null,
+    checksum bigint not null,
+    applied_at timestamptz not null default now(),
+
+    primary key (name)
+);
+";
+
+/// A versioned migration of `db/migrations`, or a generated type of `db/migrations/types`.
+///
+/// Migrations are applied once in order of version and must not change afterward. Generated types
+/// are idempotent, applied before the migrations and again whenever they change.
+pub struct Migration {
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Applied,
+    Pending,
+    /// Applied with different contents.
+    Modified,
+}
+
+#[derive(QueryableByName)]
+struct AppliedRow {
+    #[diesel(sql_type = Varchar)]
+    name: String,
+    #[diesel(sql_type = BigInt)]
+    checksum: i64,
+}
+
+impl Migration {
+    /// FNV-1a of the contents.
+    pub fn checksum(&self) -> i64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in self.sql.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+
+        hash as i64
+    }
+}
+
+/// State of the migrations, then of the generated types.
+pub async fn status(conn: &mut AsyncPgConnection) -> Result<Vec<(&'static Migration, State)>, Error> {
+    conn.batch_execute(CREATE_TABLE).await?;
+    let applied = applied(conn).await?;
+
+    let migrations = MIGRATIONS.iter().map(|migration| {
+        let state = match applied.get(migration.name) {
+            None => State::Pending,
+            Some(&checksum) if checksum == migration.checksum() => State::Applied,
+            Some(_) => State::Modified,
+        };
+
+        (migration, state)
+    });
+    // A changed type is only applied again.
+    let types = TYPES.iter().map(|migration| {
+        let state = match applied.get(migration.name) {
+            Some(&checksum) if checksum == migration.checksum() => State::Applied,
+            _ => State::Pending,
+        };
+
+        (migration, state)
+    });
+
+    Ok(migrations.chain(types).collect())
+}
+
+/// Apply the changed generated types and the pending migrations, returning their names.
+pub async fn run(conn: &mut AsyncPgConnection) -> Result<Vec<&'static str>, Error> {
+    conn.batch_execute(CREATE_TABLE).await?;
+    conn.batch_execute(&format!("select pg_advisory_lock({LOCK_KEY})")).await?;
+
+    let result = apply(conn).await;
+
+    conn.batch_execute(&format!("select pg_advisory_unlock({LOCK_KEY})")).await?;
+    result
+}
+
+/// Record the migrations as applied without applying them, for a database created before them.
+pub async fn baseline(conn: &mut AsyncPgConnection) -> Result<(), Error> {
+    conn.batch_execute(CREATE_TABLE).await?;
+
+    for migration in MIGRATIONS {
+        record(conn, migration).await?;
+    }
+
+    Ok(())
+}
+
+/// Fail on pending or modified migrations, or a `schema.rs` out of date.
+pub async fn check(conn: &mut AsyncPgConnection) -> Result<(), Error> {
+    let mut pending = Vec::new();
+    for (migration, state) in status(conn).await? {
+        match state {
+            State::Applied => {}
+            State::Pending => pending.push(migration.name.to_string()),
+            State::Modified => return Err(Error::ModifiedMigration(migration.name.to_string())),
+        }
+    }
+    if !pending.is_empty() {
+        return Err(Error::PendingMigrations(pending));
+    }
+
+    check_schema(conn).await
+}
+
+async fn apply(conn: &mut AsyncPgConnection) -> Result<Vec<&'static str>, Error> {
+    let applied = applied(conn).await?;
+    let mut names = Vec::new();
+
+    // Migrations may use the types, which are safe to apply again.
+    for migration in TYPES {
+        if applied.get(migration.name) != Some(&migration.checksum()) {
+            apply_one(conn, migration).await?;
+            names.push(migration.name);
+        }
+    }
+
+    for migration in MIGRATIONS {
+        match applied.get(migration.name) {
+            None => {
+                apply_one(conn, migration).await?;
+                names.push(migration.name);
+            }
+            Some(&checksum) if checksum != migration.checksum() => {
+                return Err(Error::ModifiedMigration(migration.name.to_string()));
+            }
+            Some(_) => {}
+        }
+    }
+
+    Ok(names)
+}
+
+async fn apply_one(conn: &mut AsyncPgConnection, migration: &'static Migration) -> Result<(), Error> {
+    conn.transaction::<_, diesel::result::Error, _>(|conn| async move {
+        conn.batch_execute(migration.sql).await?;
+        record(conn, migration).await
+    }.scope_boxed()).await?;
+
+    Ok(())
+}
+
+async fn record(
+    conn: &mut AsyncPgConnection,
+    migration: &Migration,
+) -> Result<(), diesel::result::Error> {
+    sql_query("
+        insert into schema_migration (name, checksum) values ($1, $2)
+        on conflict (name) do update set checksum = excluded.checksum, applied_at = now()
+    ")
+        .bind::<Varchar, _>(migration.name)
+        .bind::<BigInt, _>(migration.checksum())
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+async fn applied(conn: &mut AsyncPgConnection) -> Result<HashMap<String, i64>, Error> {
+    let rows: Vec<AppliedRow> = sql_query("select name, checksum from schema_migration")
+        .load(conn)
+        .await?;
+
+    Ok(rows.into_iter().map(|row| (row.name, row.checksum)).collect())
+}
@@ -0,0 +1,214 @@
+use crate::Error;
+use diesel::sql_types::{Bool, Varchar};
+use diesel::{QueryableByName, sql_query};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use std::collections::BTreeMap;
+
+const SCHEMA: &str = include_str!("../schema.rs");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Column {
+    name: String,
+    /// Postgres type name, as in `information_schema.columns.udt_name`.
+    udt_name: String,
+    nullable: bool,
+}
+
+#[derive(QueryableByName)]
+struct ColumnRow {
+    #[diesel(sql_type = Varchar)]
+    table_name: String,
+    #[diesel(sql_type = Varchar)]
+    column_name: String,
+    #[diesel(sql_type = Varchar)]
+    udt_name: String,
+    #[diesel(sql_type = Bool)]
+    nullable: bool,
+}
+
+/// Fail unless the tables of `schema.rs` are the tables of the database, column for column.
+pub async fn check_schema(conn: &mut AsyncPgConnection) -> Result<(), Error> {
+    let rows: Vec<ColumnRow> = sql_query("
+        select table_name::varchar, column_name::varchar, udt_name::varchar, is_nullable = 'YES' as nullable
+        from information_schema.columns
+        where table_schema = current_schema() and table_name <> 'schema_migration'
+        order by table_name, ordinal_position
+    ")
+        .load(conn)
+        .await?;
+
+    let mut actual: BTreeMap<String, Vec<Column>> = BTreeMap::new();
+    for row in rows {
+        actual.entry(row.table_name).or_default().push(Column {
+            name: row.column_name,
+            udt_name: row.udt_name,
+            nullable: row.nullable,
+        });
+    }
+
+    let mismatches = compare(&parse(SCHEMA), &actual);
+    if !mismatches.is_empty() {
+        return Err(Error::SchemaMismatch(mismatches));
+    }
+
+    Ok(())
+}
+
+fn compare(
+    expected: &BTreeMap<String, Vec<Column>>,
+    actual: &BTreeMap<String, Vec<Column>>,
+) -> Vec<String> {
+    let mut mismatches = Vec::new();
+
+    for (table, columns) in expected {
+        let Some(actual_columns) = actual.get(table) else {
+            mismatches.push(format!("missing table {table}"));
+            continue;
+        };
+
+        for column in columns {
+            match actual_columns.iter().find(|c| c.name == column.name) {
+                None => mismatches.push(format!("missing column {table}.{}", column.name)),
+                Some(actual_column) if actual_column != column => mismatches.push(format!(
+                    "column {table}.{} is {}{} instead of {}{}",
+                    column.name,
+                    actual_column.udt_name,
+                    if actual_column.nullable { " null" } else { "" },
+                    column.udt_name,
+                    if column.nullable { " null" } else { "" },
+                )),
+                Some(_) => {}
+            }
+        }
+        for column in actual_columns {
+            if !columns.iter().any(|c| c.name == column.name) {
+                mismatches.push(format!("unknown column {table}.{}", column.name));
+            }
+        }
+    }
+    for table in actual.keys() {
+        if !expected.contains_key(table) {
+            mismatches.push(format!("unknown table {table}"));
+        }
+    }
+
+    mismatches
+}
+
+/// Tables of the `diesel::table!` blocks of a schema printed by Diesel CLI.
+fn parse(source: &str) -> BTreeMap<String, Vec<Column>> {
+    let mut tables = BTreeMap::new();
+    let mut current: Option<(String, Vec<Column>)> = None;
+
+    for line in source.lines().map(str::trim) {
+        match &mut current {
+            Some(_) if line == "}" => {
+                let (name, columns) = current.take().unwrap();
+                tables.insert(name, columns);
+            }
+            Some((_, columns)) => {
+                let Some((name, sql_type)) = line.split_once("->") else {
+                    continue;
+                };
+                let sql_type = sql_type.trim().trim_end_matches(',');
+                let (sql_type, nullable) = match sql_type
+                    .strip_prefix("Nullable<")
+                    .and_then(|inner| inner.strip_suffix('>')) {
+                    Some(inner) => (inner, true),
+                    None => (sql_type, false),
+                };
+
+                columns.push(Column {
+                    name: name.trim().trim_start_matches("r#").to_string(),
+                    udt_name: udt_name(sql_type),
+                    nullable,
+                });
+            }
+            // e.g. `character_path (character_id, data_id) {`
+            None => {
+                if let Some((name, rest)) = line.split_once(" (")
+                    && rest.ends_with(") {")
+                    && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_') {
+                    current = Some((name.to_string(), Vec::new()));
+                }
+            }
+        }
+    }
+
+    tables
+}
+
+/// Postgres name of a Diesel SQL type. Custom types are named after their Rust type.
+fn udt_name(sql_type: &str) -> String {
+    if let Some(inner) = sql_type.strip_prefix("Array<").and_then(|inner| inner.strip_suffix('>')) {
+        return format!("_{}", udt_name(inner));
+    }
+
+    match sql_type {
+        "Int2" | "SmallInt" => "int2",
+        "Int4" | "Integer" => "int4",
+        "Int8" | "BigInt" => "int8",
+        "Float4" | "Float" => "float4",
+        "Float8" | "Double" => "float8",
+        "Bool" => "bool",
+        "Text" => "text",
+        "Varchar" => "varchar",
+        "Bytea" | "Binary" => "bytea",
+        "Timestamp" => "timestamp",
+        "Timestamptz" => "timestamptz",
+        "Date" => "date",
+        "Interval" => "interval",
+        "Json" => "json",
+        "Jsonb" => "jsonb",
+        "Uuid" => "uuid",
+        custom => return custom.to_lowercase(),
+    }.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_schema() {
+        let tables = parse(r#"
+pub mod sql_types {
+    #[derive(diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "race"))]
+    pub struct Race;
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::Race;
+
+    character (id) {
+        id -> Int8,
+        #[max_length = 16]
+        name -> Varchar,
+        race -> Race,
+        mana -> Nullable<Int8>,
+    }
+}
+
+diesel::joinable!(character -> account (account_id));
+"#);
+
+        let column = |name: &str, udt_name: &str, nullable| Column {
+            name: name.to_string(),
+            udt_name: udt_name.to_string(),
+            nullable,
+        };
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables["character"], vec![
+            column("id", "int8", false),
+            column("name", "varchar", false),
+            column("race", "race", false),
+            column("mana", "int8", true),
+        ]);
+
+        let mut actual = tables.clone();
+        actual.get_mut("character").unwrap()[3].nullable = false;
+        assert_eq!(compare(&tables, &actual), vec!["column character.mana is int8 instead of int8 null"]);
+    }
+}
@@ -21,6 +21,22 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    auction_listing (id) {
+        id -> Int8,
+        seller_id -> Int8,
+        item_id -> Int8,
+        item_data_id -> Int4,
+        item_level -> Int2,
+        item_count -> Int4,
+        #[max_length = 32]
+        item_category -> Varchar,
+        price -> Int8,
+        expire_at -> Int8,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::Race;
@@ -65,6 +81,33 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    cluster_character (character_id) {
+        character_id -> Int8,
+        node_id -> Int2,
+        located_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    cluster_node (id) {
+        id -> Int2,
+        #[max_length = 64]
+        cluster_address -> Varchar,
+        #[max_length = 64]
+        game_address -> Varchar,
+        heartbeat_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    cluster_zone (zone_id) {
+        zone_id -> Int8,
+        node_id -> Int2,
+        claimed_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     dev_account (id) {
         #[max_length = 16]
@@ -74,15 +117,99 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    item (id, character_id) {
+        id -> Int8,
+        character_id -> Int8,
+        data_id -> Int4,
+        count -> Int4,
+        level -> Int2,
+        is_bound -> Bool,
+        attributes -> Nullable<Jsonb>,
+    }
+}
+
+diesel::table! {
+    ledger (id) {
+        id -> Int8,
+        operation_id -> Int8,
+        character_id -> Int8,
+        item_id -> Nullable<Int8>,
+        item_data_id -> Nullable<Int4>,
+        item_level -> Nullable<Int2>,
+        item_is_bound -> Nullable<Bool>,
+        delta -> Int8,
+        balance -> Int8,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    ledger_operation (id) {
+        id -> Int8,
+        #[max_length = 32]
+        reason -> Varchar,
+        #[max_length = 64]
+        source -> Nullable<Varchar>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    mail (id) {
+        id -> Int8,
+        recipient_id -> Int8,
+        sender_id -> Nullable<Int8>,
+        #[max_length = 16]
+        sender_name -> Varchar,
+        #[max_length = 64]
+        subject -> Varchar,
+        body -> Text,
+        gold -> Int8,
+        is_read -> Bool,
+        is_claimed -> Bool,
+        is_kept -> Bool,
+        expire_at -> Int8,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    mail_attachment (mail_id, item_id) {
+        mail_id -> Int8,
+        item_id -> Int8,
+        item_data_id -> Int4,
+        item_level -> Int2,
+        item_is_bound -> Bool,
+        item_count -> Int4,
+    }
+}
+
+diesel::joinable!(auction_listing -> character (seller_id));
 diesel::joinable!(character -> account (account_id));
 diesel::joinable!(character_path -> character (character_id));
 diesel::joinable!(character_talent -> character (character_id));
+diesel::joinable!(cluster_character -> cluster_node (node_id));
+diesel::joinable!(cluster_zone -> cluster_node (node_id));
 diesel::joinable!(dev_account -> account (account_id));
+diesel::joinable!(item -> character (character_id));
+diesel::joinable!(ledger -> ledger_operation (operation_id));
+diesel::joinable!(mail -> character (recipient_id));
+diesel::joinable!(mail_attachment -> mail (mail_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     account,
+    auction_listing,
     character,
     character_path,
     character_talent,
+    cluster_character,
+    cluster_node,
+    cluster_zone,
     dev_account,
+    item,
+    ledger,
+    ledger_operation,
+    mail,
+    mail_attachment,
 );
@@ -0,0 +1,19 @@
+use std::env;
+use std::path::PathBuf;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Generate typed client protocols.
+    let config = protocol_generator::Config {
+        schema_dir: PathBuf::from("../protocol/inner/schema"),
+        gen_dir: PathBuf::from(env::var("OUT_DIR")?),
+        docs_dir: None,
+        descriptor_set: None,
+        lock_file: None,
+        generate_impl: false,
+        generate_handle: false,
+        generate_client: true,
+    };
+    config.generate()?;
+
+    Ok(())
+}
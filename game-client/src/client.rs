@@ -0,0 +1,229 @@
+use crate::codec::{self, Decoder};
+use crate::{Error, IngressClientProtocol, ServerProtocol};
+use protocol::game::auth::{login, Login, LoginResult};
+use protocol::game::{Compression, PROTOCOL_VERSION, SCHEMA_HASH, STREAM_COUNT};
+use quinn::crypto::rustls::QuicClientConfig;
+use quinn::{Connection, Endpoint, RecvStream, SendStream};
+use rustls::pki_types::CertificateDer;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+use tokio::sync::mpsc;
+
+/// Protocols longer than this are refused, like the server does by default.
+pub const MAX_PROTOCOL_LENGTH: u32 = 1024 * 1024;
+/// Protocols sent shorter than this are not compressed.
+pub const COMPRESSION_THRESHOLD: usize = 1024;
+
+/// Connection to a game server, sending each protocol on the stream of its category and
+/// receiving protocols of every stream and datagrams in order of arrival.
+pub struct Client {
+    connection: Connection,
+    /// Opened on the first protocol of each stream, except the main stream.
+    send_streams: Vec<Option<SendStream>>,
+    receiver: mpsc::UnboundedReceiver<Result<IngressClientProtocol, Error>>,
+    compression: Option<Compression>,
+    stats: Arc<Stats>,
+}
+
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub protocols_sent: AtomicU64,
+    pub bytes_sent: AtomicU64,
+    pub protocols_received: AtomicU64,
+    pub bytes_received: AtomicU64,
+}
+
+/// Client endpoint trusting the given certificates, e.g. the self-signed one of a local server.
+pub fn endpoint(
+    application_protocol: &str,
+    certs: impl IntoIterator<Item = CertificateDer<'static>>,
+) -> Result<Endpoint, Error> {
+    let mut roots = rustls::RootCertStore::empty();
+    for cert in certs {
+        roots.add(cert)?;
+    }
+
+    let mut tls_config = rustls::ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    tls_config.alpn_protocols = vec![application_protocol.as_bytes().to_vec()];
+
+    let mut endpoint = Endpoint::client(SocketAddr::from(([0, 0, 0, 0], 0)))?;
+    endpoint.set_default_client_config(quinn::ClientConfig::new(Arc::new(
+        QuicClientConfig::try_from(tls_config)?,
+    )));
+
+    Ok(endpoint)
+}
+
+impl Client {
+    pub async fn connect(
+        endpoint: &Endpoint,
+        address: SocketAddr,
+        server_name: &str,
+    ) -> Result<Self, Error> {
+        let connection = endpoint.connect(address, server_name)?.await?;
+        let (send_stream, receive_stream) = connection.open_bi().await?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let stats = Arc::new(Stats::default());
+
+        Self::start_receive(receive_stream, sender.clone(), stats.clone());
+        Self::start_accept_streams(connection.clone(), sender.clone(), stats.clone());
+        Self::start_receive_datagrams(connection.clone(), sender, stats.clone());
+
+        let mut send_streams: Vec<Option<SendStream>> = (0..STREAM_COUNT).map(|_| None).collect();
+        send_streams[0] = Some(send_stream);
+
+        Ok(Self {
+            connection,
+            send_streams,
+            receiver,
+            compression: None,
+            stats,
+        })
+    }
+
+    /// Log in with the version of this build, and wait for the result.
+    /// Compression is enabled if the server accepted it.
+    pub async fn login(&mut self, login: Login) -> Result<LoginResult, Error> {
+        let login = Login {
+            version_major: PROTOCOL_VERSION.major,
+            version_minor: PROTOCOL_VERSION.minor,
+            schema_hash: SCHEMA_HASH,
+            compressions: vec![login::Compression::Lz4.into()],
+            ..login
+        };
+        self.send(&login).await?;
+
+        let result = match self.recv().await? {
+            IngressClientProtocol::LoginResult(result) => result,
+            protocol => return Err(Error::UnexpectedProtocol(protocol.protocol_id())),
+        };
+
+        if result.error.is_none() && result.compression == i32::from(login::Compression::Lz4) {
+            self.compression = Some(Compression { threshold: COMPRESSION_THRESHOLD });
+        }
+
+        Ok(result)
+    }
+
+    pub async fn send(&mut self, protocol: &impl ServerProtocol) -> Result<(), Error> {
+        let bytes = codec::encode(protocol, self.compression)?;
+        let stream_id = protocol::game::protocol_stream(protocol.protocol_id());
+
+        let stream = match &mut self.send_streams[stream_id as usize] {
+            Some(stream) => stream,
+            stream => {
+                let mut opened = self.connection.open_uni().await?;
+                opened.write_all(&[stream_id]).await?;
+                stream.insert(opened)
+            }
+        };
+        stream.write_all(&bytes).await?;
+
+        self.stats.protocols_sent.fetch_add(1, Ordering::Relaxed);
+        self.stats.bytes_sent.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    /// Wait for the next protocol received on any stream.
+    pub async fn recv(&mut self) -> Result<IngressClientProtocol, Error> {
+        self.receiver.recv().await.unwrap_or(Err(Error::Closed))
+    }
+
+    /// The next protocol received, if any.
+    pub fn try_recv(&mut self) -> Option<Result<IngressClientProtocol, Error>> {
+        self.receiver.try_recv().ok()
+    }
+
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    pub fn close(&self) {
+        self.connection.close(0u32.into(), b"");
+    }
+
+    fn start_receive(
+        mut stream: RecvStream,
+        sender: mpsc::UnboundedSender<Result<IngressClientProtocol, Error>>,
+        stats: Arc<Stats>,
+    ) {
+        tokio::spawn(async move {
+            let mut decoder = Decoder::new(MAX_PROTOCOL_LENGTH);
+
+            loop {
+                let protocol = match decoder.decode() {
+                    Ok(Some(protocol)) => protocol,
+                    Ok(None) => match stream.read_buf(decoder.buffer_mut()).await {
+                        Ok(0) => return,
+                        Ok(n) => {
+                            stats.bytes_received.fetch_add(n as u64, Ordering::Relaxed);
+                            continue;
+                        }
+                        Err(e) => {
+                            _ = sender.send(Err(e.into()));
+                            return;
+                        }
+                    },
+                    Err(e) => {
+                        _ = sender.send(Err(e));
+                        return;
+                    }
+                };
+
+                stats.protocols_received.fetch_add(1, Ordering::Relaxed);
+                if sender.send(Ok(protocol)).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+
+    fn start_accept_streams(
+        connection: Connection,
+        sender: mpsc::UnboundedSender<Result<IngressClientProtocol, Error>>,
+        stats: Arc<Stats>,
+    ) {
+        tokio::spawn(async move {
+            while let Ok(mut stream) = connection.accept_uni().await {
+                let sender = sender.clone();
+                let stats = stats.clone();
+
+                tokio::spawn(async move {
+                    // Streams are told apart by the server only, protocols of all of them are
+                    // received in order of arrival.
+                    if stream.read_u8().await.is_err() {
+                        return;
+                    }
+
+                    Self::start_receive(stream, sender, stats);
+                });
+            }
+        });
+    }
+
+    fn start_receive_datagrams(
+        connection: Connection,
+        sender: mpsc::UnboundedSender<Result<IngressClientProtocol, Error>>,
+        stats: Arc<Stats>,
+    ) {
+        tokio::spawn(async move {
+            while let Ok(datagram) = connection.read_datagram().await {
+                stats.bytes_received.fetch_add(datagram.len() as u64, Ordering::Relaxed);
+                stats.protocols_received.fetch_add(1, Ordering::Relaxed);
+
+                if sender.send(codec::decode_datagram(&datagram, MAX_PROTOCOL_LENGTH)).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+}
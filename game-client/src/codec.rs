@@ -0,0 +1,65 @@
+use crate::{decode_client, Error, IngressClientProtocol, ServerProtocol};
+use bytes::{Buf, Bytes, BytesMut};
+use protocol::game::{Compression, Header};
+
+/// Encode a protocol for the server, compressing it if negotiated.
+pub fn encode(
+    protocol: &impl ServerProtocol,
+    compression: Option<Compression>,
+) -> Result<Bytes, Error> {
+    Ok(protocol::game::encode_with(protocol, compression)?)
+}
+
+/// Splits the bytes received on a stream into protocols.
+pub struct Decoder {
+    buffer: BytesMut,
+    max_length: u32,
+}
+
+impl Decoder {
+    pub fn new(max_length: u32) -> Self {
+        Self {
+            buffer: BytesMut::with_capacity(8 * 1024),
+            max_length,
+        }
+    }
+
+    /// Buffer to read received bytes into.
+    pub fn buffer_mut(&mut self) -> &mut BytesMut {
+        &mut self.buffer
+    }
+
+    /// Decode the next protocol, if fully received.
+    pub fn decode(&mut self) -> Result<Option<IngressClientProtocol>, Error> {
+        let header_size = Header::decoded_size(&self.buffer);
+        if self.buffer.len() < header_size {
+            return Ok(None);
+        }
+
+        let header = Header::decode(&self.buffer)?;
+        if header.length > self.max_length {
+            return Err(Error::ProtocolTooLong(header.length));
+        }
+        if self.buffer.len() < header_size + header.length as usize {
+            return Ok(None);
+        }
+
+        self.buffer.advance(header_size);
+        let body = self.buffer.split_to(header.length as usize).freeze();
+        let body = if header.compressed {
+            protocol::game::decompress(&body, self.max_length as usize)?
+        } else {
+            body
+        };
+
+        Ok(Some(decode_client(header.id, body)?))
+    }
+}
+
+/// Decode a protocol sent as a single datagram, like movement syncs.
+pub fn decode_datagram(datagram: &[u8], max_length: u32) -> Result<IngressClientProtocol, Error> {
+    let mut decoder = Decoder::new(max_length);
+    decoder.buffer_mut().extend_from_slice(datagram);
+
+    decoder.decode()?.ok_or(Error::IncompleteDatagram(datagram.len()))
+}
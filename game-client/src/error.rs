@@ -0,0 +1,38 @@
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Protocol error: {0}")]
+    Protocol(#[from] protocol::game::Error),
+
+    #[error("Connect error: {0}")]
+    Connect(#[from] quinn::ConnectError),
+
+    #[error("Connection error: {0}")]
+    Connection(#[from] quinn::ConnectionError),
+
+    #[error("Read error: {0}")]
+    Read(#[from] quinn::ReadError),
+
+    #[error("Write error: {0}")]
+    Write(#[from] quinn::WriteError),
+
+    #[error("IO error: {0}")]
+    IO(#[from] std::io::Error),
+
+    #[error("TLS error: {0}")]
+    Tls(#[from] rustls::Error),
+
+    #[error("QUIC TLS error: {0}")]
+    QuicTls(#[from] quinn::crypto::rustls::NoInitialCipherSuite),
+
+    #[error("Protocol too long: {0}")]
+    ProtocolTooLong(u32),
+
+    #[error("Incomplete datagram of {0} bytes")]
+    IncompleteDatagram(usize),
+
+    #[error("Unexpected protocol: {0}")]
+    UnexpectedProtocol(protocol::game::ProtocolId),
+
+    #[error("Connection closed")]
+    Closed,
+}
@@ -0,0 +1,9 @@
+pub mod client;
+pub mod codec;
+mod error;
+
+pub use client::Client;
+pub use codec::Decoder;
+pub use error::Error;
+
+include!(concat!(env!("OUT_DIR"), "/spire.protocol.game.client.rs"));
@@ -7,8 +7,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         schema_dir: PathBuf::from("../protocol/inner/schema"),
         gen_dir: PathBuf::from(env::var("OUT_DIR")?),
         docs_dir: None,
+        descriptor_set: None,
+        lock_file: None,
         generate_impl: false,
         generate_handle: true,
+        generate_client: false,
     };
     config.generate()?;
 
@@ -12,16 +12,13 @@ pub mod skill_set;
 pub mod talent_tree;
 // pub mod vision;
 
+use crate::persistence::{self, repositories};
 use bevy_ecs::prelude::*;
 use data::character::Race;
-use diesel::prelude::*;
-use diesel_async::RunQueryDsl;
 use std::collections::HashMap;
 use util::id::Id;
 
-#[derive(Debug, Component, Queryable, Selectable)]
-#[diesel(table_name = db::schema::character)]
-#[diesel(check_for_backend(diesel::pg::Pg))]
+#[derive(Debug, Component)]
 pub struct Character {
     pub id: Id,
     pub name: String,
@@ -34,18 +31,13 @@ pub struct Characters {
 }
 
 impl Character {
-    pub async fn load(
-        conn: &mut db::Connection,
-        character_id: Id,
-    ) -> Result<Character, db::Error> {
-        use db::schema::character::dsl::*;
+    pub async fn load(character_id: Id) -> Result<Character, persistence::Error> {
+        let record = repositories().character.get(character_id).await?;
 
-        let c = character
-            .select(Character::as_select())
-            .filter(id.eq(character_id))
-            .first(conn)
-            .await?;
-
-        Ok(c)
+        Ok(Character {
+            id: record.id,
+            name: record.name,
+            race: record.race,
+        })
     }
 }
@@ -1,8 +1,7 @@
+use crate::persistence::{self, repositories};
 use bevy_ecs::prelude::*;
 use data::character::PathTable;
 use data::prelude::*;
-use diesel::prelude::*;
-use diesel_async::RunQueryDsl;
 use std::collections::HashMap;
 use tracing::warn;
 
@@ -21,36 +20,15 @@ pub struct PathNode {
     pub exp: u32,
 }
 
-#[derive(Debug, Queryable, Selectable)]
-#[diesel(table_name = db::schema::character_path)]
-#[diesel(check_for_backend(diesel::pg::Pg))]
-struct SkillModel {
-    pub data_id: i32,
-    pub is_active: bool,
-    pub level: i16,
-    pub exp: i32,
-}
-
 impl PathTree {
-    pub async fn load(
-        conn: &mut db::Connection,
-        character_id: i64,
-    ) -> Result<Self, db::Error> {
+    pub async fn load(character_id: i64) -> Result<Self, persistence::Error> {
         let mut tree = Self::default();
 
-        let mut skills = {
-            use db::schema::character_path::dsl::*;
-            character_path
-                .filter(character_id.eq(character_id))
-                .select(SkillModel::as_select())
-                .load(conn)
-                .await?
-        };
+        let mut skills = repositories().path.list(character_id).await?;
 
         for skill in skills.drain(..) {
             let Some(data) = PathTable::get(&skill.data_id.into()) else {
-                warn!("Invalid {} record: character_id={}, data_id={}",
-                    std::any::type_name::<db::schema::character_path::table>(),
+                warn!("Invalid path record: character_id={}, data_id={}",
                     character_id,
                     skill.data_id,
                 );
@@ -1,8 +1,75 @@
-use std::collections::HashMap;
-
+use crate::config;
+use crate::net::session::Session;
+use crate::world::rewind::{self, HitError};
 use bevy_ecs::prelude::*;
+use protocol::game::play::{skill_use_result, SkillUse, SkillUseResult};
 
 #[derive(Component)]
 pub struct SkillSet {
 
 }
+
+/// Skill uses received from the client, to be resolved on the next tick.
+#[derive(Component, Default)]
+pub struct SkillCommands {
+    pub queue: Vec<SkillCommand>,
+}
+
+pub struct SkillCommand {
+    pub skill_use: SkillUse,
+    /// The tick the client was seeing when using the skill. Hits are validated against it.
+    pub view_tick: u64,
+}
+
+pub fn register(schedule: &mut Schedule) {
+    schedule.add_systems(resolve);
+}
+
+/// Resolve the skill uses queued this tick, judging each hit as its client saw it.
+fn resolve(world: &mut World) {
+    let mut query = world.query::<(Entity, &mut SkillCommands)>();
+    let queued: Vec<_> = query
+        .iter_mut(world)
+        .filter(|(_, commands)| !commands.queue.is_empty())
+        .map(|(entity, mut commands)| (entity, std::mem::take(&mut commands.queue)))
+        .collect();
+
+    for (attacker, commands) in queued {
+        for command in commands {
+            let result = hit(world, attacker, &command);
+
+            if let Some(session) = world.get::<Session>(attacker) {
+                session.send(&result);
+            }
+        }
+    }
+}
+
+fn hit(world: &World, attacker: Entity, command: &SkillCommand) -> SkillUseResult {
+    use skill_use_result::Error;
+
+    let skill_use = &command.skill_use;
+    let mut result = SkillUseResult {
+        skill_id: skill_use.skill_id,
+        target: skill_use.target,
+        ..Default::default()
+    };
+
+    let target = Entity::try_from_bits(skill_use.target)
+        .filter(|target| *target != attacker && world.get_entity(*target).is_ok());
+    let Some(target) = target else {
+        result.error = Some(Error::InvalidTarget.into());
+        return result;
+    };
+
+    let config = &config!(app).skill;
+    if let Err(e) = rewind::check_range(world, attacker, target, command.view_tick, config.range) {
+        let error = match e {
+            HitError::Range { .. } => Error::OutOfRange,
+            HitError::Transform(..) => Error::InvalidTarget,
+        };
+        result.error = Some(error.into());
+    }
+
+    result
+}
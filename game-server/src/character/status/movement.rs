@@ -1,6 +1,7 @@
 use crate::calc::BasedValue;
 use crate::net::session::Session;
 use crate::physics::Speed;
+use crate::world::time::Time;
 use crate::world::transform::Transform;
 use bevy_ecs::prelude::*;
 use nalgebra::UnitVector2;
@@ -61,9 +62,9 @@ pub fn register(schedule: &mut Schedule) {
 }
 
 fn process_commands(
+    time: Res<Time>,
     mut query: Query<(&mut MovementCommands, &mut Movement, &mut Transform)>,
 ) {
-    let now = chrono::Utc::now().timestamp_millis();
     let mut commands_buffer = Vec::with_capacity(8);
 
     for (mut commands, mut movement, mut transform) in query.iter_mut() {
@@ -80,27 +81,37 @@ fn process_commands(
             commands_buffer.push((timestamp, command));
         }
 
+        // Commands of a tick share the fixed step. Each lasts until the next one, and the last
+        // one lasts for the rest of the step.
+        let mut remaining = time.delta_secs();
+
         for i in 0..commands_buffer.len() {
             let (timestamp, command) = commands_buffer.get(i).unwrap();
-            let next_timestamp = match commands_buffer.get(i + 1) {
-                Some((next_timestamp, _)) => *next_timestamp,
-                None => now,
-            };
-
-            if next_timestamp <= *timestamp || *timestamp < commands.last_timestamp {
+            if *timestamp < commands.last_timestamp {
                 warn!("Invalid movement timestamp");
                 continue;
             }
-            let dt = (next_timestamp - timestamp) as f32;
+
+            let dt = match commands_buffer.get(i + 1) {
+                Some((next_timestamp, _)) if next_timestamp >= timestamp => {
+                    ((next_timestamp - timestamp) as f32 / 1000.0).min(remaining)
+                }
+                Some(_) => {
+                    warn!("Invalid movement timestamp");
+                    continue;
+                }
+                None => remaining,
+            };
+            remaining -= dt;
 
             match command {
-                Walk(walk) => handle_walk(&mut movement, &mut transform, dt, &walk),
-                Run(run) => handle_run(&mut movement, &mut transform, dt, &run),
-                Roll(roll) => handle_roll(&mut movement, &mut transform, dt, &roll),
-                Jump(jump) => handle_jump(&mut movement, dt, &jump),
+                Walk(walk) => handle_walk(&mut movement, &mut transform, dt, walk),
+                Run(run) => handle_run(&mut movement, &mut transform, dt, run),
+                Roll(roll) => handle_roll(&mut movement, &mut transform, dt, roll),
+                Jump(jump) => handle_jump(&mut movement, dt, jump),
             }
+            commands.last_timestamp = *timestamp;
         }
-        commands.last_timestamp = now;
 
         commands_buffer.clear();
     }
@@ -193,12 +204,7 @@ fn sync_movement_states(
         //     continue;
         // }
 
-        let state = MovementState {
-            entity: entity.to_bits(),
-            motion: movement.motion.into(),
-            transform: Some(transform.into()),
-        };
-        sync.states.push(state);
+        sync.states.push(movement_state(entity, movement, transform));
     }
 
     if sync.states.is_empty() {
@@ -213,3 +219,24 @@ fn sync_movement_states(
         _ = session.send_datagram(protocol.clone());
     }
 }
+
+/// Movement states of every entity, e.g. for a client catching up with the world.
+pub fn snapshot(world: &mut World) -> MovementSync {
+    let mut sync = MovementSync::default();
+    sync.timestamp = chrono::Utc::now().timestamp_millis();
+
+    let mut query = world.query::<(Entity, &Movement, &Transform)>();
+    for (entity, movement, transform) in query.iter(world) {
+        sync.states.push(movement_state(entity, movement, transform));
+    }
+
+    sync
+}
+
+fn movement_state(entity: Entity, movement: &Movement, transform: &Transform) -> MovementState {
+    MovementState {
+        entity: entity.to_bits(),
+        motion: movement.motion.into(),
+        transform: Some(transform.into()),
+    }
+}
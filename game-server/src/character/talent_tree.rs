@@ -1,8 +1,7 @@
+use crate::persistence::{self, repositories};
 use bevy_ecs::prelude::*;
 use data::character::TalentTable;
 use data::prelude::*;
-use diesel::prelude::*;
-use diesel_async::RunQueryDsl;
 use std::collections::HashMap;
 use tracing::warn;
 
@@ -17,35 +16,15 @@ pub struct TalentNode {
     pub exp: u32,
 }
 
-#[derive(Debug, Queryable, Selectable)]
-#[diesel(table_name = db::schema::character_talent)]
-#[diesel(check_for_backend(diesel::pg::Pg))]
-struct TalentModel {
-    pub data_id: i32,
-    pub level: i16,
-    pub exp: i32,
-}
-
 impl TalentTree {
-    pub async fn load(
-        conn: &mut db::Connection,
-        character_id: i64,
-    ) -> Result<Self, db::Error> {
+    pub async fn load(character_id: i64) -> Result<Self, persistence::Error> {
         let mut tree = Self::default();
 
-        let mut talents = {
-            use db::schema::character_talent::dsl::*;
-            character_talent
-                .filter(character_id.eq(character_id))
-                .select(TalentModel::as_select())
-                .load(conn)
-                .await?
-        };
+        let mut talents = repositories().talent.list(character_id).await?;
 
         for talent in talents.drain(..) {
             let Some(data) = TalentTable::get(&talent.data_id.into()) else {
-                warn!("Invalid {} record: character_id={}, data_id={}",
-                    std::any::type_name::<db::schema::character_talent::table>(),
+                warn!("Invalid talent record: character_id={}, data_id={}",
                     character_id,
                     talent.data_id,
                 );
@@ -20,6 +20,14 @@ pub struct ApplicationConfig {
     pub data: app::Data,
     pub cheat: app::Cheat,
     pub zone: app::Zone,
+    #[serde(default)]
+    pub skill: app::Skill,
+    #[serde(default)]
+    pub trade: app::Trade,
+    #[serde(default)]
+    pub auction: app::Auction,
+    #[serde(default)]
+    pub mail: app::Mail,
 }
 
 pub mod app {
@@ -39,17 +47,240 @@ pub mod app {
     }
 
     fn tick_interval_milliseconds_default() -> u8 { 50 }
+    fn max_catch_up_ticks_default() -> u8 { 5 }
+    fn report_interval_seconds_default() -> u16 { 60 }
+    fn max_rewind_milliseconds_default() -> u16 { 250 }
+    fn interpolation_delay_milliseconds_default() -> u16 { 100 }
     #[derive(Debug, Deserialize)]
     pub struct Zone {
         #[serde(default = "tick_interval_milliseconds_default")]
         tick_interval_milliseconds: u8,
         #[serde(skip_deserializing)]
         pub tick_interval: Duration,
+
+        /// Maximum ticks to run at once when the zone falls behind. At least 1.
+        #[serde(default = "max_catch_up_ticks_default")]
+        pub max_catch_up_ticks: u8,
+
+        #[serde(default = "report_interval_seconds_default")]
+        report_interval_seconds: u16,
+        #[serde(skip_deserializing)]
+        pub report_interval: Duration,
+
+        /// How far back hits are validated against what clients saw.
+        #[serde(default = "max_rewind_milliseconds_default")]
+        max_rewind_milliseconds: u16,
+        #[serde(skip_deserializing)]
+        pub max_rewind: Duration,
+
+        /// How far behind the server clients render other entities.
+        #[serde(default = "interpolation_delay_milliseconds_default")]
+        interpolation_delay_milliseconds: u16,
+        #[serde(skip_deserializing)]
+        pub interpolation_delay: Duration,
+
+        #[serde(default)]
+        pub record: ZoneRecord,
+
+        #[serde(default)]
+        pub pool: ZonePool,
+    }
+
+    fn zone_record_dir_default() -> PathBuf { PathBuf::from("records") }
+    #[derive(Debug, Deserialize)]
+    pub struct ZoneRecord {
+        #[serde(default)]
+        pub enabled: bool,
+        #[serde(default = "zone_record_dir_default")]
+        pub dir: PathBuf,
+    }
+
+    #[derive(Debug, Default, Deserialize)]
+    pub struct ZonePool {
+        /// Threads running zones. `0` for the available parallelism.
+        #[serde(default)]
+        pub threads: usize,
+        /// Run every zone on its own thread, ignoring `threads` and `placement`.
+        #[serde(default)]
+        pub dedicated: bool,
+        #[serde(default)]
+        pub placement: ZonePlacement,
+        #[serde(default)]
+        pub pins: Vec<ZonePin>,
+    }
+
+    #[derive(Debug, Default, Deserialize, Clone, Copy)]
+    #[serde(rename_all = "snake_case")]
+    pub enum ZonePlacement {
+        #[default]
+        RoundRobin,
+        LeastLoaded,
+    }
+
+    /// A zone always placed on the given thread.
+    #[derive(Debug, Deserialize)]
+    pub struct ZonePin {
+        pub zone: util::id::Id,
+        pub thread: usize,
+    }
+
+    impl Default for ZoneRecord {
+        fn default() -> Self {
+            Self {
+                enabled: false,
+                dir: zone_record_dir_default(),
+            }
+        }
     }
 
     impl Zone {
         pub fn init(&mut self) {
             self.tick_interval = Duration::from_millis(self.tick_interval_milliseconds as u64);
+            // A zone not allowed a single tick would never run.
+            self.max_catch_up_ticks = self.max_catch_up_ticks.max(1);
+            self.report_interval = Duration::from_secs(self.report_interval_seconds as u64);
+            self.max_rewind = Duration::from_millis(self.max_rewind_milliseconds as u64);
+            self.interpolation_delay = Duration::from_millis(self.interpolation_delay_milliseconds as u64);
+        }
+    }
+
+    fn skill_range_default() -> f32 { 3.0 }
+    /// The range every skill is validated with, until skills have data.
+    #[derive(Debug, Deserialize)]
+    pub struct Skill {
+        #[serde(default = "skill_range_default")]
+        pub range: f32,
+    }
+
+    impl Default for Skill {
+        fn default() -> Self {
+            Self {
+                range: skill_range_default(),
+            }
+        }
+    }
+
+    fn trade_range_default() -> f32 { 5.0 }
+    fn trade_request_timeout_seconds_default() -> u16 { 30 }
+    #[derive(Debug, Deserialize)]
+    pub struct Trade {
+        /// Maximum distance between the characters, kept during the whole trade.
+        #[serde(default = "trade_range_default")]
+        pub range: f32,
+
+        #[serde(default = "trade_request_timeout_seconds_default")]
+        request_timeout_seconds: u16,
+        #[serde(skip_deserializing)]
+        pub request_timeout: Duration,
+    }
+
+    impl Default for Trade {
+        fn default() -> Self {
+            Self {
+                range: trade_range_default(),
+                request_timeout_seconds: trade_request_timeout_seconds_default(),
+                request_timeout: Duration::ZERO,
+            }
+        }
+    }
+
+    impl Trade {
+        pub fn init(&mut self) {
+            self.request_timeout = Duration::from_secs(self.request_timeout_seconds as u64);
+        }
+    }
+
+    fn auction_max_duration_hours_default() -> u16 { 48 }
+    fn auction_max_price_default() -> i64 { 1_000_000_000 }
+    fn auction_search_limit_default() -> u16 { 50 }
+    fn auction_expire_interval_seconds_default() -> u16 { 60 }
+    fn auction_expire_batch_default() -> u16 { 100 }
+    #[derive(Debug, Deserialize)]
+    pub struct Auction {
+        #[serde(default = "auction_max_duration_hours_default")]
+        pub max_duration_hours: u16,
+        #[serde(default = "auction_max_price_default")]
+        pub max_price: i64,
+        /// Most listings returned by a search.
+        #[serde(default = "auction_search_limit_default")]
+        pub search_limit: u16,
+
+        #[serde(default = "auction_expire_interval_seconds_default")]
+        expire_interval_seconds: u16,
+        #[serde(skip_deserializing)]
+        pub expire_interval: Duration,
+        /// Most expired listings returned to their sellers at once.
+        #[serde(default = "auction_expire_batch_default")]
+        pub expire_batch: u16,
+    }
+
+    impl Default for Auction {
+        fn default() -> Self {
+            Self {
+                max_duration_hours: auction_max_duration_hours_default(),
+                max_price: auction_max_price_default(),
+                search_limit: auction_search_limit_default(),
+                expire_interval_seconds: auction_expire_interval_seconds_default(),
+                expire_interval: Duration::ZERO,
+                expire_batch: auction_expire_batch_default(),
+            }
+        }
+    }
+
+    impl Auction {
+        pub fn init(&mut self) {
+            self.expire_interval = Duration::from_secs(self.expire_interval_seconds as u64);
+        }
+    }
+
+    fn mail_max_attachments_default() -> u8 { 8 }
+    fn mail_max_subject_length_default() -> u8 { 64 }
+    fn mail_max_body_length_default() -> u16 { 1000 }
+    fn mail_max_mails_default() -> u16 { 100 }
+    fn mail_expire_interval_seconds_default() -> u16 { 60 }
+    fn mail_expire_batch_default() -> u16 { 100 }
+    #[derive(Debug, Deserialize)]
+    pub struct Mail {
+        /// Most items attached to a mail, gold aside.
+        #[serde(default = "mail_max_attachments_default")]
+        pub max_attachments: u8,
+        /// In characters.
+        #[serde(default = "mail_max_subject_length_default")]
+        pub max_subject_length: u8,
+        /// In characters.
+        #[serde(default = "mail_max_body_length_default")]
+        pub max_body_length: u16,
+        /// Most mail in a mailbox. Characters can't mail a full one, while system mail is always
+        /// delivered. Only the newest are listed.
+        #[serde(default = "mail_max_mails_default")]
+        pub max_mails: u16,
+
+        #[serde(default = "mail_expire_interval_seconds_default")]
+        expire_interval_seconds: u16,
+        #[serde(skip_deserializing)]
+        pub expire_interval: Duration,
+        /// Most expired mail deleted at once.
+        #[serde(default = "mail_expire_batch_default")]
+        pub expire_batch: u16,
+    }
+
+    impl Default for Mail {
+        fn default() -> Self {
+            Self {
+                max_attachments: mail_max_attachments_default(),
+                max_subject_length: mail_max_subject_length_default(),
+                max_body_length: mail_max_body_length_default(),
+                max_mails: mail_max_mails_default(),
+                expire_interval_seconds: mail_expire_interval_seconds_default(),
+                expire_interval: Duration::ZERO,
+                expire_batch: mail_expire_batch_default(),
+            }
+        }
+    }
+
+    impl Mail {
+        pub fn init(&mut self) {
+            self.expire_interval = Duration::from_secs(self.expire_interval_seconds as u64);
         }
     }
 }
@@ -77,6 +308,34 @@ pub mod auth {
         timeout_seconds: u8,
         #[serde(skip_deserializing)]
         pub timeout: Duration,
+
+        #[serde(default)]
+        pub duplicate: DuplicateLogin,
+        #[serde(default)]
+        pub version_check: VersionCheck,
+    }
+
+    /// How the protocol version of a client is checked against the server.
+    #[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+    #[serde(rename_all = "snake_case")]
+    pub enum VersionCheck {
+        /// Same schema hash, or the same major version and a minor version not newer.
+        #[default]
+        Compatible,
+        /// Same schema hash only.
+        Strict,
+        Disabled,
+    }
+
+    /// What to do when an account already in the game logs in again.
+    #[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+    #[serde(rename_all = "snake_case")]
+    pub enum DuplicateLogin {
+        /// Kick the existing session, or take it over if it is the same character.
+        #[default]
+        KickExisting,
+        /// Refuse the new login while the existing session is connected.
+        RefuseNew,
     }
 
     impl Login {
@@ -88,19 +347,51 @@ pub mod auth {
 
 #[derive(Debug, Deserialize)]
 pub struct DatabaseConfig {
+    #[serde(default)]
+    pub backend: db::Backend,
+    /// Memory backend only.
+    #[serde(default)]
+    pub create_missing: bool,
+    /// Apply pending migrations on startup, instead of refusing to start.
+    #[serde(default)]
+    pub migrate: bool,
+
+    // Unused by the memory backend.
+    #[serde(default)]
     pub host: String,
+    #[serde(default)]
     pub port: u16,
+    #[serde(default)]
     pub user: String,
+    #[serde(default)]
     pub name: String,
     #[serde(skip_deserializing)]
     pub password: String,
+    #[serde(default)]
     password_file: PathBuf,
 }
 
+pub mod db {
+    use serde::Deserialize;
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Backend {
+        #[default]
+        Postgres,
+        /// Lost on exit. Clusters need Postgres.
+        Memory,
+    }
+}
+
+fn host_default() -> String { "127.0.0.1".to_string() }
 #[derive(Debug, Deserialize)]
 pub struct NetworkConfig {
     #[serde(alias = "game_server_node_id")]
     pub node_id: u16,
+    /// Host advertised to clients and other nodes.
+    #[serde(alias = "game_server_host", default = "host_default")]
+    pub host: String,
     #[serde(alias = "game_server_port")]
     pub port: u16,
     #[serde(alias = "game_server_control_port")]
@@ -108,15 +399,273 @@ pub struct NetworkConfig {
     pub application_protocol: String,
 
     pub ingress: net::Ingress,
+    #[serde(default)]
+    pub egress: net::Egress,
+    #[serde(default)]
+    pub compression: net::Compression,
+    #[serde(default)]
+    pub session: net::Session,
+    #[serde(default)]
+    pub cluster: net::Cluster,
 }
 
 pub mod net {
-    use serde::Deserialize;
+    use serde::{Deserialize, Deserializer};
+    use std::time::Duration;
+    use util::id::Id;
 
+    fn ingress_queue_capacity_default() -> usize { 256 }
+    fn ingress_max_protocol_length_default() -> u32 { 64 * 1024 }
     #[derive(Debug, Deserialize)]
     pub struct Ingress {
         pub protocols_rate_limit: Option<util::rate_limiter::Params>,
         pub bytes_rate_limit: Option<util::rate_limiter::Params>,
+        /// Protocols waiting for the zone tick. The session is disconnected when exceeded.
+        #[serde(default = "ingress_queue_capacity_default")]
+        pub queue_capacity: usize,
+        /// Longest protocol body accepted from clients. Longer ones disconnect the session.
+        #[serde(default = "ingress_max_protocol_length_default")]
+        pub max_protocol_length: u32,
+        #[serde(default)]
+        pub abuse: Abuse,
+    }
+
+    impl Ingress {
+        pub fn init(&mut self) {
+            self.abuse.init();
+        }
+    }
+
+    fn abuse_decay_per_second_default() -> f32 { 1.0 }
+    fn abuse_warn_score_default() -> f32 { 5.0 }
+    fn abuse_throttle_score_default() -> f32 { 10.0 }
+    fn abuse_kick_score_default() -> f32 { 20.0 }
+    fn abuse_throttle_milliseconds_default() -> u16 { 200 }
+    fn abuse_penalty_default() -> f32 { 1.0 }
+    /// Graduated response to rate limit violations, by the abuse score of the session.
+    /// Below `warn_score`, violating protocols are only dropped.
+    #[derive(Debug, Deserialize)]
+    pub struct Abuse {
+        #[serde(default = "abuse_decay_per_second_default")]
+        pub decay_per_second: f32,
+        #[serde(default = "abuse_warn_score_default")]
+        pub warn_score: f32,
+        /// Reading from the session pauses for `throttle_milliseconds` on each violation.
+        #[serde(default = "abuse_throttle_score_default")]
+        pub throttle_score: f32,
+        #[serde(default = "abuse_kick_score_default")]
+        pub kick_score: f32,
+
+        #[serde(default = "abuse_throttle_milliseconds_default")]
+        throttle_milliseconds: u16,
+        #[serde(skip_deserializing)]
+        pub throttle: Duration,
+
+        /// Penalty of violating `protocols_rate_limit` or `bytes_rate_limit`.
+        /// Protocol limits declare their own.
+        #[serde(default = "abuse_penalty_default")]
+        pub penalty: f32,
+    }
+
+    impl Abuse {
+        pub fn init(&mut self) {
+            self.throttle = Duration::from_millis(self.throttle_milliseconds as u64);
+        }
+    }
+
+    impl Default for Abuse {
+        fn default() -> Self {
+            let mut abuse = Self {
+                decay_per_second: abuse_decay_per_second_default(),
+                warn_score: abuse_warn_score_default(),
+                throttle_score: abuse_throttle_score_default(),
+                kick_score: abuse_kick_score_default(),
+                throttle_milliseconds: abuse_throttle_milliseconds_default(),
+                throttle: Duration::ZERO,
+                penalty: abuse_penalty_default(),
+            };
+            abuse.init();
+            abuse
+        }
+    }
+
+    fn egress_max_protocols_default() -> usize { 1024 }
+    fn egress_max_bytes_default() -> usize { 1024 * 1024 }
+    #[derive(Debug, Deserialize)]
+    pub struct Egress {
+        /// Protocols waiting to be written to the stream.
+        #[serde(default = "egress_max_protocols_default")]
+        pub max_protocols: usize,
+        #[serde(default = "egress_max_bytes_default")]
+        pub max_bytes: usize,
+        #[serde(default)]
+        pub overflow: EgressOverflow,
+    }
+
+    /// What to do when a critical or reliable protocol does not fit in the egress queue.
+    #[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+    #[serde(rename_all = "snake_case")]
+    pub enum EgressOverflow {
+        #[default]
+        Disconnect,
+        /// Drop the protocol and keep the session. Only for trusted environments.
+        Drop,
+    }
+
+    impl Default for Egress {
+        fn default() -> Self {
+            Self {
+                max_protocols: egress_max_protocols_default(),
+                max_bytes: egress_max_bytes_default(),
+                overflow: EgressOverflow::default(),
+            }
+        }
+    }
+
+    fn compression_enabled_default() -> bool { true }
+    fn compression_threshold_default() -> usize { 1024 }
+    /// LZ4 compression of reliable protocols, used with clients supporting it.
+    #[derive(Debug, Deserialize)]
+    pub struct Compression {
+        #[serde(default = "compression_enabled_default")]
+        pub enabled: bool,
+        /// Bodies shorter than this are sent as is.
+        #[serde(default = "compression_threshold_default")]
+        pub threshold: usize,
+    }
+
+    impl Default for Compression {
+        fn default() -> Self {
+            Self {
+                enabled: compression_enabled_default(),
+                threshold: compression_threshold_default(),
+            }
+        }
+    }
+
+    fn reconnect_grace_seconds_default() -> u16 { 60 }
+    fn ping_interval_seconds_default() -> u16 { 5 }
+    fn max_missed_pongs_default() -> u8 { 3 }
+    #[derive(Debug, Deserialize)]
+    pub struct Session {
+        /// How long a disconnected player stays in the world, waiting to reconnect.
+        /// `0` to despawn at once.
+        #[serde(default = "reconnect_grace_seconds_default")]
+        reconnect_grace_seconds: u16,
+        #[serde(skip_deserializing)]
+        pub reconnect_grace: Duration,
+
+        #[serde(default = "ping_interval_seconds_default")]
+        ping_interval_seconds: u16,
+        #[serde(skip_deserializing)]
+        pub ping_interval: Duration,
+
+        /// Pings in a row left unanswered before the session is kicked. `0` to never kick.
+        #[serde(default = "max_missed_pongs_default")]
+        pub max_missed_pongs: u8,
+    }
+
+    impl Session {
+        pub fn init(&mut self) {
+            self.reconnect_grace = Duration::from_secs(self.reconnect_grace_seconds as u64);
+            self.ping_interval = Duration::from_secs(self.ping_interval_seconds as u64);
+        }
+    }
+
+    impl Default for Session {
+        fn default() -> Self {
+            let mut session = Self {
+                reconnect_grace_seconds: reconnect_grace_seconds_default(),
+                reconnect_grace: Duration::ZERO,
+                ping_interval_seconds: ping_interval_seconds_default(),
+                ping_interval: Duration::ZERO,
+                max_missed_pongs: max_missed_pongs_default(),
+            };
+            session.init();
+            session
+        }
+    }
+
+    fn cluster_port_default() -> u16 { 6500 }
+    fn heartbeat_interval_seconds_default() -> u16 { 5 }
+    fn node_timeout_seconds_default() -> u16 { 20 }
+    fn transfer_timeout_seconds_default() -> u16 { 30 }
+    #[derive(Debug, Deserialize)]
+    pub struct Cluster {
+        #[serde(default)]
+        pub enabled: bool,
+        #[serde(default = "cluster_port_default")]
+        pub port: u16,
+        /// Zones this node claims. Either a list or a comma separated string.
+        #[serde(default, deserialize_with = "deserialize_ids")]
+        pub zones: Vec<Id>,
+
+        #[serde(default = "heartbeat_interval_seconds_default")]
+        heartbeat_interval_seconds: u16,
+        #[serde(skip_deserializing)]
+        pub heartbeat_interval: Duration,
+
+        /// A node without heartbeat for this long loses its zones.
+        #[serde(default = "node_timeout_seconds_default")]
+        node_timeout_seconds: u16,
+        #[serde(skip_deserializing)]
+        pub node_timeout: Duration,
+
+        /// How long a player handed over by another node waits for the client to reconnect.
+        #[serde(default = "transfer_timeout_seconds_default")]
+        transfer_timeout_seconds: u16,
+        #[serde(skip_deserializing)]
+        pub transfer_timeout: Duration,
+    }
+
+    impl Cluster {
+        pub fn init(&mut self) {
+            self.heartbeat_interval = Duration::from_secs(self.heartbeat_interval_seconds as u64);
+            self.node_timeout = Duration::from_secs(self.node_timeout_seconds as u64);
+            self.transfer_timeout = Duration::from_secs(self.transfer_timeout_seconds as u64);
+        }
+    }
+
+    impl Default for Cluster {
+        fn default() -> Self {
+            let mut cluster = Self {
+                enabled: false,
+                port: cluster_port_default(),
+                zones: Vec::new(),
+                heartbeat_interval_seconds: heartbeat_interval_seconds_default(),
+                heartbeat_interval: Duration::ZERO,
+                node_timeout_seconds: node_timeout_seconds_default(),
+                node_timeout: Duration::ZERO,
+                transfer_timeout_seconds: transfer_timeout_seconds_default(),
+                transfer_timeout: Duration::ZERO,
+            };
+            cluster.init();
+            cluster
+        }
+    }
+
+    fn deserialize_ids<'de, D>(deserializer: D) -> Result<Vec<Id>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Ids {
+            List(Vec<Id>),
+            One(Id),
+            Text(String),
+        }
+
+        Ok(match Ids::deserialize(deserializer)? {
+            Ids::List(ids) => ids,
+            Ids::One(id) => vec![id],
+            Ids::Text(text) => text
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(|id| id.parse().map_err(serde::de::Error::custom))
+                .collect::<Result<_, _>>()?,
+        })
     }
 }
 
@@ -131,6 +680,11 @@ pub fn init(local_env: &Option<PathBuf>) -> Result<(), Box<dyn std::error::Error
     let db = load_database_config()?;
     let net = load_network_config()?;
 
+    // The cluster registry is in the database.
+    if net.cluster.enabled && db.backend == db::Backend::Memory {
+        return Err("Cluster needs the postgres backend".into());
+    }
+
     let config = Config {
         app,
         auth,
@@ -151,6 +705,9 @@ fn load_application_config() -> Result<ApplicationConfig, Box<dyn std::error::Er
         .try_deserialize()?;
 
     config.zone.init();
+    config.trade.init();
+    config.auction.init();
+    config.mail.init();
 
     Ok(config)
 }
@@ -173,18 +730,28 @@ fn load_database_config() -> Result<DatabaseConfig, Box<dyn std::error::Error>>
         .add_source(config::Environment::with_prefix("SPIRE_DB"))
         .build()?
         .try_deserialize()?;
-    config.password = util::io::read_file(&config.password_file)?;
+
+    if config.backend == db::Backend::Postgres {
+        config.password = util::io::read_file(&config.password_file)?;
+    }
 
     Ok(config)
 }
 
 fn load_network_config() -> Result<NetworkConfig, Box<dyn std::error::Error>> {
-    let config: NetworkConfig = config::Config::builder()
+    // Nested keys are separated by `__`, e.g. `SPIRE_CLUSTER__ENABLED`.
+    let mut config: NetworkConfig = config::Config::builder()
         .add_source(config::File::with_name("config"))
-        .add_source(config::Environment::with_prefix("SPIRE"))
+        .add_source(config::Environment::with_prefix("SPIRE")
+            .prefix_separator("_")
+            .separator("__"))
         .build()?
         .try_deserialize()?;
 
+    config.ingress.init();
+    config.session.init();
+    config.cluster.init();
+
     Ok(config)
 }
 
@@ -1,10 +1,11 @@
 use crate::handler::ProtocolGlobalHandler;
-use crate::net::session::Session;
-use protocol::game::net::Ping;
-use tracing::info;
+use crate::net::session::{Priority, Session};
+use protocol::game::net::{Ping, Pong};
 
 impl ProtocolGlobalHandler for Ping {
     fn handle(self, session: Session) {
-        info!("{} pinged: {}", session, self.timestamp);
+        session.send_with(&Pong {
+            timestamp: self.timestamp,
+        }, Priority::Critical);
     }
 }
@@ -1,6 +1,7 @@
 use crate::character::Characters;
+use crate::character::status::movement;
 use crate::handler::ProtocolLocalHandler;
-use crate::net::session::Session;
+use crate::net::session::{Priority, Session};
 use crate::net::zone::player_transfer::PlayerTransferProcess;
 use bevy_ecs::prelude::*;
 use protocol::game::net::ZoneTransferReady;
@@ -18,7 +19,8 @@ impl ProtocolLocalHandler for ZoneTransferReady {
         };
         
         world.resource_mut::<Characters>().map.insert(session.entry.character_id, entity);
-        
-        todo!("Send result");
+
+        // Catch the client up with the world it enters.
+        session.send_with(&movement::snapshot(world), Priority::Cosmetic);
     }
-}
\ No newline at end of file
+}
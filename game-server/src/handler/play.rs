@@ -1,3 +1,7 @@
+mod auction_buy;
+mod auction_cancel;
+mod auction_list;
+mod auction_search;
 mod movement_command;
 mod item_pickup;
 mod skill_use;
@@ -0,0 +1,45 @@
+use crate::handler::ProtocolLocalHandler;
+use crate::net::session::Session;
+use crate::persistence::{self, repositories};
+use crate::social::mail;
+use crate::task::Task;
+use bevy_ecs::prelude::*;
+use protocol::game::play::{auction_buy_result, AuctionBuy, AuctionBuyResult};
+use tracing::error;
+
+impl ProtocolLocalHandler for AuctionBuy {
+    fn handle(self, world: &mut World, entity: Entity, session: Session) {
+        use auction_buy_result::Error;
+
+        let listing_id = self.listing_id;
+        let buyer_id = session.entry.character_id;
+
+        let future = async move {
+            match repositories().auction.buy(listing_id, buyer_id).await {
+                Ok((listing, proceeds)) => {
+                    mail::notify(&proceeds);
+                    Ok(Ok(listing))
+                }
+                Err(persistence::Error::ListingNotFound(_)) => Ok(Err(Error::NotFound)),
+                Err(persistence::Error::InsufficientGold(_)) => Ok(Err(Error::InsufficientGold)),
+                Err(e) => Err(e),
+            }
+        };
+        let task = Task::serial_with_return(future, move |result, _, _| {
+            let mut response = AuctionBuyResult::default();
+
+            match result {
+                Ok(Ok(listing)) => response.listing = Some((&listing).into()),
+                Ok(Err(e)) => response.error = Some(e.into()),
+                Err(e) => {
+                    error!("Failed to buy listing {}: {}", listing_id, e);
+                    response.error = Some(Error::Internal.into());
+                }
+            }
+
+            session.send(&response);
+        });
+
+        task.dispatch(world, entity);
+    }
+}
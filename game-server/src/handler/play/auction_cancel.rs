@@ -0,0 +1,43 @@
+use crate::handler::ProtocolLocalHandler;
+use crate::net::session::Session;
+use crate::persistence::{self, repositories};
+use crate::task::Task;
+use bevy_ecs::prelude::*;
+use protocol::game::play::{auction_cancel_result, AuctionCancel, AuctionCancelResult};
+use tracing::error;
+
+impl ProtocolLocalHandler for AuctionCancel {
+    fn handle(self, world: &mut World, entity: Entity, session: Session) {
+        use auction_cancel_result::Error;
+
+        let listing_id = self.listing_id;
+        let seller_id = session.entry.character_id;
+
+        let future = async move {
+            match repositories().auction.cancel(listing_id, seller_id).await {
+                Ok(_) => Ok(Ok(())),
+                Err(persistence::Error::ListingNotFound(_)) => Ok(Err(Error::NotFound)),
+                Err(e) => Err(e),
+            }
+        };
+        let task = Task::serial_with_return(future, move |result, _, _| {
+            let mut response = AuctionCancelResult {
+                listing_id,
+                ..Default::default()
+            };
+
+            match result {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => response.error = Some(e.into()),
+                Err(e) => {
+                    error!("Failed to cancel listing {}: {}", listing_id, e);
+                    response.error = Some(Error::Internal.into());
+                }
+            }
+
+            session.send(&response);
+        });
+
+        task.dispatch(world, entity);
+    }
+}
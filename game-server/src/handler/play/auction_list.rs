@@ -0,0 +1,62 @@
+use crate::config;
+use crate::handler::ProtocolLocalHandler;
+use crate::market::auction;
+use crate::net::session::Session;
+use crate::task::Task;
+use bevy_ecs::prelude::*;
+use protocol::game::play::{auction_list_result, AuctionList, AuctionListResult};
+use std::time::Duration;
+use tracing::error;
+
+impl ProtocolLocalHandler for AuctionList {
+    fn handle(self, world: &mut World, entity: Entity, session: Session) {
+        use auction_list_result::Error;
+
+        let config = &config!(app).auction;
+        if self.count <= 0 {
+            session.send(&AuctionListResult {
+                error: Some(Error::InvalidItem.into()),
+                ..Default::default()
+            });
+            return;
+        }
+        if self.price <= 0 || self.price > config.max_price {
+            session.send(&AuctionListResult {
+                error: Some(Error::InvalidPrice.into()),
+                ..Default::default()
+            });
+            return;
+        }
+        if self.duration_hours == 0 || self.duration_hours > config.max_duration_hours as u32 {
+            session.send(&AuctionListResult {
+                error: Some(Error::InvalidDuration.into()),
+                ..Default::default()
+            });
+            return;
+        }
+
+        let future = auction::list(
+            session.entry.character_id,
+            self.item_id,
+            self.count,
+            self.price,
+            Duration::from_secs(self.duration_hours as u64 * 3600),
+        );
+        let task = Task::serial_with_return(future, move |result, _, _| {
+            let mut response = AuctionListResult::default();
+
+            match result {
+                Ok(Ok(listing)) => response.listing = Some((&listing).into()),
+                Ok(Err(e)) => response.error = Some(e.into()),
+                Err(e) => {
+                    error!("Failed to list item: {}", e);
+                    response.error = Some(Error::Internal.into());
+                }
+            }
+
+            session.send(&response);
+        });
+
+        task.dispatch(world, entity);
+    }
+}
@@ -0,0 +1,37 @@
+use crate::handler::ProtocolLocalHandler;
+use crate::net::session::Session;
+use crate::market::auction;
+use crate::persistence::repositories;
+use crate::task::Task;
+use bevy_ecs::prelude::*;
+use protocol::game::play::{auction_search_result, AuctionSearch, AuctionSearchResult};
+use tracing::error;
+
+impl ProtocolLocalHandler for AuctionSearch {
+    fn handle(self, world: &mut World, entity: Entity, session: Session) {
+        let Some(filter) = auction::filter(&self.filter.unwrap_or_default()) else {
+            session.send(&AuctionSearchResult {
+                error: Some(auction_search_result::Error::InvalidFilter.into()),
+                ..Default::default()
+            });
+            return;
+        };
+
+        let future = repositories().auction.search(filter);
+        let task = Task::serial_with_return(future, move |result, _, _| {
+            let mut response = AuctionSearchResult::default();
+
+            match result {
+                Ok(listings) => response.listings = listings.iter().map(Into::into).collect(),
+                Err(e) => {
+                    error!("Failed to search listings: {}", e);
+                    response.error = Some(auction_search_result::Error::Internal.into());
+                }
+            }
+
+            session.send(&response);
+        });
+
+        task.dispatch(world, entity);
+    }
+}
@@ -1,11 +1,24 @@
 use crate::character::status::movement::MovementCommands;
 use crate::handler::ProtocolLocalHandler;
 use crate::net::session::Session;
+use crate::world::rewind::ClientClock;
+use crate::world::time::Time;
 use bevy_ecs::prelude::*;
 use protocol::game::play::MovementCommand;
 
 impl ProtocolLocalHandler for MovementCommand {
-    fn handle(self, world: &mut World, entity: Entity, _: Session) {
+    fn handle(self, world: &mut World, entity: Entity, session: Session) {
+        let server_millis = world.resource::<Time>().elapsed().as_millis() as i64;
+        let one_way = session.rtt().estimate() / 2;
+        match world.get_mut::<ClientClock>(entity) {
+            Some(mut clock) => clock.update(self.timestamp, server_millis, one_way),
+            None => {
+                let mut clock = ClientClock::default();
+                clock.update(self.timestamp, server_millis, one_way);
+                world.entity_mut(entity).insert(clock);
+            }
+        }
+
         if let Some(mut movement_commands) = world.get_mut::<MovementCommands>(entity) {
             movement_commands.queue.push(self);
         };
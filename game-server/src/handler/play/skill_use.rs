@@ -1,11 +1,21 @@
+use crate::character::skill_set::{SkillCommand, SkillCommands};
 use crate::handler::ProtocolLocalHandler;
 use crate::net::session::Session;
+use crate::world::rewind;
 use bevy_ecs::entity::Entity;
 use bevy_ecs::prelude::World;
 use protocol::game::play::SkillUse;
 
 impl ProtocolLocalHandler for SkillUse {
     fn handle(self, world: &mut World, entity: Entity, session: Session) {
-        todo!()
+        let view_tick = rewind::view_tick(world, entity, &session, Some(self.timestamp));
+        let command = SkillCommand { skill_use: self, view_tick };
+
+        match world.get_mut::<SkillCommands>(entity) {
+            Some(mut skill_commands) => skill_commands.queue.push(command),
+            None => {
+                world.entity_mut(entity).insert(SkillCommands { queue: vec![command] });
+            }
+        }
     }
-}
\ No newline at end of file
+}
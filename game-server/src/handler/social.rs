@@ -1,3 +1,13 @@
+mod mail_claim;
+mod mail_delete;
+mod mail_list;
+mod mail_read;
+mod mail_send;
 mod party_create;
 mod party_invite;
-
+mod trade_accept;
+mod trade_cancel;
+mod trade_confirm;
+mod trade_lock;
+mod trade_offer;
+mod trade_request;
@@ -0,0 +1,40 @@
+use crate::handler::ProtocolLocalHandler;
+use crate::net::session::Session;
+use crate::persistence::{self, repositories};
+use crate::task::Task;
+use bevy_ecs::prelude::*;
+use protocol::game::social::{mail_claim_result, MailClaim, MailClaimResult};
+use tracing::error;
+
+impl ProtocolLocalHandler for MailClaim {
+    fn handle(self, world: &mut World, entity: Entity, session: Session) {
+        use mail_claim_result::Error;
+
+        let mail_id = self.mail_id;
+        let recipient_id = session.entry.character_id;
+
+        let future = async move {
+            match repositories().mail.claim(mail_id, recipient_id).await {
+                Ok(mail) => Ok(Ok(mail)),
+                Err(persistence::Error::MailNotFound(_)) => Ok(Err(Error::NotFound)),
+                Err(e) => Err(e),
+            }
+        };
+        let task = Task::serial_with_return(future, move |result, _, _| {
+            let mut response = MailClaimResult::default();
+
+            match result {
+                Ok(Ok(mail)) => response.mail = Some((&mail).into()),
+                Ok(Err(e)) => response.error = Some(e.into()),
+                Err(e) => {
+                    error!("Failed to claim mail {}: {}", mail_id, e);
+                    response.error = Some(Error::Internal.into());
+                }
+            }
+
+            session.send(&response);
+        });
+
+        task.dispatch(world, entity);
+    }
+}
@@ -0,0 +1,44 @@
+use crate::handler::ProtocolLocalHandler;
+use crate::net::session::Session;
+use crate::persistence::{self, repositories};
+use crate::task::Task;
+use bevy_ecs::prelude::*;
+use protocol::game::social::{mail_delete_result, MailDelete, MailDeleteResult};
+use tracing::error;
+
+impl ProtocolLocalHandler for MailDelete {
+    fn handle(self, world: &mut World, entity: Entity, session: Session) {
+        use mail_delete_result::Error;
+
+        let mail_id = self.mail_id;
+        let recipient_id = session.entry.character_id;
+
+        let future = async move {
+            match repositories().mail.delete(mail_id, recipient_id).await {
+                Ok(()) => Ok(Ok(())),
+                Err(persistence::Error::MailNotFound(_)) => Ok(Err(Error::NotFound)),
+                Err(persistence::Error::MailUnclaimed(_)) => Ok(Err(Error::Unclaimed)),
+                Err(e) => Err(e),
+            }
+        };
+        let task = Task::serial_with_return(future, move |result, _, _| {
+            let mut response = MailDeleteResult {
+                mail_id,
+                ..Default::default()
+            };
+
+            match result {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => response.error = Some(e.into()),
+                Err(e) => {
+                    error!("Failed to delete mail {}: {}", mail_id, e);
+                    response.error = Some(Error::Internal.into());
+                }
+            }
+
+            session.send(&response);
+        });
+
+        task.dispatch(world, entity);
+    }
+}
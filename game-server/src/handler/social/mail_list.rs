@@ -0,0 +1,33 @@
+use crate::config;
+use crate::handler::ProtocolLocalHandler;
+use crate::net::session::Session;
+use crate::persistence::repositories;
+use crate::task::Task;
+use bevy_ecs::prelude::*;
+use protocol::game::social::{mail_list_result, MailList, MailListResult};
+use tracing::error;
+
+impl ProtocolLocalHandler for MailList {
+    fn handle(self, world: &mut World, entity: Entity, session: Session) {
+        let recipient_id = session.entry.character_id;
+
+        let limit = config!(app).mail.max_mails as i64;
+
+        let future = async move { repositories().mail.list(recipient_id, limit).await };
+        let task = Task::serial_with_return(future, move |result, _, _| {
+            let mut response = MailListResult::default();
+
+            match result {
+                Ok(mails) => response.mails = mails.iter().map(Into::into).collect(),
+                Err(e) => {
+                    error!("Failed to list mail of {}: {}", recipient_id, e);
+                    response.error = Some(mail_list_result::Error::Internal.into());
+                }
+            }
+
+            session.send(&response);
+        });
+
+        task.dispatch(world, entity);
+    }
+}
@@ -0,0 +1,43 @@
+use crate::handler::ProtocolLocalHandler;
+use crate::net::session::Session;
+use crate::persistence::{self, repositories};
+use crate::task::Task;
+use bevy_ecs::prelude::*;
+use protocol::game::social::{mail_read_result, MailRead, MailReadResult};
+use tracing::error;
+
+impl ProtocolLocalHandler for MailRead {
+    fn handle(self, world: &mut World, entity: Entity, session: Session) {
+        use mail_read_result::Error;
+
+        let mail_id = self.mail_id;
+        let recipient_id = session.entry.character_id;
+
+        let future = async move {
+            match repositories().mail.read(mail_id, recipient_id).await {
+                Ok(()) => Ok(Ok(())),
+                Err(persistence::Error::MailNotFound(_)) => Ok(Err(Error::NotFound)),
+                Err(e) => Err(e),
+            }
+        };
+        let task = Task::serial_with_return(future, move |result, _, _| {
+            let mut response = MailReadResult {
+                mail_id,
+                ..Default::default()
+            };
+
+            match result {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => response.error = Some(e.into()),
+                Err(e) => {
+                    error!("Failed to read mail {}: {}", mail_id, e);
+                    response.error = Some(Error::Internal.into());
+                }
+            }
+
+            session.send(&response);
+        });
+
+        task.dispatch(world, entity);
+    }
+}
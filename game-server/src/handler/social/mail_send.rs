@@ -0,0 +1,63 @@
+use crate::character::Character;
+use crate::config;
+use crate::handler::ProtocolLocalHandler;
+use crate::net::session::Session;
+use crate::social::mail;
+use crate::task::Task;
+use bevy_ecs::prelude::*;
+use protocol::game::social::{mail_send_result, MailSend, MailSendResult};
+use tracing::error;
+
+impl ProtocolLocalHandler for MailSend {
+    fn handle(self, world: &mut World, entity: Entity, session: Session) {
+        use mail_send_result::Error;
+
+        let config = &config!(app).mail;
+        let error = if self.subject.chars().count() > config.max_subject_length as usize
+            || self.body.chars().count() > config.max_body_length as usize
+        {
+            Some(Error::InvalidText)
+        } else if self.gold < 0 || self.items.len() > config.max_attachments as usize {
+            Some(Error::InvalidItem)
+        } else {
+            None
+        };
+        if let Some(error) = error {
+            session.send(&MailSendResult {
+                error: Some(error.into()),
+                ..Default::default()
+            });
+            return;
+        }
+
+        let Some(character) = world.get::<Character>(entity) else {
+            return;
+        };
+
+        let future = mail::send(
+            session.entry.character_id,
+            character.name.clone(),
+            self.recipient_id,
+            self.subject,
+            self.body,
+            self.gold,
+            self.items.iter().map(|item| (item.item_id, item.count)).collect(),
+        );
+        let task = Task::serial_with_return(future, move |result, _, _| {
+            let mut response = MailSendResult::default();
+
+            match result {
+                Ok(Ok(mail)) => response.mail_id = mail.id,
+                Ok(Err(e)) => response.error = Some(e.into()),
+                Err(e) => {
+                    error!("Failed to send mail: {}", e);
+                    response.error = Some(Error::Internal.into());
+                }
+            }
+
+            session.send(&response);
+        });
+
+        task.dispatch(world, entity);
+    }
+}
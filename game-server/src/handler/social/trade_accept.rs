@@ -0,0 +1,26 @@
+use crate::handler::ProtocolLocalHandler;
+use crate::net::session::Session;
+use crate::social::trade::{self, Error, State};
+use bevy_ecs::prelude::*;
+use protocol::game::social::TradeAccept;
+
+impl ProtocolLocalHandler for TradeAccept {
+    fn handle(self, world: &mut World, entity: Entity, session: Session) {
+        let result = trade::with_trade(world, entity, self.trade_id, |world, trade, index| {
+            // Only the requested player accepts.
+            if index != 1 || !matches!(trade.state, State::Requested { .. }) {
+                return Err(Error::InvalidState);
+            }
+            trade::check(world, trade)?;
+
+            trade.state = State::Open;
+            trade::broadcast(world, trade);
+
+            Ok(())
+        });
+
+        if let Err(error) = result {
+            trade::refuse(&session, self.trade_id, error);
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use crate::handler::ProtocolLocalHandler;
+use crate::net::session::Session;
+use crate::social::trade::{self, Error, State};
+use bevy_ecs::prelude::*;
+use protocol::game::social::TradeCancel;
+
+impl ProtocolLocalHandler for TradeCancel {
+    fn handle(self, world: &mut World, entity: Entity, session: Session) {
+        // Too late once both confirmed.
+        let result = trade::with_trade(world, entity, self.trade_id, |_, trade, _| {
+            match trade.state {
+                State::Committing => Err(Error::InvalidState),
+                _ => Ok(()),
+            }
+        });
+
+        match result {
+            Ok(()) => trade::finish(world, self.trade_id, Some(Error::Cancelled)),
+            Err(error) => trade::refuse(&session, self.trade_id, error),
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use crate::handler::ProtocolLocalHandler;
+use crate::net::session::Session;
+use crate::social::trade::{self, State};
+use bevy_ecs::prelude::*;
+use protocol::game::social::TradeConfirm;
+
+impl ProtocolLocalHandler for TradeConfirm {
+    fn handle(self, world: &mut World, entity: Entity, session: Session) {
+        let result = trade::with_trade(world, entity, self.trade_id, |world, trade, index| {
+            trade::check(world, trade)?;
+            let confirmed = trade.confirm(index)?;
+            trade::broadcast(world, trade);
+
+            if !confirmed {
+                return Ok(None);
+            }
+
+            trade.state = State::Committing;
+            Ok(Some(trade.clone()))
+        });
+
+        match result {
+            Ok(Some(trade)) => trade::commit(world, trade),
+            Ok(None) => {}
+            Err(error) => trade::refuse(&session, self.trade_id, error),
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use crate::handler::ProtocolLocalHandler;
+use crate::net::session::Session;
+use crate::social::trade;
+use bevy_ecs::prelude::*;
+use protocol::game::social::TradeLock;
+
+impl ProtocolLocalHandler for TradeLock {
+    fn handle(self, world: &mut World, entity: Entity, session: Session) {
+        let result = trade::with_trade(world, entity, self.trade_id, |world, trade, index| {
+            trade::check(world, trade)?;
+            trade.lock(index)?;
+            trade::broadcast(world, trade);
+
+            Ok(())
+        });
+
+        if let Err(error) = result {
+            trade::refuse(&session, self.trade_id, error);
+        }
+    }
+}
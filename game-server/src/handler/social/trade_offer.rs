@@ -0,0 +1,25 @@
+use crate::handler::ProtocolLocalHandler;
+use crate::net::session::Session;
+use crate::social::trade::{self, Offer};
+use bevy_ecs::prelude::*;
+use protocol::game::social::TradeOffer;
+
+impl ProtocolLocalHandler for TradeOffer {
+    fn handle(self, world: &mut World, entity: Entity, session: Session) {
+        // Whether the items are owned is left to the commit.
+        let result = trade::with_trade(world, entity, self.trade_id, |world, trade, index| {
+            let offer = Offer {
+                gold: self.gold,
+                ..Offer::try_from(self.items.as_slice())?
+            };
+            trade.set_offer(index, offer)?;
+            trade::broadcast(world, trade);
+
+            Ok(())
+        });
+
+        if let Err(error) = result {
+            trade::refuse(&session, self.trade_id, error);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use crate::character::Characters;
+use crate::config;
+use crate::handler::ProtocolLocalHandler;
+use crate::net::session::Session;
+use crate::social::trade::{self, Error, Trade, Trades, Trading};
+use crate::world::time::Time;
+use bevy_ecs::prelude::*;
+use protocol::game::social::{TradeInvitation, TradeRequest};
+use util::id::Id;
+
+impl ProtocolLocalHandler for TradeRequest {
+    fn handle(self, world: &mut World, entity: Entity, session: Session) {
+        if let Err(error) = request(world, entity, &session, self.character_id) {
+            trade::refuse(&session, 0, error);
+        }
+    }
+}
+
+fn request(world: &mut World, entity: Entity, session: &Session, target_id: Id) -> Result<(), Error> {
+    let requester_id = session.entry.character_id;
+    let Some(&target) = world.resource::<Characters>().map.get(&target_id) else {
+        return Err(Error::NotFound);
+    };
+    if target == entity {
+        return Err(Error::NotFound);
+    }
+    if world.get::<Trading>(entity).is_some() || world.get::<Trading>(target).is_some() {
+        return Err(Error::Busy);
+    }
+
+    trade::check_state(world, entity)?;
+    trade::check_state(world, target)?;
+    trade::check_range(world, entity, target)?;
+
+    let time = world.resource::<Time>();
+    let timeout = config!(app).trade.request_timeout;
+    let expire_tick = time.ticks + (timeout.as_nanos() / time.delta().as_nanos().max(1)) as u64;
+
+    let trade = Trade::new(util::id::universal(), (entity, requester_id), (target, target_id), expire_tick);
+    let trade_id = trade.id;
+
+    session.send(&trade.update());
+    if let Some(target_session) = world.get::<Session>(target) {
+        target_session.send(&TradeInvitation {
+            trade_id,
+            character_id: requester_id,
+        });
+    }
+
+    world.resource_mut::<Trades>().map.insert(trade_id, trade);
+    world.entity_mut(entity).insert(Trading { trade_id });
+    world.entity_mut(target).insert(Trading { trade_id });
+
+    Ok(())
+}
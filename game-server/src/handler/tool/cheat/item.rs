@@ -1,19 +1,56 @@
+use crate::character::Character;
+use crate::persistence::ledger::{Change, ItemKind, Operation, Reason};
+use crate::persistence::repositories;
 use crate::task::Task;
 use bevy_ecs::prelude::*;
+use data::item::ItemTable;
+use data::prelude::*;
 use protocol::game::tool::cheat_result::Result;
+use tracing::{info, warn};
 
+/// `<data_id> [count]`
 pub fn handle(world: &mut World, entity: Entity, args: &[String]) -> Option<(Result, String)> {
-    let task = Task::serial(async {
-        let mut conn = db::conn().await?;
+    let character_id = world.get::<Character>(entity)?.id;
 
-        //TODO: Insert a item
+    let data_id = args.first().and_then(|arg| arg.parse::<i32>().ok());
+    let count = args.get(1).map_or(Some(1), |arg| arg.parse::<i32>().ok());
+    let (Some(data_id), Some(count)) = (data_id, count) else {
+        //TODO: Send fail message
+        return None;
+    };
+    if count <= 0 || ItemTable::get(&DataId::from(data_id)).is_none() {
+        //TODO: Send fail message
+        return None;
+    }
+
+    let operation = Operation {
+        id: util::id::universal(),
+        reason: Reason::Cheat,
+        source: None,
+        changes: vec![Change::Item {
+            character_id,
+            item: ItemKind {
+                id: util::id::universal(),
+                data_id,
+                level: 0,
+                is_bound: false,
+            },
+            delta: count,
+        }],
+    };
+
+    let task = Task::serial(async move {
+        repositories().ledger.apply(operation).await?;
 
         Ok(())
-    }).on_complete(|error, world, entity| {
+    }).on_complete(move |error, _, _| {
         if let Some(error) = error {
+            warn!("Failed to cheat item {} for {}: {}", data_id, character_id, error);
             //TODO: Send fail message
             return;
         }
+
+        info!("Cheated {} of item {} for {}", count, data_id, character_id);
     });
 
     task.dispatch(world, entity);
@@ -0,0 +1,43 @@
+pub mod calc;
+pub mod character;
+pub mod config;
+pub mod handler;
+pub mod market;
+pub mod net;
+pub mod persistence;
+pub mod physics;
+pub mod player;
+pub mod social;
+pub mod task;
+pub mod world;
+
+use crate::market::auction::AuctionHouse;
+use crate::net::authenticator::Authenticator;
+use crate::net::cluster::Cluster;
+use crate::net::game_listener::GameListener;
+use crate::net::gateway::Gateway;
+use crate::net::zone_pool::{SpawnZone, ZonePool};
+use crate::social::guild::GuildManager;
+use crate::social::mail::PostOffice;
+use crate::social::party::PartyManager;
+use actix::prelude::*;
+
+/// Start the services, once the config, data and database are initialized.
+pub fn start() {
+    _ = Authenticator::from_registry();
+    _ = GameListener::from_registry();
+    _ = Gateway::from_registry();
+    _ = ZonePool::from_registry();
+    _ = Cluster::from_registry();
+    _ = PartyManager::from_registry();
+    _ = GuildManager::from_registry();
+    _ = AuctionHouse::from_registry();
+    _ = PostOffice::from_registry();
+}
+
+pub fn run() {
+    // In cluster mode, zones are spawned once claimed by this node.
+    if !Cluster::is_enabled() {
+        ZonePool::from_registry().do_send(SpawnZone { id: 0 });
+    }
+}
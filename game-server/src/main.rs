@@ -1,21 +1,4 @@
-mod calc;
-mod character;
-mod config;
-mod handler;
-mod net;
-mod physics;
-mod player;
-mod social;
-mod task;
-mod world;
-
-use crate::net::authenticator::Authenticator;
-use crate::net::game_listener::GameListener;
-use crate::net::gateway::{Gateway, NewZone};
-use crate::net::zone::Zone;
-use crate::social::guild::GuildManager;
-use crate::social::party::PartyManager;
-use actix::prelude::*;
+use game_server::{config, net, persistence};
 use clap::Parser;
 use mimalloc::MiMalloc;
 use rustls::crypto::aws_lc_rs;
@@ -36,6 +19,10 @@ struct Args {
     /// Use local environment file
     #[arg(long)]
     local_env: Option<PathBuf>,
+
+    /// Replay a recorded zone without network and exit
+    #[arg(long)]
+    replay: Option<PathBuf>,
 }
 
 #[actix::main]
@@ -56,9 +43,19 @@ async fn main() {
         exit(0);
     }
 
-    run();
+    if let Some(path) = &args.replay {
+        if let Err(e) = net::zone::replay::run(path) {
+            error!("Failed to replay \"{}\": {}", path.display(), e);
+            exit(1);
+        }
+        exit(0);
+    }
+
+    game_server::run();
 
     tokio::signal::ctrl_c().await.unwrap();
+
+    net::cluster::leave().await;
 }
 
 async fn init(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
@@ -75,29 +72,17 @@ async fn init(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
 
     config::init(&args.local_env)?;
     util::id::init(config!(net).node_id);
-    db::init(
-        &config!(db).user,
-        &config!(db).password,
-        &config!(db).host,
-        config!(db).port,
-        &config!(db).name,
-    ).await?;
 
     data::init(&config!(app).data.dir).await?;
 
-    _ = Authenticator::from_registry();
-    _ = GameListener::from_registry();
-    _ = Gateway::from_registry();
-    _ = PartyManager::from_registry();
-    _ = GuildManager::from_registry();
+    // Replaying needs neither database nor network.
+    if args.replay.is_some() {
+        return Ok(());
+    }
 
-    Ok(())
-}
+    persistence::init().await?;
 
-fn run() {
-    let default_zone = Zone::new(0).start();
-    Gateway::from_registry().do_send(NewZone {
-        id: 0,
-        zone: default_zone.clone(),
-    });
+    game_server::start();
+
+    Ok(())
 }
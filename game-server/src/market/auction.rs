@@ -0,0 +1,141 @@
+use crate::config;
+use crate::persistence::auction::{category_of, Category, Filter, Listing, Sort};
+use crate::persistence::ledger::ItemKind;
+use crate::persistence::{self, repositories};
+use crate::social::mail;
+use actix::prelude::*;
+use data::item::ItemTable;
+use data::prelude::*;
+use protocol::game::play::auction_list_result;
+use std::time::Duration;
+use tracing::{error, info};
+use util::id::Id;
+
+/// Returns expired listings to their sellers. Every node runs it, each taking its own batch.
+#[derive(Default)]
+pub struct AuctionHouse;
+
+impl Actor for AuctionHouse {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(config!(app).auction.expire_interval, |_, _| {
+            actix::spawn(expire());
+        });
+    }
+}
+
+impl Supervised for AuctionHouse {}
+
+impl SystemService for AuctionHouse {}
+
+async fn expire() {
+    match repositories().auction.expire(config!(app).auction.expire_batch as i64).await {
+        Ok(unsold) if unsold.is_empty() => {}
+        Ok(unsold) => {
+            info!("Returned {} expired listings", unsold.len());
+            unsold.iter().for_each(mail::notify);
+        }
+        Err(e) => error!("Failed to expire listings: {}", e),
+    }
+}
+
+/// Take the items from the seller and list them for `duration`.
+pub async fn list(
+    seller_id: Id,
+    item_id: Id,
+    count: i32,
+    price: i64,
+    duration: Duration,
+) -> Result<Result<Listing, auction_list_result::Error>, persistence::Error> {
+    use auction_list_result::Error;
+
+    let items = repositories().item.list(seller_id).await?;
+    let Some(item) = items.iter().find(|item| item.id == item_id) else {
+        return Ok(Err(Error::InvalidItem));
+    };
+    if item.is_bound || item.count < count {
+        return Ok(Err(Error::InvalidItem));
+    }
+    let Some(data) = ItemTable::get(&DataId::from(item.data_id)) else {
+        return Ok(Err(Error::InvalidItem));
+    };
+
+    let listing = Listing {
+        id: util::id::universal(),
+        seller_id,
+        item: ItemKind {
+            id: item.id,
+            data_id: item.data_id,
+            level: item.level,
+            is_bound: false,
+        },
+        count,
+        category: category_of(data),
+        price,
+        expire_at: chrono::Utc::now().timestamp_millis() + duration.as_millis() as i64,
+    };
+
+    match repositories().auction.create(listing.clone()).await {
+        Ok(()) => Ok(Ok(listing)),
+        // Taken since it was checked.
+        Err(persistence::Error::InsufficientItem { .. }) => Ok(Err(Error::InvalidItem)),
+        Err(e) => Err(e),
+    }
+}
+
+/// The filter of a search, or `None` if it asks for levels out of the range of items.
+pub fn filter(filter: &protocol::AuctionFilter) -> Option<Filter> {
+    let search_limit = config!(app).auction.search_limit as i64;
+    let level = |level: Option<u32>| level.map(i16::try_from).transpose().ok();
+
+    Some(Filter {
+        category: filter.category
+            .and_then(|category| protocol::AuctionCategory::try_from(category).ok())
+            .map(category),
+        data_id: filter.data_id,
+        min_level: level(filter.min_level)?,
+        max_level: level(filter.max_level)?,
+        max_price: filter.max_price,
+        seller_id: filter.seller_id,
+        sort: match filter.sort() {
+            protocol::AuctionSort::PriceAscending => Sort::PriceAscending,
+            protocol::AuctionSort::PriceDescending => Sort::PriceDescending,
+            protocol::AuctionSort::ExpireAt => Sort::ExpireAt,
+        },
+        offset: filter.offset as i64,
+        limit: match filter.limit as i64 {
+            0 => search_limit,
+            limit => limit.min(search_limit),
+        },
+    })
+}
+
+fn category(category: protocol::AuctionCategory) -> Category {
+    match category {
+        protocol::AuctionCategory::Equipment => Category::Equipment,
+        protocol::AuctionCategory::RandomBox => Category::RandomBox,
+        protocol::AuctionCategory::Other => Category::Other,
+    }
+}
+
+impl From<&Listing> for protocol::AuctionListing {
+    fn from(listing: &Listing) -> Self {
+        let category = match listing.category {
+            Category::Equipment => protocol::AuctionCategory::Equipment,
+            Category::RandomBox => protocol::AuctionCategory::RandomBox,
+            Category::Other => protocol::AuctionCategory::Other,
+        };
+
+        Self {
+            id: listing.id,
+            seller_id: listing.seller_id,
+            data_id: listing.item.data_id,
+            level: listing.item.level as u32,
+            count: listing.count,
+            category: category.into(),
+            price: listing.price,
+            expire_at: listing.expire_at,
+        }
+    }
+}
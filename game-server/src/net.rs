@@ -1,7 +1,9 @@
 pub mod authenticator;
+pub mod cluster;
 pub mod control_listener;
 pub mod game_listener;
 pub mod gateway;
 pub mod region;
 pub mod session;
 pub mod zone;
+pub mod zone_pool;
@@ -3,16 +3,55 @@ mod new_connection;
 pub use new_connection::NewConnection;
 
 use crate::config;
+use crate::config::auth::VersionCheck;
 use crate::net::session::Entry;
+use crate::persistence::{self, repositories};
 use actix::prelude::*;
 use jsonwebtoken::DecodingKey;
 use protocol::game::auth::*;
+use protocol::game::{Compression, ProtocolId, ProtocolVersion, PROTOCOL_VERSION, SCHEMA_HASH};
 use util::token;
 
 pub struct Authenticator {
     decoding_key: DecodingKey,
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Login timed out")]
+    Timeout(#[from] tokio::time::error::Elapsed),
+
+    #[error(transparent)]
+    Connection(#[from] quinn::ConnectionError),
+
+    #[error(transparent)]
+    Read(#[from] quinn::ReadExactError),
+
+    #[error(transparent)]
+    Protocol(#[from] protocol::game::Error),
+
+    #[error(transparent)]
+    Decode(#[from] prost::DecodeError),
+
+    #[error("Unexpected protocol {0} instead of login")]
+    ProtocolId(ProtocolId),
+
+    #[error("Incompatible client version {client:?} with schema hash {schema_hash:#018x}")]
+    Version { client: ProtocolVersion, schema_hash: u64 },
+
+    #[error("Unknown login kind: {0}")]
+    Kind(#[from] prost::UnknownEnumValue),
+
+    #[error("Invalid token: {0}")]
+    Token(#[from] jsonwebtoken::errors::Error),
+
+    #[error("Character {character_id} is not owned by account {account_id}")]
+    Character { account_id: i64, character_id: i64 },
+
+    #[error(transparent)]
+    Persistence(#[from] persistence::Error),
+}
+
 impl Default for Authenticator {
     fn default() -> Self {
         let decoding_key = DecodingKey::from_secret(&config!(auth).token_key);
@@ -29,18 +68,83 @@ impl Supervised for Authenticator {}
 
 impl SystemService for Authenticator {}
 
-impl Authenticator {
-    fn validate_login(
-        &self,
-        login: &Login,
-    ) -> Result<(Entry, login::Kind), Box<dyn std::error::Error>> {
-        let claims = token::verify(&login.token, &self.decoding_key)?;
-        let entry = Entry {
-            account_id: claims.account_id,
-            character_id: login.character_id,
-        };
-        let login_kind = login::Kind::try_from(login.kind)?;
-
-        Ok((entry, login_kind))
+impl Error {
+    /// Error code replied to the client, if the client is still there to receive it.
+    fn login_error(&self) -> Option<login_result::Error> {
+        use login_result::Error::*;
+
+        Some(match self {
+            Error::Timeout(_) | Error::Connection(_) | Error::Read(_) => return None,
+            Error::Protocol(_) | Error::Decode(_) | Error::ProtocolId(_) | Error::Kind(_) => InvalidProtocol,
+            Error::Version { .. } => IncompatibleVersion,
+            Error::Token(_) => InvalidToken,
+            Error::Character { .. } => InvalidCharacter,
+            Error::Persistence(_) => Internal,
+        })
+    }
+}
+
+/// Check that the client speaks protocols the server understands.
+fn validate_version(login: &Login) -> Result<(), Error> {
+    let client = ProtocolVersion {
+        major: login.version_major,
+        minor: login.version_minor,
+    };
+
+    let compatible = match config!(auth).login.version_check {
+        VersionCheck::Compatible => login.schema_hash == SCHEMA_HASH
+            || (client.major == PROTOCOL_VERSION.major && client.minor <= PROTOCOL_VERSION.minor),
+        VersionCheck::Strict => login.schema_hash == SCHEMA_HASH,
+        VersionCheck::Disabled => true,
+    };
+
+    if !compatible {
+        return Err(Error::Version {
+            client,
+            schema_hash: login.schema_hash,
+        });
     }
+
+    Ok(())
+}
+
+fn validate_login(
+    decoding_key: &DecodingKey,
+    login: &Login,
+) -> Result<(Entry, login::Kind), Error> {
+    let claims = token::verify(&login.token, decoding_key)?;
+    let entry = Entry {
+        account_id: claims.account_id,
+        character_id: login.character_id,
+    };
+    let login_kind = login::Kind::try_from(login.kind)?;
+
+    Ok((entry, login_kind))
+}
+
+/// Compress protocols sent to the client if it supports it, unless disabled.
+fn negotiate_compression(login: &Login) -> Option<Compression> {
+    let compression_config = &config!(net).compression;
+    let supported = login.compressions.contains(&(login::Compression::Lz4 as i32));
+
+    (compression_config.enabled && supported).then(|| Compression {
+        threshold: compression_config.threshold,
+    })
+}
+
+/// Check that the character belongs to the account of the token.
+async fn validate_ownership(entry: &Entry) -> Result<(), Error> {
+    let owned = repositories()
+        .character
+        .is_owned(entry.account_id, entry.character_id)
+        .await?;
+
+    if !owned {
+        return Err(Error::Character {
+            account_id: entry.account_id,
+            character_id: entry.character_id,
+        });
+    }
+
+    Ok(())
 }
@@ -1,12 +1,14 @@
+use super::{negotiate_compression, validate_login, validate_ownership, validate_version, Error};
 use crate::config;
 use crate::net::authenticator::Authenticator;
 use crate::net::gateway::{Gateway, NewPlayer};
-use crate::net::session::Session;
+use crate::net::session::{Entry, Session};
 use actix::prelude::*;
 use bytes::Bytes;
+use jsonwebtoken::DecodingKey;
 use prost::Message;
-use protocol::game::auth::Login;
-use protocol::game::Header;
+use protocol::game::auth::{login, login_result, Login, LoginResult};
+use protocol::game::{encode, Compression, Header, Protocol};
 use quinn::{Connection, RecvStream, SendStream};
 use tokio::time::timeout;
 use tracing::{error, info};
@@ -21,67 +23,89 @@ impl Handler<NewConnection> for Authenticator {
     type Result = ();
 
     fn handle(&mut self, msg: NewConnection, ctx: &mut Self::Context) -> Self::Result {
+        let decoding_key = self.decoding_key.clone();
+
         ctx.spawn(async move {
             // Receive login protocol with timeout.
             let connection = msg.connection;
-            let (send_stream, mut receive_stream) = timeout(
+            let (mut send_stream, mut receive_stream) = match timeout(
                 config!(auth).login.timeout,
                 connection.accept_bi(),
-            ).await??;
-
-            let login = receive_login(&mut receive_stream).await?;
-
-            Ok::<(Connection, SendStream, RecvStream, Login), Box<dyn std::error::Error>>((
-                connection,
-                send_stream,
-                receive_stream,
-                login,
-            ))
-        }
-        .into_actor(self)
-        .then(|res, act, _| {
-            let (connection, send_stream, receive_stream, login) = match res {
-                Ok(o) => o,
+            ).await.map_err(Error::from).and_then(|res| res.map_err(Error::from)) {
+                Ok(streams) => streams,
                 Err(e) => {
-                    error!("Failed to receive login protocol: {}", e);
-                    return fut::ready(());
+                    error!("Failed to accept login stream: {}", e);
+                    return;
                 }
             };
 
-            let (entry, login_kind) = match act.validate_login(&login) {
+            let (entry, login_kind, compression) = match authenticate(&decoding_key, &mut receive_stream).await {
                 Ok(o) => o,
                 Err(e) => {
-                    error!("Failed to validate login: {}, {}", e, &login.token);
-                    return fut::ready(());
+                    error!("Failed to authenticate {}: {}", connection.remote_address(), e);
+
+                    if let Some(login_error) = e.login_error() {
+                        reply_error(&mut send_stream, login_error).await;
+                    }
+                    return;
                 }
             };
 
             info!("Authenticated: {}", entry);
 
-            let session = Session::start(entry, connection, receive_stream, send_stream);
+            // The login result is sent by the gateway, which may still refuse a duplicate login.
+            let session = Session::start(entry, compression, connection, receive_stream, send_stream);
             Gateway::from_registry().do_send(NewPlayer {
                 login_kind,
                 session,
             });
-
-            fut::ready(())
-        }));
+        }
+        .into_actor(self));
     }
 }
 
-async fn receive_login(stream: &mut RecvStream) -> Result<Login, Box<dyn std::error::Error>> {
+async fn authenticate(
+    decoding_key: &DecodingKey,
+    stream: &mut RecvStream,
+) -> Result<(Entry, login::Kind, Option<Compression>), Error> {
+    let login = receive_login(stream).await?;
+    // Checked first, as an outdated client may not even send a valid token.
+    validate_version(&login)?;
+    let (entry, login_kind) = validate_login(decoding_key, &login)?;
+    validate_ownership(&entry).await?;
+
+    Ok((entry, login_kind, negotiate_compression(&login)))
+}
+
+async fn receive_login(stream: &mut RecvStream) -> Result<Login, Error> {
     let mut header_buffer = [0u8; Header::size()];
     timeout(config!(auth).login.timeout, stream.read_exact(&mut header_buffer)).await??;
 
     let header = Header::decode(&header_buffer)?;
+    if header.compressed || header.id != Login::default().protocol_id() {
+        return Err(Error::ProtocolId(header.id));
+    }
 
     let mut body_buffer = vec![0u8; header.length as usize];
     timeout(config!(auth).login.timeout, stream.read_exact(&mut body_buffer)).await??;
-    
+
     let login = Login::decode(Bytes::from(body_buffer))?;
-    // if login.protocol_id() != header.id {
-    //     return Err();
-    // }
 
     Ok(login)
 }
+
+async fn reply_error(stream: &mut SendStream, login_error: login_result::Error) {
+    let result = LoginResult {
+        error: Some(login_error.into()),
+        ..Default::default()
+    };
+
+    let Ok(bytes) = encode(&result) else {
+        return;
+    };
+    if stream.write_all(&bytes).await.is_ok() {
+        _ = stream.finish();
+        // Give the client a chance to receive the result before the connection is dropped.
+        _ = timeout(config!(auth).login.timeout, stream.stopped()).await;
+    }
+}
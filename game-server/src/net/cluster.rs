@@ -0,0 +1,358 @@
+mod find_zone;
+mod locate;
+pub mod message;
+pub mod registry;
+mod relay;
+mod tls;
+mod transfer_player;
+
+pub use find_zone::{FindZone, RemoteZone};
+pub use locate::Locate;
+pub use relay::Relay;
+pub use transfer_player::TransferPlayer;
+
+use crate::config;
+use crate::net::gateway::{AcceptTransfer, Gateway, SendToCharacter};
+use crate::net::session::Entry;
+use crate::net::zone::record::PlayerSnapshot;
+use crate::net::zone_pool::{SpawnZone, ZonePool};
+use actix::prelude::*;
+use message::cluster_message::Kind;
+use message::{ClusterMessage, TransferResult};
+use prost::Message as _;
+use quinn::{Connection, Endpoint, RecvStream, SendStream};
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use tracing::{error, info, warn};
+use util::id::Id;
+
+const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+const SERVER_NAME: &str = "spire-cluster";
+
+/// Keeps this node registered to the cluster, owns the zones claimed by it, and carries
+/// messages to the other nodes.
+///
+/// Does nothing unless `cluster.enabled`, in which case a single node owns every zone.
+pub struct Cluster {
+    node_id: i16,
+    endpoint: Option<Endpoint>,
+
+    nodes: HashMap<i16, registry::Node>,
+    zones: HashMap<Id, i16>,
+    spawned_zones: HashSet<Id>,
+    connections: HashMap<i16, Connection>,
+    /// The node of each character relayed to or located here, sparing the registry a lookup.
+    /// Those on other nodes are forgotten on every sync, as they may have moved since.
+    characters: HashMap<Id, i16>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Db(#[from] db::Error),
+
+    #[error(transparent)]
+    IO(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Connect(#[from] quinn::ConnectError),
+
+    #[error(transparent)]
+    Connection(#[from] quinn::ConnectionError),
+
+    #[error(transparent)]
+    Write(#[from] quinn::WriteError),
+
+    #[error(transparent)]
+    ClosedStream(#[from] quinn::ClosedStream),
+
+    #[error(transparent)]
+    Read(#[from] quinn::ReadToEndError),
+
+    #[error(transparent)]
+    Decode(#[from] prost::DecodeError),
+
+    #[error(transparent)]
+    Record(#[from] crate::net::zone::record::Error),
+
+    #[error(transparent)]
+    Mailbox(#[from] MailboxError),
+
+    #[error("Cluster is not enabled")]
+    Disabled,
+
+    #[error("Unknown node {0}")]
+    UnknownNode(i16),
+
+    #[error("Unknown zone {0}")]
+    UnknownZone(Id),
+
+    #[error("Invalid address: {0}")]
+    Address(String),
+
+    #[error("Transfer refused")]
+    TransferRefused,
+
+    #[error("Unexpected message")]
+    UnexpectedMessage,
+}
+
+impl Cluster {
+    pub fn is_enabled() -> bool {
+        config!(net).cluster.enabled
+    }
+
+    fn start_endpoint(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let listen_addr = SocketAddr::from(([0, 0, 0, 0], config!(net).cluster.port));
+        let mut endpoint = Endpoint::server(tls::server_config()?, listen_addr)?;
+        endpoint.set_default_client_config(tls::client_config()?);
+
+        info!("Cluster listening on {}", endpoint.local_addr()?);
+
+        let acceptor = endpoint.clone();
+        tokio::spawn(async move {
+            while let Some(incoming) = acceptor.accept().await {
+                tokio::spawn(async move {
+                    match incoming.await {
+                        Ok(connection) => serve(connection).await,
+                        Err(e) => error!("Failed to accept node: {}", e),
+                    }
+                });
+            }
+        });
+
+        self.endpoint = Some(endpoint);
+        Ok(())
+    }
+
+    /// Refresh the heartbeat, reap dead nodes, claim free zones and reload the registry.
+    fn sync(&mut self, ctx: &mut Context<Self>) {
+        let node_id = self.node_id;
+
+        ctx.spawn(async move {
+            let cluster_config = &config!(net).cluster;
+            let mut conn = db::conn().await?;
+
+            registry::register(&mut conn, node_id, &cluster_address(), &game_address()).await?;
+
+            let reaped = registry::reap(&mut conn, cluster_config.node_timeout).await?;
+            if reaped > 0 {
+                warn!("Reaped {} unresponsive nodes", reaped);
+            }
+
+            registry::claim(&mut conn, node_id, &cluster_config.zones).await?;
+            registry::load(&mut conn).await
+        }
+        .into_actor(self)
+        .map(|res, act, _| {
+            let (nodes, zones) = match res {
+                Ok(o) => o,
+                Err(e) => {
+                    error!("Failed to sync cluster: {}", e);
+                    return;
+                }
+            };
+
+            act.nodes = nodes.into_iter().map(|node| (node.id, node)).collect();
+            act.zones = zones.into_iter().collect();
+            act.connections.retain(|node_id, _| act.nodes.contains_key(node_id));
+            act.characters.retain(|_, node_id| *node_id == act.node_id);
+
+            for (&zone_id, &owner) in &act.zones {
+                if owner != act.node_id {
+                    if act.spawned_zones.contains(&zone_id) {
+                        warn!("Zone {} is now owned by node {}", zone_id, owner);
+                    }
+                    continue;
+                }
+
+                if act.spawned_zones.insert(zone_id) {
+                    info!("Zone {} claimed", zone_id);
+                    ZonePool::from_registry().do_send(SpawnZone { id: zone_id });
+                }
+            }
+        }));
+    }
+
+    /// A connection to the node, reusing the previous one if still alive.
+    fn connect(&self, node_id: i16) -> impl ActorFuture<Self, Output = Result<Connection, Error>> + use<> {
+        let cached = self.connections
+            .get(&node_id)
+            .filter(|connection| connection.close_reason().is_none())
+            .cloned();
+        let address = self.nodes.get(&node_id).map(|node| node.cluster_address.clone());
+        let endpoint = self.endpoint.clone();
+
+        async move {
+            if let Some(connection) = cached {
+                return Ok(connection);
+            }
+
+            let endpoint = endpoint.ok_or(Error::Disabled)?;
+            let address = address.ok_or(Error::UnknownNode(node_id))?;
+            let socket_addr = tokio::net::lookup_host(&address)
+                .await?
+                .next()
+                .ok_or(Error::Address(address))?;
+
+            Ok(endpoint.connect(socket_addr, SERVER_NAME)?.await?)
+        }
+        .into_actor(self)
+        .map(move |res, act, _| {
+            if let Ok(connection) = &res {
+                act.connections.insert(node_id, connection.clone());
+            }
+            res
+        })
+    }
+}
+
+impl Default for Cluster {
+    fn default() -> Self {
+        Self {
+            node_id: config!(net).node_id as i16,
+            endpoint: None,
+            nodes: HashMap::new(),
+            zones: HashMap::new(),
+            spawned_zones: HashSet::new(),
+            connections: HashMap::new(),
+            characters: HashMap::new(),
+        }
+    }
+}
+
+impl Actor for Cluster {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if !Self::is_enabled() {
+            return;
+        }
+
+        if let Err(e) = self.start_endpoint() {
+            error!("Failed to start cluster endpoint: {}", e);
+            return;
+        }
+
+        self.sync(ctx);
+        ctx.run_interval(config!(net).cluster.heartbeat_interval, |act, ctx| {
+            act.sync(ctx);
+        });
+    }
+}
+
+impl Supervised for Cluster {}
+
+impl SystemService for Cluster {}
+
+/// Unregister this node, releasing its zones to the other nodes at once.
+pub async fn leave() {
+    if !Cluster::is_enabled() {
+        return;
+    }
+
+    let node_id = config!(net).node_id as i16;
+    let result = match db::conn().await {
+        Ok(mut conn) => registry::leave(&mut conn, node_id).await,
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = result {
+        error!("Failed to leave cluster: {}", e);
+    }
+}
+
+fn cluster_address() -> String {
+    format!("{}:{}", config!(net).host, config!(net).cluster.port)
+}
+
+fn game_address() -> String {
+    format!("{}:{}", config!(net).host, config!(net).port)
+}
+
+async fn request(connection: &Connection, message: ClusterMessage) -> Result<ClusterMessage, Error> {
+    let (mut send_stream, mut receive_stream) = connection.open_bi().await?;
+    send_stream.write_all(&message.encode_to_vec()).await?;
+    send_stream.finish()?;
+
+    let response = receive_stream.read_to_end(MAX_MESSAGE_SIZE).await?;
+    Ok(ClusterMessage::decode(&response[..])?)
+}
+
+async fn notify(connection: &Connection, message: ClusterMessage) -> Result<(), Error> {
+    let mut send_stream = connection.open_uni().await?;
+    send_stream.write_all(&message.encode_to_vec()).await?;
+    send_stream.finish()?;
+
+    Ok(())
+}
+
+async fn serve(connection: Connection) {
+    info!("Node connected from {}", connection.remote_address());
+
+    loop {
+        tokio::select! {
+            stream = connection.accept_bi() => {
+                let Ok((send_stream, receive_stream)) = stream else { break; };
+                tokio::spawn(async move {
+                    if let Err(e) = serve_request(send_stream, receive_stream).await {
+                        error!("Failed to serve node request: {}", e);
+                    }
+                });
+            }
+            stream = connection.accept_uni() => {
+                let Ok(receive_stream) = stream else { break; };
+                tokio::spawn(async move {
+                    if let Err(e) = serve_notification(receive_stream).await {
+                        error!("Failed to serve node notification: {}", e);
+                    }
+                });
+            }
+        }
+    }
+}
+
+async fn serve_request(mut send_stream: SendStream, mut receive_stream: RecvStream) -> Result<(), Error> {
+    let request = receive_stream.read_to_end(MAX_MESSAGE_SIZE).await?;
+
+    let response = match ClusterMessage::decode(&request[..])?.kind {
+        Some(Kind::Transfer(mut transfer)) => {
+            let player_data = PlayerSnapshot::decode(&mut transfer.player)?.restore()?;
+            let accepted = Gateway::from_registry()
+                .send(AcceptTransfer {
+                    entry: Entry {
+                        account_id: transfer.account_id,
+                        character_id: transfer.character_id,
+                    },
+                    zone_id: transfer.zone_id,
+                    player_data,
+                })
+                .await?;
+
+            Kind::TransferResult(TransferResult { accepted })
+        }
+        _ => return Err(Error::UnexpectedMessage),
+    };
+
+    send_stream.write_all(&ClusterMessage::from(response).encode_to_vec()).await?;
+    send_stream.finish()?;
+
+    Ok(())
+}
+
+async fn serve_notification(mut receive_stream: RecvStream) -> Result<(), Error> {
+    let notification = receive_stream.read_to_end(MAX_MESSAGE_SIZE).await?;
+
+    match ClusterMessage::decode(&notification[..])?.kind {
+        Some(Kind::Relay(relay)) => {
+            Gateway::from_registry().do_send(SendToCharacter {
+                character_id: relay.character_id,
+                protocol: relay.protocol,
+                relay: false,
+            });
+        }
+        _ => return Err(Error::UnexpectedMessage),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use super::Cluster;
+use actix::prelude::*;
+use util::id::Id;
+
+/// Find the node owning a zone, if it is not this node.
+#[derive(actix::Message)]
+#[rtype(result = "Option<RemoteZone>")]
+pub struct FindZone {
+    pub zone_id: Id,
+}
+
+pub struct RemoteZone {
+    pub node_id: i16,
+    pub game_address: String,
+}
+
+impl Handler<FindZone> for Cluster {
+    type Result = MessageResult<FindZone>;
+
+    fn handle(&mut self, msg: FindZone, _: &mut Self::Context) -> Self::Result {
+        let remote = self.zones
+            .get(&msg.zone_id)
+            .filter(|&&owner| owner != self.node_id)
+            .and_then(|owner| self.nodes.get(owner))
+            .map(|node| RemoteZone {
+                node_id: node.id,
+                game_address: node.game_address.clone(),
+            });
+
+        MessageResult(remote)
+    }
+}
@@ -0,0 +1,50 @@
+use super::{registry, Cluster};
+use actix::prelude::*;
+use tracing::error;
+use util::id::Id;
+
+/// Record whether a character is in a zone of this node, for the other nodes to relay to it.
+/// Cached at once, and written to the registry for the other nodes.
+#[derive(actix::Message)]
+#[rtype(result = "()")]
+pub struct Locate {
+    pub character_id: Id,
+    pub present: bool,
+}
+
+impl Handler<Locate> for Cluster {
+    type Result = ();
+
+    fn handle(&mut self, msg: Locate, ctx: &mut Self::Context) -> Self::Result {
+        if !Cluster::is_enabled() {
+            return;
+        }
+
+        let node_id = self.node_id;
+        match msg.present {
+            true => {
+                self.characters.insert(msg.character_id, node_id);
+            }
+            false => {
+                if self.characters.get(&msg.character_id) == Some(&node_id) {
+                    self.characters.remove(&msg.character_id);
+                }
+            }
+        }
+
+        ctx.spawn(async move {
+            let mut conn = db::conn().await?;
+
+            match msg.present {
+                true => registry::locate(&mut conn, msg.character_id, node_id).await,
+                false => registry::unlocate(&mut conn, msg.character_id, node_id).await,
+            }
+        }
+        .into_actor(self)
+        .map(move |res, _, _| {
+            if let Err(e) = res {
+                error!("Failed to locate character {}: {}", msg.character_id, e);
+            }
+        }));
+    }
+}
@@ -0,0 +1,57 @@
+//! Messages exchanged between nodes. Each message travels on its own QUIC stream:
+//! bidirectional for requests expecting a response, unidirectional otherwise.
+
+use bytes::Bytes;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ClusterMessage {
+    #[prost(oneof = "cluster_message::Kind", tags = "1, 2, 3")]
+    pub kind: Option<cluster_message::Kind>,
+}
+
+pub mod cluster_message {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Kind {
+        #[prost(message, tag = "1")]
+        Transfer(super::Transfer),
+        #[prost(message, tag = "2")]
+        TransferResult(super::TransferResult),
+        #[prost(message, tag = "3")]
+        Relay(super::Relay),
+    }
+}
+
+/// Hand a player over to the node owning the destination zone.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Transfer {
+    #[prost(int64, tag = "1")]
+    pub account_id: i64,
+    #[prost(int64, tag = "2")]
+    pub character_id: i64,
+    #[prost(int64, tag = "3")]
+    pub zone_id: i64,
+    /// `PlayerSnapshot` of the player data.
+    #[prost(bytes = "bytes", tag = "4")]
+    pub player: Bytes,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TransferResult {
+    #[prost(bool, tag = "1")]
+    pub accepted: bool,
+}
+
+/// An encoded protocol to deliver to a character, e.g. party, guild or chat messages.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Relay {
+    #[prost(int64, tag = "1")]
+    pub character_id: i64,
+    #[prost(bytes = "bytes", tag = "2")]
+    pub protocol: Bytes,
+}
+
+impl From<cluster_message::Kind> for ClusterMessage {
+    fn from(kind: cluster_message::Kind) -> Self {
+        Self { kind: Some(kind) }
+    }
+}
@@ -0,0 +1,184 @@
+use diesel::data_types::PgInterval;
+use diesel::dsl::now;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use diesel_async::RunQueryDsl;
+use std::time::Duration;
+use util::id::Id;
+
+/// A node of the cluster, as registered in the database.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = db::schema::cluster_node)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Node {
+    pub id: i16,
+    pub cluster_address: String,
+    pub game_address: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = db::schema::cluster_node)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct NewNode<'a> {
+    id: i16,
+    cluster_address: &'a str,
+    game_address: &'a str,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = db::schema::cluster_zone)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct NewZoneClaim {
+    zone_id: Id,
+    node_id: i16,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = db::schema::cluster_character)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct NewCharacterLocation {
+    character_id: Id,
+    node_id: i16,
+}
+
+/// Register the node, or refresh its addresses and heartbeat if it is registered already.
+pub async fn register(
+    conn: &mut db::Connection,
+    node_id: i16,
+    cluster_address: &str,
+    game_address: &str,
+) -> Result<(), db::Error> {
+    use db::schema::cluster_node::dsl;
+
+    let node = NewNode {
+        id: node_id,
+        cluster_address,
+        game_address,
+    };
+
+    diesel::insert_into(dsl::cluster_node)
+        .values(&node)
+        .on_conflict(dsl::id)
+        .do_update()
+        .set((
+            dsl::cluster_address.eq(excluded(dsl::cluster_address)),
+            dsl::game_address.eq(excluded(dsl::game_address)),
+            dsl::heartbeat_at.eq(now),
+        ))
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Remove nodes without heartbeat for `timeout`. Their zones are released by cascade.
+pub async fn reap(conn: &mut db::Connection, timeout: Duration) -> Result<usize, db::Error> {
+    use db::schema::cluster_node::dsl;
+
+    let timeout = PgInterval::from_microseconds(timeout.as_micros() as i64);
+    let reaped = diesel::delete(dsl::cluster_node.filter((dsl::heartbeat_at + timeout).lt(now)))
+        .execute(conn)
+        .await?;
+
+    Ok(reaped)
+}
+
+pub async fn leave(conn: &mut db::Connection, node_id: i16) -> Result<(), db::Error> {
+    use db::schema::cluster_node::dsl;
+
+    diesel::delete(dsl::cluster_node.find(node_id))
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Claim the zones not owned by any node yet. Zones owned by others are left untouched.
+pub async fn claim(
+    conn: &mut db::Connection,
+    node_id: i16,
+    zones: &[Id],
+) -> Result<(), db::Error> {
+    use db::schema::cluster_zone::dsl;
+
+    let claims: Vec<_> = zones
+        .iter()
+        .map(|&zone_id| NewZoneClaim { zone_id, node_id })
+        .collect();
+
+    diesel::insert_into(dsl::cluster_zone)
+        .values(&claims)
+        .on_conflict_do_nothing()
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Load every live node and the owner of every zone.
+pub async fn load(conn: &mut db::Connection) -> Result<(Vec<Node>, Vec<(Id, i16)>), db::Error> {
+    let nodes = {
+        use db::schema::cluster_node::dsl::*;
+
+        cluster_node
+            .select(Node::as_select())
+            .load(conn)
+            .await?
+    };
+
+    let zones = {
+        use db::schema::cluster_zone::dsl::*;
+
+        cluster_zone
+            .select((zone_id, node_id))
+            .load(conn)
+            .await?
+    };
+
+    Ok((nodes, zones))
+}
+
+/// Record the character as being on the node.
+pub async fn locate(conn: &mut db::Connection, character_id: Id, node_id: i16) -> Result<(), db::Error> {
+    use db::schema::cluster_character::dsl;
+
+    diesel::insert_into(dsl::cluster_character)
+        .values(&NewCharacterLocation { character_id, node_id })
+        .on_conflict(dsl::character_id)
+        .do_update()
+        .set((
+            dsl::node_id.eq(excluded(dsl::node_id)),
+            dsl::located_at.eq(now),
+        ))
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Forget the character, unless another node located it since.
+pub async fn unlocate(conn: &mut db::Connection, character_id: Id, node_id: i16) -> Result<(), db::Error> {
+    use db::schema::cluster_character::dsl;
+
+    diesel::delete(dsl::cluster_character
+        .find(character_id)
+        .filter(dsl::node_id.eq(node_id)))
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// The node of the character, if it is in the game.
+pub async fn find_character(conn: &mut db::Connection, character_id: Id) -> Result<Option<i16>, db::Error> {
+    use db::schema::cluster_character::dsl;
+
+    let node_id = dsl::cluster_character
+        .find(character_id)
+        .select(dsl::node_id)
+        .first(conn)
+        .await
+        .optional()?;
+
+    Ok(node_id)
+}
@@ -0,0 +1,73 @@
+use super::message::cluster_message::Kind;
+use super::message::Relay as RelayMessage;
+use super::{notify, registry, Cluster, Error};
+use actix::prelude::*;
+use bytes::Bytes;
+use futures::future::Either;
+use tracing::error;
+use util::id::Id;
+
+/// Deliver an encoded protocol to a character on another node, found in the cache of the cluster
+/// or else in the registry. Dropped if the character is not in the game.
+#[derive(actix::Message)]
+#[rtype(result = "()")]
+pub struct Relay {
+    pub character_id: Id,
+    pub protocol: Bytes,
+}
+
+impl Handler<Relay> for Cluster {
+    type Result = ();
+
+    fn handle(&mut self, msg: Relay, ctx: &mut Self::Context) -> Self::Result {
+        let character_id = msg.character_id;
+
+        if let Some(&node_id) = self.characters.get(&character_id) {
+            if node_id != self.node_id {
+                ctx.spawn(self.relay(node_id, msg));
+            }
+            return;
+        }
+
+        ctx.spawn(async move {
+            let mut conn = db::conn().await?;
+            registry::find_character(&mut conn, character_id).await
+        }
+        .into_actor(self)
+        .then(move |res, act, _| {
+            let node_id = match res {
+                Ok(Some(node_id)) if node_id != act.node_id => node_id,
+                Ok(_) => return Either::Left(actix::fut::ready(())),
+                Err(e) => {
+                    error!("Failed to find the node of character {}: {}", character_id, e);
+                    return Either::Left(actix::fut::ready(()));
+                }
+            };
+
+            act.characters.insert(character_id, node_id);
+            Either::Right(act.relay(node_id, msg))
+        }));
+    }
+}
+
+impl Cluster {
+    fn relay(&self, node_id: i16, msg: Relay) -> impl ActorFuture<Self, Output = ()> + use<> {
+        let message = Kind::Relay(RelayMessage {
+            character_id: msg.character_id,
+            protocol: msg.protocol,
+        });
+
+        self.connect(node_id).then(move |res, act, _| {
+            async move {
+                notify(&res?, message.into()).await?;
+                Ok::<_, Error>(())
+            }
+            .into_actor(act)
+            .map(move |res, _, _| {
+                if let Err(e) = res {
+                    error!("Failed to relay to node {}: {}", node_id, e);
+                }
+            })
+        })
+    }
+}
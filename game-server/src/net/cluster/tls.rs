@@ -0,0 +1,140 @@
+use crate::config;
+use quinn::crypto::rustls::{QuicClientConfig, QuicServerConfig};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{CryptoProvider, WebPkiSupportedAlgorithms};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::server::danger::{ClientCertVerified, ClientCertVerifier};
+use rustls::{DigitallySignedStruct, DistinguishedName, SignatureScheme};
+use std::sync::Arc;
+
+pub const APPLICATION_PROTOCOL: &[u8] = b"spire-cluster";
+
+/// Nodes share the game server certificate, and only accept peers presenting exactly that
+/// certificate on both sides of the connection.
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    cert: CertificateDer<'static>,
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+pub fn server_config() -> Result<quinn::ServerConfig, Box<dyn std::error::Error>> {
+    let tls_cert_chain = config::get_tls_cert_chain()?;
+    let tls_key = config::get_tls_key()?;
+    let verifier = PinnedCertVerifier::new(&tls_cert_chain)?;
+
+    let mut tls_config = rustls::ServerConfig::builder()
+        .with_client_cert_verifier(Arc::new(verifier))
+        .with_single_cert(tls_cert_chain, tls_key)?;
+    tls_config.alpn_protocols = vec![APPLICATION_PROTOCOL.to_vec()];
+
+    Ok(quinn::ServerConfig::with_crypto(Arc::new(QuicServerConfig::try_from(tls_config)?)))
+}
+
+pub fn client_config() -> Result<quinn::ClientConfig, Box<dyn std::error::Error>> {
+    let tls_cert_chain = config::get_tls_cert_chain()?;
+    let tls_key = config::get_tls_key()?;
+    let verifier = PinnedCertVerifier::new(&tls_cert_chain)?;
+
+    let mut tls_config = rustls::ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_client_auth_cert(tls_cert_chain, tls_key)?;
+    tls_config.alpn_protocols = vec![APPLICATION_PROTOCOL.to_vec()];
+
+    Ok(quinn::ClientConfig::new(Arc::new(QuicClientConfig::try_from(tls_config)?)))
+}
+
+impl PinnedCertVerifier {
+    fn new(cert_chain: &[CertificateDer<'static>]) -> Result<Self, Box<dyn std::error::Error>> {
+        let cert = cert_chain.first().ok_or("Empty TLS certificate chain")?.clone();
+        let algorithms = CryptoProvider::get_default()
+            .ok_or("No default crypto provider")?
+            .signature_verification_algorithms;
+
+        Ok(Self { cert, algorithms })
+    }
+
+    fn verify_cert(&self, end_entity: &CertificateDer<'_>) -> Result<(), rustls::Error> {
+        if end_entity.as_ref() != self.cert.as_ref() {
+            return Err(rustls::Error::InvalidCertificate(
+                rustls::CertificateError::ApplicationVerificationFailure,
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        self.verify_cert(end_entity)?;
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+impl ClientCertVerifier for PinnedCertVerifier {
+    fn root_hint_subjects(&self) -> &[DistinguishedName] {
+        &[]
+    }
+
+    fn verify_client_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _now: UnixTime,
+    ) -> Result<ClientCertVerified, rustls::Error> {
+        self.verify_cert(end_entity)?;
+        Ok(ClientCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
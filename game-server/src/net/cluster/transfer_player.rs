@@ -0,0 +1,59 @@
+use super::message::cluster_message::Kind;
+use super::message::Transfer;
+use super::{request, Cluster, Error};
+use crate::net::session::Entry;
+use crate::net::zone::record::PlayerSnapshot;
+use crate::player::PlayerData;
+use actix::prelude::*;
+use bytes::BytesMut;
+use util::id::Id;
+
+/// Hand a player over to another node. Once accepted, the client is expected to reconnect to
+/// that node with a transfer login.
+#[derive(actix::Message)]
+#[rtype(result = "Result<(), Error>")]
+pub struct TransferPlayer {
+    pub node_id: i16,
+    pub entry: Entry,
+    pub zone_id: Id,
+    pub player_data: PlayerData,
+}
+
+impl Handler<TransferPlayer> for Cluster {
+    type Result = ResponseActFuture<Self, Result<(), Error>>;
+
+    fn handle(&mut self, msg: TransferPlayer, _: &mut Self::Context) -> Self::Result {
+        let character_id = msg.entry.character_id;
+        let node_id = msg.node_id;
+
+        let mut player = BytesMut::new();
+        PlayerSnapshot::capture(&msg.player_data).encode(&mut player);
+
+        let message = Kind::Transfer(Transfer {
+            account_id: msg.entry.account_id,
+            character_id: msg.entry.character_id,
+            zone_id: msg.zone_id,
+            player: player.freeze(),
+        });
+
+        Box::pin(self.connect(node_id).then(move |res, act, _| {
+            async move {
+                let response = request(&res?, message.into()).await?;
+
+                match response.kind {
+                    Some(Kind::TransferResult(result)) if result.accepted => Ok(()),
+                    Some(Kind::TransferResult(_)) => Err(Error::TransferRefused),
+                    _ => Err(Error::UnexpectedMessage),
+                }
+            }
+            .into_actor(act)
+        })
+        .map(move |res, act, _| {
+            // Relayed to its new node from now on, without waiting for the registry.
+            if res.is_ok() {
+                act.characters.insert(character_id, node_id);
+            }
+            res
+        }))
+    }
+}
@@ -1,13 +1,26 @@
+mod accept_transfer;
 mod new_player;
 mod new_zone;
+mod player_leave;
+mod send_to_character;
 
+pub use accept_transfer::AcceptTransfer;
 pub use new_player::NewPlayer;
 pub use new_zone::NewZone;
+pub use player_leave::PlayerLeave;
+pub use send_to_character::{send_to_character, SendToCharacter};
 
+use crate::net::cluster::{Cluster, FindZone, Locate, TransferPlayer};
 use crate::net::region::Region;
-use crate::net::zone::Zone;
+use crate::net::session::{Priority, Session};
+use crate::net::zone::{self, Zone};
+use crate::player::PlayerData;
 use actix::prelude::*;
-use std::collections::HashMap;
+use protocol::game::auth::{login, login_result, LoginResult};
+use protocol::game::net::ServerTransfer;
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+use tracing::{error, info};
 use util::id::Id;
 
 #[derive(Default)]
@@ -16,6 +29,134 @@ pub struct Gateway {
     regions: HashMap<Id, Region>,
 
     character_zones: HashMap<Id, Id>,
+    /// The character in the game of each account.
+    account_characters: HashMap<Id, Id>,
+    /// Accounts logging in, reserved until their players are routed so that logins racing them
+    /// are refused.
+    reserved_accounts: HashSet<Id>,
+    /// Players handed over by other nodes, waiting for their clients to reconnect.
+    pending_transfers: HashMap<Id, PendingTransfer>,
+}
+
+struct PendingTransfer {
+    account_id: Id,
+    zone_id: Id,
+    player_data: PlayerData,
+    expire_at: Instant,
+}
+
+impl Gateway {
+    /// Send the player to the zone, either on this node or on the node owning it. The client is
+    /// told it is in only once the zone is found.
+    fn route(
+        &mut self,
+        session: Session,
+        player_data: PlayerData,
+        zone_id: Id,
+        ctx: &mut Context<Self>,
+    ) {
+        let entry = session.entry;
+
+        if let Some(zone) = self.zones.get(&zone_id) {
+            accept(&session);
+            self.reserved_accounts.remove(&entry.account_id);
+            self.character_zones.insert(entry.character_id, zone_id);
+            self.account_characters.insert(entry.account_id, entry.character_id);
+            Cluster::from_registry().do_send(Locate {
+                character_id: entry.character_id,
+                present: true,
+            });
+            zone.do_send(zone::PlayerTransfer { session, player_data });
+            return;
+        }
+
+        if !Cluster::is_enabled() {
+            error!("{}: Zone {} not found", session, zone_id);
+            self.reserved_accounts.remove(&entry.account_id);
+            refuse(session, login_result::Error::Internal);
+            return;
+        }
+
+        ctx.spawn(async move {
+            let cluster = Cluster::from_registry();
+            let remote = cluster
+                .send(FindZone { zone_id })
+                .await?
+                .ok_or(crate::net::cluster::Error::UnknownZone(zone_id))?;
+
+            cluster.send(TransferPlayer {
+                node_id: remote.node_id,
+                entry,
+                zone_id,
+                player_data,
+            }).await??;
+
+            Ok::<_, crate::net::cluster::Error>(remote)
+        }
+        .into_actor(self)
+        .map(move |res, act, _| {
+            act.reserved_accounts.remove(&entry.account_id);
+
+            match res {
+                Ok(remote) => {
+                    info!("{}: Transferred to node {}", session, remote.node_id);
+
+                    act.forget(entry.account_id, entry.character_id);
+                    accept(&session);
+                    session.send_with(&ServerTransfer {
+                        address: remote.game_address,
+                        zone_id,
+                    }, Priority::Critical);
+
+                    // The client reconnects to the owning node by itself.
+                    session.stop();
+                }
+                Err(e) => {
+                    error!("{}: Failed to transfer to zone {}: {}", session, zone_id, e);
+                    refuse(session, login_result::Error::Internal);
+                }
+            }
+        }));
+    }
+
+    fn zone_of(&self, character_id: Id) -> Option<Addr<Zone>> {
+        self.character_zones
+            .get(&character_id)
+            .and_then(|zone_id| self.zones.get(zone_id))
+            .cloned()
+    }
+
+    fn forget(&mut self, account_id: Id, character_id: Id) {
+        if self.character_zones.remove(&character_id).is_some() {
+            Cluster::from_registry().do_send(Locate { character_id, present: false });
+        }
+        if self.account_characters.get(&account_id) == Some(&character_id) {
+            self.account_characters.remove(&account_id);
+        }
+    }
+}
+
+/// Tell the client it is in, along with the compression negotiated.
+pub fn accept(session: &Session) {
+    let compression = match session.compression() {
+        Some(_) => login::Compression::Lz4,
+        None => login::Compression::None,
+    };
+
+    session.send_with(&LoginResult {
+        compression: compression.into(),
+        ..Default::default()
+    }, Priority::Critical);
+}
+
+fn refuse(session: Session, error: login_result::Error) {
+    error!("{}: Login refused: {:?}", session, error);
+
+    session.send_with(&LoginResult {
+        error: Some(error.into()),
+        ..Default::default()
+    }, Priority::Critical);
+    session.stop();
 }
 
 impl Actor for Gateway {
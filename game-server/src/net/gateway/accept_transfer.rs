@@ -0,0 +1,40 @@
+use super::{Gateway, PendingTransfer};
+use crate::config;
+use crate::net::session::Entry;
+use crate::player::PlayerData;
+use actix::prelude::*;
+use std::time::Instant;
+use tracing::info;
+use util::id::Id;
+
+/// A player handed over by another node. Returns `false` if the zone is not on this node.
+#[derive(actix::Message)]
+#[rtype(result = "bool")]
+pub struct AcceptTransfer {
+    pub entry: Entry,
+    pub zone_id: Id,
+    pub player_data: PlayerData,
+}
+
+impl Handler<AcceptTransfer> for Gateway {
+    type Result = bool;
+
+    fn handle(&mut self, msg: AcceptTransfer, _: &mut Self::Context) -> Self::Result {
+        if !self.zones.contains_key(&msg.zone_id) {
+            return false;
+        }
+
+        let now = Instant::now();
+        self.pending_transfers.retain(|_, transfer| transfer.expire_at > now);
+
+        info!("{}: Transfer to zone {} accepted", msg.entry, msg.zone_id);
+        self.pending_transfers.insert(msg.entry.character_id, PendingTransfer {
+            account_id: msg.entry.account_id,
+            zone_id: msg.zone_id,
+            player_data: msg.player_data,
+            expire_at: now + config!(net).cluster.transfer_timeout,
+        });
+
+        true
+    }
+}
@@ -1,11 +1,14 @@
-use actix::{ActorFutureExt, AsyncContext, Handler, WrapFuture};
+use actix::{ActorFutureExt, AsyncContext, Context, Handler, WrapFuture};
+use std::time::Instant;
 use tracing::{error, info};
 
-use super::Gateway;
+use super::{refuse, Gateway, PendingTransfer};
+use crate::config;
+use crate::config::auth::DuplicateLogin;
 use crate::net::session::Session;
-use crate::net::zone;
+use crate::net::zone::{Kick, Reattach, ReattachResult};
 use crate::player::PlayerData;
-use protocol::game::auth::login;
+use protocol::game::auth::{login, login_result};
 
 #[derive(actix::Message)]
 #[rtype(result = "()")]
@@ -18,21 +21,122 @@ impl Handler<NewPlayer> for Gateway {
     type Result = ();
 
     fn handle(&mut self, msg: NewPlayer, ctx: &mut Self::Context) -> Self::Result {
+        let session = msg.session;
+
+        let transfer = match msg.login_kind {
+            login::Kind::Enter => None,
+            login::Kind::Transfer => {
+                let now = Instant::now();
+                self.pending_transfers.retain(|_, transfer| transfer.expire_at > now);
+
+                match self.pending_transfers.remove(&session.entry.character_id) {
+                    Some(transfer) if transfer.account_id == session.entry.account_id => Some(transfer),
+                    _ => {
+                        refuse(session, login_result::Error::InvalidCharacter);
+                        return;
+                    }
+                }
+            }
+        };
+
+        // Held until the player is routed or refused; a login racing it is refused as a duplicate.
+        if !self.reserved_accounts.insert(session.entry.account_id) {
+            refuse(session, login_result::Error::Duplicated);
+            return;
+        }
+
+        self.enter(session, transfer, ctx);
+    }
+}
+
+impl Gateway {
+    /// Apply the duplicate login policy, then load the player, or take it from the transfer.
+    fn enter(&mut self, session: Session, transfer: Option<PendingTransfer>, ctx: &mut Context<Self>) {
+        let entry = session.entry;
+        let force = config!(auth).login.duplicate == DuplicateLogin::KickExisting;
+
+        // Another character of the account is in the game.
+        let previous = self.account_characters
+            .get(&entry.account_id)
+            .copied()
+            .filter(|&character_id| character_id != entry.character_id);
+        if let Some(previous) = previous {
+            if let Some(zone) = self.zone_of(previous) {
+                ctx.spawn(async move {
+                    zone.send(Kick { character_id: previous, force }).await
+                }
+                .into_actor(self)
+                .map(move |res, act, ctx| {
+                    match res {
+                        Ok(true) => {
+                            act.forget(entry.account_id, previous);
+                            act.enter(session, transfer, ctx);
+                        }
+                        Ok(false) => act.release(session, login_result::Error::Duplicated),
+                        Err(e) => {
+                            error!("Failed to kick character {}: {}", previous, e);
+                            act.release(session, login_result::Error::Internal);
+                        }
+                    }
+                }));
+                return;
+            }
+
+            self.forget(entry.account_id, previous);
+        }
+
+        // The character is still in the game, either linkdead or connected.
+        let Some(zone) = self.zone_of(entry.character_id) else {
+            match transfer {
+                Some(transfer) => self.route(session, transfer.player_data, transfer.zone_id, ctx),
+                None => self.load(session, ctx),
+            }
+            return;
+        };
+
+        // Handed over by another node while still here, which is never expected.
+        if transfer.is_some() {
+            self.release(session, login_result::Error::Duplicated);
+            return;
+        }
+
         ctx.spawn(async move {
-            let session = msg.session;
-            let player_data = match msg.login_kind {
-                login::Kind::Enter => PlayerData::load(&session.entry).await?,
-                login::Kind::Transfer => todo!(),
-            };
+            zone.send(Reattach { session, force }).await
+        }
+        .into_actor(self)
+        .map(move |res, act, ctx| {
+            match res {
+                Ok(ReattachResult::Reattached) => {
+                    act.reserved_accounts.remove(&entry.account_id);
+                }
+                Ok(ReattachResult::Connected(session)) => {
+                    act.release(session, login_result::Error::Duplicated);
+                }
+                Ok(ReattachResult::NotFound(session)) => {
+                    info!("{}: Nothing to reattach, loading", session);
+                    act.load(session, ctx);
+                }
+                Err(e) => {
+                    error!("Failed to reattach: {}", e);
+                    act.reserved_accounts.remove(&entry.account_id);
+                }
+            }
+        }));
+    }
 
-            Ok::<(Session, PlayerData), db::Error>((session, player_data))
+    fn load(&mut self, session: Session, ctx: &mut Context<Self>) {
+        ctx.spawn(async move {
+            let player_data = PlayerData::load(&session.entry).await;
+
+            (session, player_data)
         }
         .into_actor(self)
-        .then(|res, act, _| {
-            let (session, player_data) = match res {
+        .then(|(session, res), act, ctx| {
+            let player_data = match res {
                 Ok(data) => data,
                 Err(e) => {
                     error!("Failed to load player data: {}", e);
+                    act.release(session, login_result::Error::Internal);
                     return actix::fut::ready(());
                 }
             };
@@ -42,11 +146,15 @@ impl Handler<NewPlayer> for Gateway {
             // );
 
             //TODO: Find the player's last zone
-            act.character_zones.insert(session.entry.character_id, 0);
-            let default_zone = act.zones.get(&0).unwrap();
-            default_zone.do_send(zone::PlayerTransfer { session, player_data });
+            act.route(session, player_data, 0, ctx);
 
             actix::fut::ready(())
         }));
     }
+
+    /// Refuse the login, releasing the account for the next one.
+    fn release(&mut self, session: Session, error: login_result::Error) {
+        self.reserved_accounts.remove(&session.entry.account_id);
+        refuse(session, error);
+    }
 }
@@ -0,0 +1,19 @@
+use super::Gateway;
+use actix::prelude::*;
+use util::id::Id;
+
+/// A player despawned from its zone, after leaving or failing to reconnect in time.
+#[derive(actix::Message)]
+#[rtype(result = "()")]
+pub struct PlayerLeave {
+    pub account_id: Id,
+    pub character_id: Id,
+}
+
+impl Handler<PlayerLeave> for Gateway {
+    type Result = ();
+
+    fn handle(&mut self, msg: PlayerLeave, _: &mut Self::Context) -> Self::Result {
+        self.forget(msg.account_id, msg.character_id);
+    }
+}
@@ -0,0 +1,53 @@
+use super::Gateway;
+use crate::net::cluster::{Cluster, Relay};
+use crate::net::zone::Deliver;
+use actix::prelude::*;
+use bytes::Bytes;
+use protocol::game::{encode, Protocol};
+use util::id::Id;
+
+/// Send an encoded protocol to a character, wherever the character is in the cluster.
+#[derive(actix::Message)]
+#[rtype(result = "()")]
+pub struct SendToCharacter {
+    pub character_id: Id,
+    pub protocol: Bytes,
+    /// Relay to the node of the character if it is not on this node.
+    pub relay: bool,
+}
+
+/// Send a protocol to a character wherever it is in the cluster, e.g. party, guild or chat
+/// messages. Dropped if the character is not in the game.
+pub fn send_to_character(character_id: Id, protocol: &(impl prost::Message + Protocol)) {
+    let Ok(protocol) = encode(protocol) else {
+        return;
+    };
+
+    Gateway::from_registry().do_send(SendToCharacter {
+        character_id,
+        protocol,
+        relay: true,
+    });
+}
+
+impl Handler<SendToCharacter> for Gateway {
+    type Result = ();
+
+    fn handle(&mut self, msg: SendToCharacter, _: &mut Self::Context) -> Self::Result {
+        let zone = self.character_zones
+            .get(&msg.character_id)
+            .and_then(|zone_id| self.zones.get(zone_id));
+
+        if let Some(zone) = zone {
+            zone.do_send(Deliver {
+                character_id: msg.character_id,
+                protocol: msg.protocol,
+            });
+        } else if msg.relay && Cluster::is_enabled() {
+            Cluster::from_registry().do_send(Relay {
+                character_id: msg.character_id,
+                protocol: msg.protocol,
+            });
+        }
+    }
+}
@@ -1,15 +1,30 @@
+mod egress;
+mod ingress;
+
+pub use egress::{EgressStats, Priority};
+
+use crate::character::{Character, Characters};
 use crate::config;
+use crate::net::gateway::{Gateway, PlayerLeave};
+use crate::net::session::egress::EgressQueue;
+use crate::net::session::ingress::{IngressLimiter, Verdict};
+use crate::net::zone::record::{Event, Recorder};
+use crate::world::time::Time;
+use actix::SystemService;
 use bevy_ecs::prelude::*;
 use bytes::{Buf, Bytes, BytesMut};
-use protocol::game::{encode, Header, IngressLocalProtocol, Protocol, ProtocolHandler};
-use quinn::{Connection, RecvStream, SendStream, WriteError};
+use protocol::game::net::Ping;
+use protocol::game::{encode_with, Compression, Header, IngressLocalProtocol, Protocol, ProtocolHandler, StreamId, STREAM_COUNT};
+use quinn::{Connection, ConnectionError, RecvStream, SendStream, WriteError};
 use std::fmt::{Display, Formatter};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 use tokio::io::AsyncReadExt;
-use tokio::sync::{broadcast, mpsc};
-use tracing::{error, info};
-use util::rate_limiter::RateLimiter;
+use tokio::sync::broadcast;
+use tracing::{error, info, warn};
+use util::rtt::RttEstimator;
 
 pub type EgressProtocol = Bytes;
 
@@ -26,13 +41,42 @@ pub struct Session {
 }
 
 struct SessionInner {
-    connection: Connection,
+    /// `None` if the session is detached from network.
+    connection: Option<Connection>,
+    /// Negotiated at login. Protocols are sent uncompressed without it.
+    compression: Option<Compression>,
     ingress_protocol_receiver: crossbeam_channel::Receiver<IngressLocalProtocol>,
-    egress_protocol_sender: mpsc::UnboundedSender<EgressProtocol>,
+    /// Shared by the receive tasks of every stream.
+    ingress_limiter: Mutex<IngressLimiter>,
+    /// One per stream, indexed by the stream id.
+    egress_queues: Vec<EgressQueue>,
 
     stop_signal_sender: broadcast::Sender<()>,
     receive_finished: AtomicBool,
     send_finished: AtomicBool,
+
+    latency: Mutex<Latency>,
+}
+
+#[derive(Default)]
+struct Latency {
+    rtt: RttEstimator,
+    missed_pongs: u8,
+    /// Pings not answered yet, by their timestamp, with the time they were sent.
+    outstanding_pings: VecDeque<(i64, Instant)>,
+}
+
+/// Unanswered pings remembered, when sessions are never kicked for missing pongs.
+const MAX_OUTSTANDING_PINGS: usize = 16;
+
+/// Round-trip time of a session, measured by pings and by the transport.
+#[derive(Debug, Clone, Copy)]
+pub struct Rtt {
+    /// Smoothed RTT of pings, including the time to handle them on both sides.
+    pub smoothed: Option<Duration>,
+    pub jitter: Duration,
+    /// RTT estimated by QUIC.
+    pub transport: Option<Duration>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -46,58 +90,124 @@ enum Error {
     #[error(transparent)]
     Write(#[from] WriteError),
 
-    #[error("Ingress protocols limit error: {0}")]
-    IngressProtocolsLimit(util::rate_limiter::Error),
+    #[error(transparent)]
+    Connection(#[from] ConnectionError),
+
+    #[error("Invalid stream: {0}")]
+    Stream(StreamId),
+
+    #[error("Kicked for abuse after {0} violations")]
+    Abuse(u64),
+
+    #[error("Ingress queue is full")]
+    IngressQueueFull,
 
-    #[error("Ingress bytes limit error: {0}")]
-    IngressBytesLimit(util::rate_limiter::Error),
+    #[error("Protocol too long: {0} bytes")]
+    ProtocolTooLong(u32),
+
+    #[error("Compressed protocol without compression negotiated")]
+    Compression,
 }
 
 impl Session {
     pub fn start(
         entry: Entry,
+        compression: Option<Compression>,
         connection: Connection,
         receive_stream: RecvStream,
         send_stream: SendStream,
     ) -> Self {
-        let (ingress_protocol_sender, ingress_protocol_receiver) = crossbeam_channel::unbounded();
-        let (egress_protocol_sender, egress_protocol_receiver) = mpsc::unbounded_channel();
+        let (ingress_protocol_sender, ingress_protocol_receiver) =
+            crossbeam_channel::bounded(config!(net).ingress.queue_capacity);
         let (stop_signal_sender, stop_signal_receiver) = broadcast::channel(1);
 
         let session = Self {
             entry,
             inner: Arc::new(SessionInner {
-                connection,
+                connection: Some(connection),
+                compression,
                 ingress_protocol_receiver,
-                egress_protocol_sender,
+                ingress_limiter: Mutex::new(IngressLimiter::new(entry)),
+                egress_queues: (0..STREAM_COUNT).map(|_| EgressQueue::new()).collect(),
                 stop_signal_sender,
                 receive_finished: AtomicBool::new(false),
                 send_finished: AtomicBool::new(false),
+                latency: Mutex::new(Latency::default()),
             }),
         };
 
         Self::start_receive(
             session.clone(),
+            0,
             receive_stream,
+            ingress_protocol_sender.clone(),
+            stop_signal_receiver.resubscribe(),
+        );
+        Self::start_accept_streams(
+            session.clone(),
             ingress_protocol_sender,
             stop_signal_receiver.resubscribe(),
         );
-        Self::start_send(
+
+        // Other streams are opened once they have something to send.
+        let mut send_stream = Some(send_stream);
+        for stream_id in 0..STREAM_COUNT {
+            Self::start_send(
+                session.clone(),
+                stream_id as StreamId,
+                send_stream.take(),
+                stop_signal_receiver.resubscribe(),
+            );
+        }
+        Self::start_ping(
             session.clone(),
-            send_stream,
-            egress_protocol_receiver,
             stop_signal_receiver.resubscribe(),
         );
 
         session
     }
 
+    /// Create a session not bound to any connection, e.g. for replaying a zone.
+    /// Protocols sent to it are discarded.
+    pub fn detached(entry: Entry) -> Self {
+        let (_, ingress_protocol_receiver) = crossbeam_channel::bounded(0);
+        let (stop_signal_sender, _) = broadcast::channel(1);
+        let egress_queues: Vec<_> = (0..STREAM_COUNT).map(|_| EgressQueue::new()).collect();
+        egress_queues.iter().for_each(EgressQueue::close);
+
+        Self {
+            entry,
+            inner: Arc::new(SessionInner {
+                connection: None,
+                compression: None,
+                ingress_protocol_receiver,
+                ingress_limiter: Mutex::new(IngressLimiter::new(entry)),
+                egress_queues,
+                stop_signal_sender,
+                receive_finished: AtomicBool::new(false),
+                send_finished: AtomicBool::new(false),
+                latency: Mutex::new(Latency::default()),
+            }),
+        }
+    }
+
+    /// Mark the session as finished, so that it is cleaned up on the next tick.
+    pub fn finish(&self) {
+        self.inner.receive_finished.store(true, Ordering::Relaxed);
+        self.inner.send_finished.store(true, Ordering::Relaxed);
+    }
+
     pub fn stop(&self) {
         _ = self.inner.stop_signal_sender.send(());
     }
 
+    /// Send a gameplay protocol. Same as `send_with` of `Priority::Reliable`.
     pub fn send(&self, protocol: &(impl prost::Message + Protocol)) {
-        let bytes = match encode(protocol) {
+        self.send_with(protocol, Priority::Reliable);
+    }
+
+    pub fn send_with(&self, protocol: &(impl prost::Message + Protocol), priority: Priority) {
+        let bytes = match encode_with(protocol, self.inner.compression) {
             Ok(bytes) => bytes,
             Err(e) => {
                 error!("{} failed to encode protocol: {}", self, e);
@@ -105,12 +215,94 @@ impl Session {
             }
         };
 
-        _ = self.inner.egress_protocol_sender.send(bytes);
+        self.push_egress(protocol.protocol_id(), bytes, priority);
+    }
+
+    /// Send a protocol already encoded, e.g. relayed from another node.
+    pub fn send_encoded(&self, protocol: EgressProtocol) {
+        if protocol.len() < Header::size() {
+            error!("{} tried to send a protocol without header", self);
+            return;
+        }
+
+        let Ok(header) = Header::decode(&protocol) else {
+            return;
+        };
+        self.push_egress(header.id, protocol, Priority::Reliable);
+    }
+
+    fn push_egress(&self, id: u16, protocol: EgressProtocol, priority: Priority) {
+        let stream_id = protocol::game::protocol_stream(id) as usize;
+        let Some(egress_queue) = self.inner.egress_queues.get(stream_id) else {
+            error!("{} tried to send protocol {} to unknown stream {}", self, id, stream_id);
+            return;
+        };
+
+        if let Err(e) = egress_queue.push(id, protocol, priority) {
+            warn!("{} is too slow, disconnecting: {}", self, e);
+            self.stop();
+        }
+    }
+
+    /// Rate limit violations of the protocols received from the client.
+    pub fn ingress_violations(&self) -> u64 {
+        self.inner.ingress_limiter.lock().unwrap().violations()
+    }
+
+    pub fn compression(&self) -> Option<Compression> {
+        self.inner.compression
+    }
+
+    /// Egress counters summed over every stream.
+    pub fn egress_stats(&self) -> EgressStats {
+        self.inner.egress_queues.iter().map(EgressQueue::stats).sum()
     }
 
+    /// Send a protocol already encoded as an unreliable datagram. One not fitting a datagram is
+    /// queued on its stream as cosmetic instead, to be superseded by the next one of its kind.
     pub fn send_datagram(&self, protocol: Bytes) {
-        if let Err(e) = self.inner.connection.send_datagram(protocol) {
-            error!("{} failed to send datagram: {}", self, e);
+        let Some(connection) = &self.inner.connection else {
+            return;
+        };
+
+        if connection.max_datagram_size().is_some_and(|max| protocol.len() <= max) {
+            if let Err(e) = connection.send_datagram(protocol) {
+                error!("{} failed to send datagram: {}", self, e);
+            }
+            return;
+        }
+
+        let Ok(header) = Header::decode(&protocol) else {
+            return;
+        };
+        self.push_egress(header.id, protocol, Priority::Cosmetic);
+    }
+
+    /// Record the pong of the ping sent at `timestamp`.
+    ///
+    /// The RTT is measured by the server clock. Pongs answering no outstanding ping are ignored.
+    pub fn on_pong(&self, timestamp: i64) {
+        let mut latency = self.inner.latency.lock().unwrap();
+        let Some(index) = latency.outstanding_pings
+            .iter()
+            .position(|(sent_timestamp, _)| *sent_timestamp == timestamp)
+        else {
+            return;
+        };
+
+        // Pings before the answered one are left unanswered for good.
+        let (_, sent_at) = latency.outstanding_pings.drain(..=index).last().unwrap();
+        latency.rtt.update(sent_at.elapsed());
+        latency.missed_pongs = 0;
+    }
+
+    pub fn rtt(&self) -> Rtt {
+        let latency = self.inner.latency.lock().unwrap();
+
+        Rtt {
+            smoothed: (latency.rtt.samples() > 0).then(|| latency.rtt.smoothed()),
+            jitter: latency.rtt.jitter(),
+            transport: self.inner.connection.as_ref().map(|connection| connection.rtt()),
         }
     }
 
@@ -120,6 +312,7 @@ impl Session {
 
     fn start_receive(
         session: Session,
+        stream_id: StreamId,
         stream: RecvStream,
         ingress_protocol_sender: crossbeam_channel::Sender<IngressLocalProtocol>,
         stop_signal_receiver: broadcast::Receiver<()>,
@@ -130,15 +323,11 @@ impl Session {
             ingress_protocol_sender: crossbeam_channel::Sender<IngressLocalProtocol>,
             mut stop_signal_receiver: broadcast::Receiver<()>,
         ) -> Result<(), Error> {
-            let mut ingress_protocols_limiter = config!(net).ingress.protocols_rate_limit
-                .map(|params| RateLimiter::new(params));
-            let mut ingress_bytes_limiter = config!(net).ingress.bytes_rate_limit
-                .map(|params| RateLimiter::new(params));
-
+            let max_protocol_length = config!(net).ingress.max_protocol_length;
             let mut buffer = BytesMut::with_capacity(8 * 1024);
 
             loop {
-                while buffer.len() < Header::size() {
+                while buffer.len() < Header::decoded_size(&buffer) {
                     tokio::select! {
                         biased;
                         res = stream.read_buf(&mut buffer) => {
@@ -151,8 +340,13 @@ impl Session {
                     }
                 }
 
-                let header = Header::decode(&mut buffer)?;
-                buffer.advance(Header::size());
+                let header = Header::decode(&buffer)?;
+                buffer.advance(Header::decoded_size(&buffer));
+
+                // Checked before reading the body, so that a client cannot make us buffer it.
+                if header.length > max_protocol_length {
+                    return Err(Error::ProtocolTooLong(header.length));
+                }
 
                 while buffer.len() < header.length as usize {
                     tokio::select! {
@@ -169,19 +363,37 @@ impl Session {
 
                 let body = buffer.split_to(header.length as usize).freeze();
 
-                if let Some(limiter) = ingress_protocols_limiter.as_mut() {
-                    limiter.check().map_err(Error::IngressProtocolsLimit)?;
-                }
-                if let Some(limiter) = ingress_bytes_limiter.as_mut() {
-                    limiter.check_with_value(body.len() as f32).map_err(Error::IngressBytesLimit)?;
+                let verdict = session.inner.ingress_limiter.lock().unwrap().check(header.id, body.len());
+                match verdict {
+                    Verdict::Accept => {}
+                    Verdict::Drop => continue,
+                    Verdict::Throttle(delay) => {
+                        // Stop reading, so that the flow control of QUIC slows the client down.
+                        tokio::time::sleep(delay).await;
+                        continue;
+                    }
+                    Verdict::Kick => {
+                        let violations = session.inner.ingress_limiter.lock().unwrap().violations();
+                        return Err(Error::Abuse(violations));
+                    }
                 }
 
+                // Clients compress only if negotiated, like the server.
+                let body = match (header.compressed, session.inner.compression) {
+                    (false, _) => body,
+                    (true, Some(_)) => protocol::game::decompress(&body, max_protocol_length as usize)?,
+                    (true, None) => return Err(Error::Compression),
+                };
+
                 match protocol::game::protocol_handler(header.id)? {
                     ProtocolHandler::Local => {
                         let protocol = protocol::game::decode_local(header.id, body)?;
-                        if let Err(crossbeam_channel::TrySendError::Disconnected(_))
-                            = ingress_protocol_sender.try_send(protocol) {
-                            break;
+                        match ingress_protocol_sender.try_send(protocol) {
+                            Ok(_) => {}
+                            Err(crossbeam_channel::TrySendError::Full(_)) => {
+                                return Err(Error::IngressQueueFull);
+                            }
+                            Err(crossbeam_channel::TrySendError::Disconnected(_)) => break,
                         }
                     }
                     ProtocolHandler::Global => {
@@ -201,25 +413,81 @@ impl Session {
                 ingress_protocol_sender,
                 stop_signal_receiver,
             ).await {
-                error!(error = %e, "receive task failed");
+                error!(error = %e, stream = stream_id, "receive task failed");
+
+                // The session cannot go on without the protocols lost.
+                session.stop();
             }
 
-            session.inner.receive_finished.store(true, Ordering::Relaxed);
+            if stream_id == 0 {
+                session.inner.receive_finished.store(true, Ordering::Relaxed);
+            }
+        });
+    }
+
+    /// Accept the uni-directional streams opened by the client, each starting with its id.
+    fn start_accept_streams(
+        session: Session,
+        ingress_protocol_sender: crossbeam_channel::Sender<IngressLocalProtocol>,
+        mut stop_signal_receiver: broadcast::Receiver<()>,
+    ) {
+        let Some(connection) = session.inner.connection.clone() else {
+            return;
+        };
+
+        tokio::spawn(async move {
+            loop {
+                let mut stream = tokio::select! {
+                    biased;
+                    Ok(_) = stop_signal_receiver.recv() => {
+                        break;
+                    }
+                    res = connection.accept_uni() => match res {
+                        Ok(stream) => stream,
+                        Err(_) => break,
+                    }
+                };
+
+                let session = session.clone();
+                let ingress_protocol_sender = ingress_protocol_sender.clone();
+                let stop_signal_receiver = stop_signal_receiver.resubscribe();
+
+                tokio::spawn(async move {
+                    let stream_id = match stream.read_u8().await {
+                        Ok(stream_id) if stream_id != 0 && (stream_id as usize) < STREAM_COUNT => stream_id,
+                        Ok(stream_id) => {
+                            error!("{} opened invalid stream: {}", session, Error::Stream(stream_id));
+                            session.stop();
+                            return;
+                        }
+                        Err(_) => return,
+                    };
+
+                    Self::start_receive(
+                        session,
+                        stream_id,
+                        stream,
+                        ingress_protocol_sender,
+                        stop_signal_receiver,
+                    );
+                });
+            }
         });
     }
 
     fn start_send(
         session: Session,
-        stream: SendStream,
-        egress_protocol_receiver: mpsc::UnboundedReceiver<EgressProtocol>,
+        stream_id: StreamId,
+        stream: Option<SendStream>,
         stop_signal_receiver: broadcast::Receiver<()>,
     ) {
         async fn do_send(
             session: &Session,
-            mut stream: SendStream,
-            mut egress_protocol_receiver: mpsc::UnboundedReceiver<EgressProtocol>,
+            stream_id: StreamId,
+            mut stream: Option<SendStream>,
             mut stop_signal_receiver: broadcast::Receiver<()>,
         ) -> Result<(), Error> {
+            let egress_queue = &session.inner.egress_queues[stream_id as usize];
             let mut protocols = Vec::with_capacity(16);
 
             loop {
@@ -228,43 +496,138 @@ impl Session {
                     Ok(_) = stop_signal_receiver.recv() => {
                         break;
                     }
-                    n = egress_protocol_receiver.recv_many(&mut protocols, 16) => {
+                    n = egress_queue.pop_many(&mut protocols, 16) => {
                         if n == 0 { break; }
                     }
                 }
 
-                for protocol in protocols.drain(..) {
-                    stream.write_all(&protocol[..]).await?;
-                }
+                write(session, stream_id, &mut stream, &mut protocols).await?;
             }
 
             // Ensure remaining protocols are sent before finishing.
-            egress_protocol_receiver.close();
-            while let Some(protocol) = egress_protocol_receiver.recv().await {
+            egress_queue.close();
+            egress_queue.drain(&mut protocols);
+            write(session, stream_id, &mut stream, &mut protocols).await?;
+
+            if let Some(mut stream) = stream {
+                _ = stream.finish();
+            }
+
+            Ok(())
+        }
+
+        async fn write(
+            session: &Session,
+            stream_id: StreamId,
+            stream: &mut Option<SendStream>,
+            protocols: &mut Vec<EgressProtocol>,
+        ) -> Result<(), Error> {
+            if protocols.is_empty() {
+                return Ok(());
+            }
+
+            if stream.is_none() {
+                let Some(connection) = &session.inner.connection else {
+                    protocols.clear();
+                    return Ok(());
+                };
+
+                let mut opened = connection.open_uni().await?;
+                opened.write_all(&[stream_id]).await?;
+                *stream = Some(opened);
+            }
+
+            let Some(stream) = stream.as_mut() else {
+                return Ok(());
+            };
+            for protocol in protocols.drain(..) {
                 stream.write_all(&protocol[..]).await?;
             }
 
-            _ = stream.finish();
-            
             Ok(())
         }
 
         tokio::spawn(async move {
             if let Err(e) = do_send(
                 &session,
+                stream_id,
                 stream,
-                egress_protocol_receiver,
                 stop_signal_receiver
             ).await {
-                error!(error = %e, "send task failed");
+                error!(error = %e, stream = stream_id, "send task failed");
+                session.stop();
+            }
+
+            if stream_id != 0 {
+                return;
             }
 
             session.inner.send_finished.store(true, Ordering::Relaxed);
-            session.inner.connection.close(0u32.into(), b"");
+            if let Some(connection) = &session.inner.connection {
+                connection.close(0u32.into(), b"");
+            }
         });
     }
 }
 
+impl Session {
+    /// Ping the client on a schedule, and kick it if it stops answering.
+    fn start_ping(session: Session, mut stop_signal_receiver: broadcast::Receiver<()>) {
+        let ping_interval = config!(net).session.ping_interval;
+        let max_missed_pongs = config!(net).session.max_missed_pongs;
+        if ping_interval.is_zero() {
+            return;
+        }
+
+        tokio::spawn(async move {
+            let start = tokio::time::Instant::now() + ping_interval;
+            let mut interval = tokio::time::interval_at(start, ping_interval);
+
+            loop {
+                tokio::select! {
+                    biased;
+                    Ok(_) = stop_signal_receiver.recv() => {
+                        break;
+                    }
+                    _ = interval.tick() => {}
+                }
+
+                let (missed_pongs, timestamp) = {
+                    let mut latency = session.inner.latency.lock().unwrap();
+                    let missed_pongs = latency.missed_pongs;
+                    latency.missed_pongs = missed_pongs.saturating_add(1);
+
+                    // The timestamp identifies the ping, so keep it unique.
+                    let mut timestamp = chrono::Utc::now().timestamp_millis();
+                    if let Some((last, _)) = latency.outstanding_pings.back() {
+                        timestamp = timestamp.max(last + 1);
+                    }
+                    if latency.outstanding_pings.len() >= MAX_OUTSTANDING_PINGS.max(max_missed_pongs as usize) {
+                        latency.outstanding_pings.pop_front();
+                    }
+                    latency.outstanding_pings.push_back((timestamp, Instant::now()));
+
+                    (missed_pongs, timestamp)
+                };
+                if max_missed_pongs > 0 && missed_pongs >= max_missed_pongs {
+                    warn!("{} missed {} pongs, kicking", session, missed_pongs);
+                    session.stop();
+                    break;
+                }
+
+                session.send_with(&Ping { timestamp }, Priority::Critical);
+            }
+        });
+    }
+}
+
+impl Rtt {
+    /// Best known RTT, preferring pings over the transport.
+    pub fn estimate(&self) -> Duration {
+        self.smoothed.or(self.transport).unwrap_or_default()
+    }
+}
+
 impl Display for Session {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -287,17 +650,45 @@ impl Display for Entry {
     }
 }
 
+/// A player who lost the connection, kept in the world until reconnecting or expiring.
+#[derive(Component)]
+pub struct Linkdead {
+    pub entry: Entry,
+    pub expire_tick: u64,
+}
+
+/// Ticks a linkdead player is kept in the world.
+#[derive(Resource)]
+pub struct ReconnectGrace {
+    pub ticks: u64,
+}
+
+impl ReconnectGrace {
+    pub fn new(tick_interval: std::time::Duration) -> Self {
+        let grace = config!(net).session.reconnect_grace;
+
+        Self {
+            ticks: (grace.as_nanos() / tick_interval.as_nanos().max(1)) as u64,
+        }
+    }
+}
+
 pub fn register(schedule: &mut Schedule) {
     schedule.add_systems((
         cleanup,
-    ));
+        expire_linkdead,
+    ).chain());
 }
 
 fn cleanup(
     mut commands: Commands,
-    mut query: Query<(Entity, &mut Session)>,
+    query: Query<(Entity, &Session, Has<Character>)>,
+    time: Res<Time>,
+    grace: Res<ReconnectGrace>,
+    mut characters: ResMut<Characters>,
+    mut recorder: Option<ResMut<Recorder>>,
 ) {
-    for (entity, session) in query.iter_mut() {
+    for (entity, session, has_character) in query.iter() {
         let receive_finished = session.inner.receive_finished.load(Ordering::Relaxed);
         let send_finished = session.inner.send_finished.load(Ordering::Relaxed);
 
@@ -305,9 +696,65 @@ fn cleanup(
             continue;
         }
 
+        session.stop();
+
+        // Players not in the world yet have nothing to resume.
+        if has_character && grace.ticks > 0 {
+            info!("{} is linkdead", *session);
+
+            if let Some(recorder) = recorder.as_mut() {
+                recorder.record(&Event::Detach { tick: time.ticks, entity: entity.to_bits() });
+            }
+
+            commands.entity(entity)
+                .remove::<Session>()
+                .insert(Linkdead {
+                    entry: session.entry,
+                    expire_tick: time.ticks.saturating_add(grace.ticks),
+                });
+            continue;
+        }
+
         info!("{} is cleaned up", *session);
 
-        session.stop();
-        commands.entity(entity).despawn();
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.record(&Event::Leave { tick: time.ticks, entity: entity.to_bits() });
+        }
+
+        leave(&mut commands, &mut characters, entity, session.entry);
+    }
+}
+
+fn expire_linkdead(
+    mut commands: Commands,
+    query: Query<(Entity, &Linkdead)>,
+    time: Res<Time>,
+    mut characters: ResMut<Characters>,
+    mut recorder: Option<ResMut<Recorder>>,
+) {
+    for (entity, linkdead) in query.iter() {
+        if time.ticks < linkdead.expire_tick {
+            continue;
+        }
+
+        info!("{} has not reconnected in time", linkdead.entry);
+
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.record(&Event::Leave { tick: time.ticks, entity: entity.to_bits() });
+        }
+
+        leave(&mut commands, &mut characters, entity, linkdead.entry);
     }
 }
+
+fn leave(commands: &mut Commands, characters: &mut Characters, entity: Entity, entry: Entry) {
+    if characters.map.get(&entry.character_id) == Some(&entity) {
+        characters.map.remove(&entry.character_id);
+    }
+
+    commands.entity(entity).despawn();
+    Gateway::from_registry().do_send(PlayerLeave {
+        account_id: entry.account_id,
+        character_id: entry.character_id,
+    });
+}
@@ -0,0 +1,297 @@
+use crate::config;
+use crate::config::net::EgressOverflow;
+use bytes::Bytes;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+/// Priority class of an egress protocol. Higher classes are written first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// Handshake and connection control. Never dropped.
+    Critical,
+    /// Gameplay protocols the client relies on. Dropped only if `egress.overflow` says so.
+    Reliable,
+    /// Protocols superseded by the next one of the same kind. Coalesced, and dropped under
+    /// pressure.
+    Cosmetic,
+}
+
+/// Counters of an egress queue.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EgressStats {
+    pub queued_protocols: usize,
+    pub queued_bytes: usize,
+    pub sent: u64,
+    /// Cosmetic protocols replaced by a newer one of the same kind.
+    pub coalesced: u64,
+    pub dropped: u64,
+    pub overflows: u64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Egress queue overflow: {protocols} protocols, {bytes} bytes")]
+    Overflow { protocols: usize, bytes: usize },
+}
+
+/// Bounded egress queue of a session stream, with one lane per priority class.
+pub struct EgressQueue {
+    state: Mutex<State>,
+    notify: Notify,
+    max_protocols: usize,
+    max_bytes: usize,
+    overflow: EgressOverflow,
+}
+
+#[derive(Default)]
+struct State {
+    lanes: [VecDeque<Entry>; 3],
+    stats: EgressStats,
+    closed: bool,
+}
+
+struct Entry {
+    /// Protocol id, to coalesce cosmetic protocols of the same kind.
+    id: u16,
+    bytes: Bytes,
+}
+
+impl std::iter::Sum for EgressStats {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |sum, stats| Self {
+            queued_protocols: sum.queued_protocols + stats.queued_protocols,
+            queued_bytes: sum.queued_bytes + stats.queued_bytes,
+            sent: sum.sent + stats.sent,
+            coalesced: sum.coalesced + stats.coalesced,
+            dropped: sum.dropped + stats.dropped,
+            overflows: sum.overflows + stats.overflows,
+        })
+    }
+}
+
+impl EgressQueue {
+    pub fn new() -> Self {
+        let egress_config = &config!(net).egress;
+
+        Self::with_limits(egress_config.max_protocols, egress_config.max_bytes, egress_config.overflow)
+    }
+
+    fn with_limits(max_protocols: usize, max_bytes: usize, overflow: EgressOverflow) -> Self {
+        Self {
+            state: Mutex::new(State::default()),
+            notify: Notify::new(),
+            max_protocols,
+            max_bytes,
+            overflow,
+        }
+    }
+
+    /// Queue an encoded protocol. An error means the session must be disconnected.
+    pub fn push(&self, id: u16, bytes: Bytes, priority: Priority) -> Result<(), Error> {
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            return Ok(());
+        }
+
+        let entry = Entry { id, bytes };
+        match priority {
+            Priority::Cosmetic => {
+                let state = &mut *state;
+                let lane = &mut state.lanes[priority as usize];
+                if let Some(queued) = lane.iter_mut().find(|queued| queued.id == id) {
+                    state.stats.queued_bytes += entry.bytes.len();
+                    state.stats.queued_bytes -= std::mem::replace(queued, entry).bytes.len();
+                    state.stats.coalesced += 1;
+                    return Ok(());
+                }
+
+                // Under pressure once half full, so that room is left for the other classes.
+                if !self.fits(&state, &entry, 2) {
+                    state.stats.dropped += 1;
+                    return Ok(());
+                }
+            }
+            Priority::Critical | Priority::Reliable => {
+                while !self.fits(&state, &entry, 1) && state.evict_cosmetic() {}
+
+                if !self.fits(&state, &entry, 1) {
+                    state.stats.overflows += 1;
+                    if priority == Priority::Reliable && self.overflow == EgressOverflow::Drop {
+                        state.stats.dropped += 1;
+                        return Ok(());
+                    }
+
+                    return Err(Error::Overflow {
+                        protocols: state.stats.queued_protocols,
+                        bytes: state.stats.queued_bytes,
+                    });
+                }
+            }
+        }
+
+        state.stats.queued_protocols += 1;
+        state.stats.queued_bytes += entry.bytes.len();
+        state.lanes[priority as usize].push_back(entry);
+        drop(state);
+
+        self.notify.notify_one();
+        Ok(())
+    }
+
+    /// Take up to `max` protocols, highest priority first. Waits while the queue is empty,
+    /// and returns nothing once it is closed and drained.
+    pub async fn pop_many(&self, protocols: &mut Vec<Bytes>, max: usize) -> usize {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                let n = state.pop_many(protocols, max);
+                if n > 0 || state.closed {
+                    return n;
+                }
+            }
+
+            self.notify.notified().await;
+        }
+    }
+
+    /// Take the remaining protocols without waiting.
+    pub fn drain(&self, protocols: &mut Vec<Bytes>) -> usize {
+        self.state.lock().unwrap().pop_many(protocols, usize::MAX)
+    }
+
+    /// Stop accepting protocols. Queued ones can still be taken.
+    pub fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.notify.notify_one();
+    }
+
+    pub fn stats(&self) -> EgressStats {
+        self.state.lock().unwrap().stats
+    }
+
+    /// A protocol always fits an empty queue, even if larger than `max_bytes`.
+    fn fits(&self, state: &State, entry: &Entry, divisor: usize) -> bool {
+        if state.stats.queued_protocols == 0 {
+            return true;
+        }
+
+        state.stats.queued_protocols < self.max_protocols / divisor
+            && state.stats.queued_bytes + entry.bytes.len() <= self.max_bytes / divisor
+    }
+}
+
+impl State {
+    fn pop_many(&mut self, protocols: &mut Vec<Bytes>, max: usize) -> usize {
+        let mut n = 0;
+
+        for lane in &mut self.lanes {
+            while n < max {
+                let Some(entry) = lane.pop_front() else {
+                    break;
+                };
+
+                self.stats.queued_protocols -= 1;
+                self.stats.queued_bytes -= entry.bytes.len();
+                protocols.push(entry.bytes);
+                n += 1;
+            }
+        }
+
+        self.stats.sent += n as u64;
+        n
+    }
+
+    /// Drop the oldest cosmetic protocol to make room. `false` if there is none.
+    fn evict_cosmetic(&mut self) -> bool {
+        let Some(entry) = self.lanes[Priority::Cosmetic as usize].pop_front() else {
+            return false;
+        };
+
+        self.stats.queued_protocols -= 1;
+        self.stats.queued_bytes -= entry.bytes.len();
+        self.stats.dropped += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn protocol(tag: u8) -> Bytes {
+        Bytes::from(vec![tag; 4])
+    }
+
+    fn drain(queue: &EgressQueue) -> Vec<Bytes> {
+        let mut protocols = Vec::new();
+        queue.drain(&mut protocols);
+        protocols
+    }
+
+    #[test]
+    fn pops_by_priority() {
+        let queue = EgressQueue::with_limits(16, 1024, EgressOverflow::Disconnect);
+        queue.push(1, protocol(3), Priority::Cosmetic).unwrap();
+        queue.push(2, protocol(2), Priority::Reliable).unwrap();
+        queue.push(3, protocol(1), Priority::Critical).unwrap();
+        queue.push(4, protocol(4), Priority::Reliable).unwrap();
+
+        assert_eq!(drain(&queue), [protocol(1), protocol(2), protocol(4), protocol(3)]);
+        assert_eq!(queue.stats().sent, 4);
+        assert_eq!(queue.stats().queued_protocols, 0);
+    }
+
+    #[test]
+    fn coalesces_cosmetic_of_same_id() {
+        let queue = EgressQueue::with_limits(16, 1024, EgressOverflow::Disconnect);
+        queue.push(1, protocol(1), Priority::Cosmetic).unwrap();
+        queue.push(2, protocol(2), Priority::Cosmetic).unwrap();
+        queue.push(1, protocol(3), Priority::Cosmetic).unwrap();
+
+        let stats = queue.stats();
+        assert_eq!(stats.coalesced, 1);
+        assert_eq!(stats.queued_protocols, 2);
+        assert_eq!(stats.queued_bytes, 8);
+        assert_eq!(drain(&queue), [protocol(3), protocol(2)]);
+    }
+
+    #[test]
+    fn drops_cosmetic_past_half() {
+        let queue = EgressQueue::with_limits(4, 1024, EgressOverflow::Disconnect);
+        for id in 0..4 {
+            queue.push(id, protocol(id as u8), Priority::Cosmetic).unwrap();
+        }
+
+        assert_eq!(queue.stats().dropped, 2);
+        assert_eq!(drain(&queue), [protocol(0), protocol(1)]);
+    }
+
+    #[test]
+    fn evicts_cosmetic_then_overflows() {
+        let queue = EgressQueue::with_limits(2, 1024, EgressOverflow::Disconnect);
+        queue.push(1, protocol(1), Priority::Cosmetic).unwrap();
+        queue.push(2, protocol(2), Priority::Reliable).unwrap();
+        queue.push(3, protocol(3), Priority::Reliable).unwrap();
+        assert_eq!(queue.stats().dropped, 1);
+
+        assert!(matches!(
+            queue.push(4, protocol(4), Priority::Critical),
+            Err(Error::Overflow { protocols: 2, .. })
+        ));
+        assert_eq!(queue.stats().overflows, 1);
+        assert_eq!(drain(&queue), [protocol(2), protocol(3)]);
+    }
+
+    #[test]
+    fn drops_reliable_on_overflow_if_configured() {
+        let queue = EgressQueue::with_limits(1, 1024, EgressOverflow::Drop);
+        queue.push(1, protocol(1), Priority::Reliable).unwrap();
+        queue.push(2, protocol(2), Priority::Reliable).unwrap();
+
+        let stats = queue.stats();
+        assert_eq!((stats.overflows, stats.dropped), (1, 1));
+        assert!(queue.push(3, protocol(3), Priority::Critical).is_err());
+        assert_eq!(drain(&queue), [protocol(1)]);
+    }
+}
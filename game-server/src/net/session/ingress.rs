@@ -0,0 +1,154 @@
+use crate::config;
+use crate::net::session::Entry;
+use protocol::game::{ProtocolId, RateLimitKey};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+use util::abuse::AbuseScore;
+use util::rate_limiter::{Params, RateLimiter};
+
+/// Rate limits of the protocols received from a session, and the abuse score raised by
+/// violating them. Shared by the receive tasks of every stream.
+pub struct IngressLimiter {
+    entry: Entry,
+    protocols: Option<RateLimiter>,
+    bytes: Option<RateLimiter>,
+    /// Declared by the protocol schema, created on the first protocol of each key.
+    limits: HashMap<RateLimitKey, RateLimiter>,
+
+    abuse: AbuseScore,
+    level: Level,
+    violations: u64,
+}
+
+/// Response to the abuse score of a session, in escalating order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Drop,
+    Warn,
+    Throttle,
+    Kick,
+}
+
+/// What to do with a received protocol.
+pub enum Verdict {
+    Accept,
+    Drop,
+    /// Drop, and pause reading from the session.
+    Throttle(Duration),
+    Kick,
+}
+
+enum Violation {
+    Protocols(util::rate_limiter::Error),
+    Bytes(util::rate_limiter::Error),
+    Protocol(ProtocolId, util::rate_limiter::Error),
+}
+
+impl IngressLimiter {
+    pub fn new(entry: Entry) -> Self {
+        let ingress_config = &config!(net).ingress;
+
+        Self {
+            entry,
+            protocols: ingress_config.protocols_rate_limit.map(RateLimiter::new),
+            bytes: ingress_config.bytes_rate_limit.map(RateLimiter::new),
+            limits: HashMap::new(),
+            abuse: AbuseScore::new(ingress_config.abuse.decay_per_second),
+            level: Level::Drop,
+            violations: 0,
+        }
+    }
+
+    pub fn check(&mut self, id: ProtocolId, length: usize) -> Verdict {
+        let (violation, penalty) = match self.check_limits(id, length) {
+            Ok(()) => return Verdict::Accept,
+            Err(o) => o,
+        };
+
+        let abuse_config = &config!(net).ingress.abuse;
+        let score = self.abuse.add(penalty, Instant::now());
+        let level = Level::of(score);
+        self.violations += 1;
+
+        debug!(
+            account_id = self.entry.account_id,
+            score,
+            "{} violated {}",
+            self.entry,
+            violation,
+        );
+
+        // Logged once per escalation, as a flooding client violates on every protocol.
+        if level > self.level && level >= Level::Warn {
+            warn!(
+                account_id = self.entry.account_id,
+                score,
+                violations = self.violations,
+                "{} escalated to {:?} by {}",
+                self.entry,
+                level,
+                violation,
+            );
+        }
+        self.level = level;
+
+        match level {
+            Level::Drop | Level::Warn => Verdict::Drop,
+            Level::Throttle => Verdict::Throttle(abuse_config.throttle),
+            Level::Kick => Verdict::Kick,
+        }
+    }
+
+    pub fn violations(&self) -> u64 {
+        self.violations
+    }
+
+    fn check_limits(&mut self, id: ProtocolId, length: usize) -> Result<(), (Violation, f32)> {
+        let penalty = config!(net).ingress.abuse.penalty;
+
+        if let Some(limiter) = self.protocols.as_mut() {
+            limiter.check().map_err(|e| (Violation::Protocols(e), penalty))?;
+        }
+        if let Some(limiter) = self.bytes.as_mut() {
+            limiter.check_with_value(length as f32).map_err(|e| (Violation::Bytes(e), penalty))?;
+        }
+
+        if let Some(limit) = protocol::game::protocol_rate_limit(id) {
+            self.limits
+                .entry(limit.key)
+                .or_insert_with(|| RateLimiter::new(Params::new(limit.rate, limit.capacity)))
+                .check()
+                .map_err(|e| (Violation::Protocol(id, e), limit.penalty))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Level {
+    fn of(score: f32) -> Self {
+        let abuse_config = &config!(net).ingress.abuse;
+
+        if score >= abuse_config.kick_score {
+            Level::Kick
+        } else if score >= abuse_config.throttle_score {
+            Level::Throttle
+        } else if score >= abuse_config.warn_score {
+            Level::Warn
+        } else {
+            Level::Drop
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::Protocols(e) => write!(f, "protocols rate limit: {}", e),
+            Violation::Bytes(e) => write!(f, "bytes rate limit: {}", e),
+            Violation::Protocol(id, e) => write!(f, "rate limit of protocol {}: {}", id, e),
+        }
+    }
+}
@@ -1,18 +1,34 @@
+pub mod deliver;
+pub mod inspect;
+pub mod kick;
 pub mod player_transfer;
+pub mod reattach;
+pub mod record;
+pub mod replay;
 
+pub use deliver::Deliver;
+pub use inspect::Inspect;
+pub use kick::Kick;
 pub use player_transfer::PlayerTransfer;
+pub use reattach::{Reattach, ReattachResult};
 
 use crate::config;
-use crate::net::session::Session;
+use crate::net::session::{EgressStats, ReconnectGrace, Session};
+use crate::net::zone::record::{Event, Header, Recorder};
+use crate::world::random::Random;
+use crate::world::rewind::RewindWindow;
 use crate::world::time::Time;
 use actix::prelude::*;
 use bevy_ecs::prelude::*;
 use protocol::game::IngressLocalProtocol;
+use rand::Rng;
 use std::collections::VecDeque;
 use std::fmt;
 use std::fmt::Formatter;
-use std::time::Instant;
-use tracing::info;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
 use util::id::Id;
 use util::interval_counter::IntervalCounter;
 
@@ -20,32 +36,122 @@ pub struct Zone {
     pub id: Id,
 
     pub world: World,
-    pub schedule: Schedule,
+    stages: Vec<Stage>,
+
+    accumulator: Duration,
+    last_update: Instant,
+    last_report: Instant,
     fps: IntervalCounter,
+    tick_cost: IntervalCounter,
+    overruns: u64,
+    load: Arc<ZoneLoad>,
 
     protocols_buffer: VecDeque<(Entity, Session, IngressLocalProtocol)>
 }
 
+/// Load of a zone, shared with the pool placing zones on threads.
+#[derive(Default)]
+pub struct ZoneLoad {
+    tick_cost_micros: AtomicU64,
+}
+
+/// A named group of systems, timed separately from the others.
+struct Stage {
+    name: &'static str,
+    schedule: Schedule,
+    cost: IntervalCounter,
+}
+
 impl Zone {
     pub fn new(id: i64) -> Self {
+        Self::with_seed(id, rand::rng().random())
+    }
+
+    pub fn with_seed(id: i64, seed: u64) -> Self {
+        let now = Instant::now();
+
         Zone {
             id,
-            world: new_world(),
-            schedule: new_schedule(),
+            world: new_world(seed),
+            stages: new_stages(),
+            accumulator: Duration::ZERO,
+            last_update: now,
+            last_report: now,
             fps: IntervalCounter::new(128),
+            tick_cost: IntervalCounter::new(128),
+            overruns: 0,
+            load: Arc::new(ZoneLoad::default()),
             protocols_buffer: VecDeque::with_capacity(128),
         }
     }
 
+    pub fn load(&self) -> Arc<ZoneLoad> {
+        self.load.clone()
+    }
+
+    /// Run as many fixed steps as the real time elapsed since the last update.
+    fn update(&mut self) {
+        let tick_interval = config!(app).zone.tick_interval;
+        let max_catch_up_ticks = config!(app).zone.max_catch_up_ticks as u32;
+
+        let now = Instant::now();
+        self.accumulator += now.duration_since(self.last_update);
+        self.last_update = now;
+
+        let mut steps = 0;
+        while self.accumulator >= tick_interval {
+            if steps == max_catch_up_ticks {
+                let skipped = self.accumulator.as_nanos() / tick_interval.as_nanos();
+                warn!("{}: Falling behind, skipped {} ticks", self, skipped);
+
+                self.accumulator = Duration::ZERO;
+                break;
+            }
+
+            self.tick();
+            self.accumulator -= tick_interval;
+            steps += 1;
+        }
+
+        if self.last_report.elapsed() >= config!(app).zone.report_interval {
+            self.report();
+            self.last_report = Instant::now();
+        }
+    }
+
+    /// Advance the simulation by exactly one fixed step.
     fn tick(&mut self) {
+        let start = Instant::now();
+
         self.handle_protocols();
 
-        self.schedule.run(&mut self.world);
+        for stage in &mut self.stages {
+            let stage_start = Instant::now();
+            stage.schedule.run(&mut self.world);
+            stage.cost.record(stage_start.elapsed());
+        }
 
-        let mut time = self.world.get_resource_mut::<Time>().unwrap();
-        time.last_tick = Instant::now();
-        time.ticks += 1;
+        self.world.resource_mut::<Time>().advance();
         self.fps.tick();
+
+        let cost = start.elapsed();
+        self.tick_cost.record(cost);
+        self.load.tick_cost_micros.store(
+            self.tick_cost.average_duration().as_micros() as u64,
+            Ordering::Relaxed,
+        );
+
+        let tick_interval = config!(app).zone.tick_interval;
+        if cost > tick_interval {
+            self.overruns += 1;
+            warn!(
+                "{}: Tick {} overran: {:?} > {:?}",
+                self,
+                self.world.resource::<Time>().ticks,
+                cost,
+                tick_interval,
+            );
+        }
     }
 
     fn handle_protocols(&mut self) {
@@ -57,18 +163,100 @@ impl Zone {
             }
         }
 
+        let tick = self.world.resource::<Time>().ticks;
         for (entity, ctx, protocol) in self.protocols_buffer.drain(..) {
+            if let Some(mut recorder) = self.world.get_resource_mut::<Recorder>() {
+                recorder.record(&Event::Protocol {
+                    tick,
+                    entity: entity.to_bits(),
+                    id: protocol.protocol_id(),
+                    body: protocol.encode_body().into(),
+                });
+            }
+
             crate::handler::handle_local(&mut self.world, entity, ctx, protocol);
         }
     }
+
+    fn start_recording(&mut self) -> Result<(), record::Error> {
+        let record_config = &config!(app).zone.record;
+        let path = record_config.dir.join(format!(
+            "zone-{}-{}.record",
+            self.id,
+            chrono::Utc::now().format("%Y%m%d%H%M%S"),
+        ));
+
+        let header = Header {
+            zone_id: self.id,
+            seed: self.world.resource::<Random>().seed,
+            tick_interval: self.world.resource::<Time>().delta(),
+        };
+        let recorder = Recorder::create(&path, &header)?;
+        self.world.insert_resource(recorder);
+
+        info!("{}: Recording to \"{}\"", self, path.display());
+        Ok(())
+    }
+
+    fn report(&mut self) {
+        if let Some(mut recorder) = self.world.get_resource_mut::<Recorder>() {
+            recorder.flush();
+        }
+
+        let mut ingress_violations = 0;
+        let (rtts, egress): (Vec<Duration>, Vec<EgressStats>) = self.world
+            .query::<&Session>()
+            .iter(&self.world)
+            .map(|session| {
+                ingress_violations += session.ingress_violations();
+                (session.rtt().estimate(), session.egress_stats())
+            })
+            .unzip();
+        let rtt_average = rtts.iter().sum::<Duration>() / rtts.len().max(1) as u32;
+        let rtt_max = rtts.iter().max().copied().unwrap_or_default();
+        let egress_queued_max = egress.iter().map(|stats| stats.queued_bytes).max().unwrap_or_default();
+        let egress_dropped = egress.iter().map(|stats| stats.dropped).sum::<u64>();
+        let egress_coalesced = egress.iter().map(|stats| stats.coalesced).sum::<u64>();
+
+        let stages = self.stages
+            .iter()
+            .map(|stage| format!("{}={:?}", stage.name, stage.cost.average_duration()))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        info!(
+            "{}: fps={:.1}, tick_cost(avg={:?}, max={:?}), overruns={}, stages({}), sessions={}, rtt(avg={:?}, max={:?}), egress(queued_max={}B, dropped={}, coalesced={}), ingress_violations={}",
+            self,
+            self.fps.reversed(),
+            self.tick_cost.average_duration(),
+            self.tick_cost.max_duration(),
+            self.overruns,
+            stages,
+            rtts.len(),
+            rtt_average,
+            rtt_max,
+            egress_queued_max,
+            egress_dropped,
+            egress_coalesced,
+            ingress_violations,
+        );
+    }
 }
 
 impl Actor for Zone {
     type Context = Context<Self>;
 
     fn started(&mut self, ctx: &mut Self::Context) {
+        if config!(app).zone.record.enabled {
+            if let Err(e) = self.start_recording() {
+                error!("{}: Failed to start recording: {}", self, e);
+            }
+        }
+
+        self.last_update = Instant::now();
+
         ctx.run_interval(config!(app).zone.tick_interval, |act, _| {
-            act.tick();
+            act.update();
         });
     }
 }
@@ -79,21 +267,46 @@ impl fmt::Display for Zone {
     }
 }
 
-fn new_world() -> World {
+impl ZoneLoad {
+    /// Average cost of a tick.
+    pub fn tick_cost(&self) -> Duration {
+        Duration::from_micros(self.tick_cost_micros.load(Ordering::Relaxed))
+    }
+}
+
+impl Stage {
+    fn new(name: &'static str, register: fn(&mut Schedule)) -> Self {
+        let mut schedule = Schedule::default();
+        register(&mut schedule);
+
+        Self {
+            name,
+            schedule,
+            cost: IntervalCounter::new(128),
+        }
+    }
+}
+
+fn new_world(seed: u64) -> World {
     let mut world = World::new();
 
-    world.insert_resource(Time::new());
+    world.insert_resource(Time::new(config!(app).zone.tick_interval));
+    world.insert_resource(Random::new(seed));
+    world.insert_resource(ReconnectGrace::new(config!(app).zone.tick_interval));
+    world.insert_resource(RewindWindow::new(config!(app).zone.tick_interval));
     world.insert_resource(crate::character::Characters::default());
+    world.insert_resource(crate::social::trade::Trades::default());
 
     world
 }
 
-fn new_schedule() -> Schedule {
-    let mut schedule = Schedule::default();
-
-    crate::character::status::movement::register(&mut schedule);
-    crate::net::session::register(&mut schedule);
-    crate::task::register(&mut schedule);
-
-    schedule
+fn new_stages() -> Vec<Stage> {
+    vec![
+        Stage::new("movement", crate::character::status::movement::register),
+        Stage::new("rewind", crate::world::rewind::register),
+        Stage::new("skill", crate::character::skill_set::register),
+        Stage::new("session", crate::net::session::register),
+        Stage::new("trade", crate::social::trade::register),
+        Stage::new("task", crate::task::register),
+    ]
 }
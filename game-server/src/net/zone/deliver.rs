@@ -0,0 +1,28 @@
+use super::Zone;
+use crate::character::Characters;
+use crate::net::session::Session;
+use actix::prelude::*;
+use bytes::Bytes;
+use util::id::Id;
+
+/// Send an encoded protocol to a character in the zone.
+#[derive(actix::Message)]
+#[rtype(result = "()")]
+pub struct Deliver {
+    pub character_id: Id,
+    pub protocol: Bytes,
+}
+
+impl Handler<Deliver> for Zone {
+    type Result = ();
+
+    fn handle(&mut self, msg: Deliver, _: &mut Self::Context) -> Self::Result {
+        let Some(&entity) = self.world.resource::<Characters>().map.get(&msg.character_id) else {
+            return;
+        };
+
+        if let Some(session) = self.world.get::<Session>(entity) {
+            session.send_encoded(msg.protocol);
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use super::Zone;
+use actix::prelude::*;
+use bevy_ecs::prelude::*;
+
+/// Run a function on the world of the zone between ticks, e.g. to check its state in tests
+/// or to debug it.
+pub struct Inspect<F>(pub F);
+
+impl<F, R> actix::Message for Inspect<F>
+where
+    F: FnOnce(&mut World) -> R,
+    R: 'static,
+{
+    type Result = R;
+}
+
+impl<F, R> Handler<Inspect<F>> for Zone
+where
+    F: FnOnce(&mut World) -> R,
+    R: 'static,
+{
+    type Result = MessageResult<Inspect<F>>;
+
+    fn handle(&mut self, msg: Inspect<F>, _: &mut Self::Context) -> Self::Result {
+        MessageResult((msg.0)(&mut self.world))
+    }
+}
@@ -0,0 +1,58 @@
+use super::Zone;
+use super::record::{Event, Recorder};
+use crate::character::Characters;
+use crate::net::session::{Linkdead, Session};
+use crate::world::time::Time;
+use actix::prelude::*;
+use bevy_ecs::prelude::*;
+use tracing::info;
+use util::id::Id;
+
+/// Remove a character from the zone at once, e.g. for a duplicate login.
+/// Unless `force`, a connected character is not kicked. Returns whether the character is gone.
+#[derive(actix::Message)]
+#[rtype(result = "bool")]
+pub struct Kick {
+    pub character_id: Id,
+    pub force: bool,
+}
+
+impl Handler<Kick> for Zone {
+    type Result = bool;
+
+    fn handle(&mut self, msg: Kick, _: &mut Self::Context) -> Self::Result {
+        let mut query = self.world.query::<(Entity, Option<&Session>, Option<&Linkdead>)>();
+        let found = query
+            .iter(&self.world)
+            .find_map(|(entity, session, linkdead)| {
+                let entry = session.map(|session| session.entry).or(linkdead.map(|linkdead| linkdead.entry))?;
+                (entry.character_id == msg.character_id).then(|| (entity, session.cloned()))
+            });
+
+        let Some((entity, session)) = found else {
+            return true;
+        };
+
+        if let Some(session) = session {
+            if !msg.force {
+                return false;
+            }
+            session.stop();
+        }
+
+        info!("{}: Character {} kicked", self, msg.character_id);
+
+        let tick = self.world.resource::<Time>().ticks;
+        if let Some(mut recorder) = self.world.get_resource_mut::<Recorder>() {
+            recorder.record(&Event::Leave { tick, entity: entity.to_bits() });
+        }
+
+        let mut characters = self.world.resource_mut::<Characters>();
+        if characters.map.get(&msg.character_id) == Some(&entity) {
+            characters.map.remove(&msg.character_id);
+        }
+        self.world.despawn(entity);
+
+        true
+    }
+}
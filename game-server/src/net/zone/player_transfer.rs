@@ -1,6 +1,8 @@
 use super::Zone;
-use crate::net::session::Session;
+use super::record::{Event, PlayerSnapshot, Recorder};
+use crate::net::session::{Priority, Session};
 use crate::player::PlayerData;
+use crate::world::time::Time;
 use actix::prelude::*;
 use bevy_ecs::prelude::*;
 use protocol::game::net::ZoneTransfer;
@@ -21,21 +23,44 @@ pub struct PlayerTransferProcess {
 impl Handler<PlayerTransfer> for Zone {
     type Result = ();
 
-    fn handle(&mut self, msg: PlayerTransfer, ctx: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, msg: PlayerTransfer, _: &mut Self::Context) -> Self::Result {
         let PlayerTransfer { session, player_data } = msg;
 
         info!("{}: [{}] New player transfer started", self, session);
 
-        session.send(&ZoneTransfer {
+        session.send_with(&ZoneTransfer {
             zone_id: self.id,
-        });
+        }, Priority::Critical);
+
+        self.spawn_player(session, player_data);
+    }
+}
+
+impl Zone {
+    pub(super) fn spawn_player(&mut self, session: Session, player_data: PlayerData) -> Entity {
+        let entry = session.entry;
+        let snapshot = self.world
+            .contains_resource::<Recorder>()
+            .then(|| PlayerSnapshot::capture(&player_data));
 
         let process = PlayerTransferProcess {
             player_data,
         };
-        self.world.spawn((
+        let entity = self.world.spawn((
             session,
             process,
-        ));
+        )).id();
+
+        if let Some(snapshot) = snapshot {
+            let tick = self.world.resource::<Time>().ticks;
+            self.world.resource_mut::<Recorder>().record(&Event::Join {
+                tick,
+                entity: entity.to_bits(),
+                entry,
+                snapshot,
+            });
+        }
+
+        entity
     }
 }
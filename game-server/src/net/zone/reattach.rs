@@ -0,0 +1,94 @@
+use super::Zone;
+use super::record::{Event, Recorder};
+use crate::character::Characters;
+use crate::character::status::movement;
+use crate::net::gateway;
+use crate::net::session::{Linkdead, Priority, Session};
+use crate::world::time::Time;
+use actix::prelude::*;
+use bevy_ecs::prelude::*;
+use protocol::game::net::ZoneTransfer;
+use tracing::info;
+
+/// Bind a reconnected player to its entity still in the zone.
+/// Unless `force`, an entity still connected is not taken over.
+#[derive(actix::Message)]
+#[rtype(result = "ReattachResult")]
+pub struct Reattach {
+    pub session: Session,
+    pub force: bool,
+}
+
+#[derive(MessageResponse)]
+pub enum ReattachResult {
+    Reattached,
+    /// The entity is still connected. The session is given back.
+    Connected(Session),
+    /// No entity to reattach to. The session is given back.
+    NotFound(Session),
+}
+
+impl Handler<Reattach> for Zone {
+    type Result = ReattachResult;
+
+    fn handle(&mut self, msg: Reattach, _: &mut Self::Context) -> Self::Result {
+        let session = msg.session;
+        match self.reattach(session.clone(), msg.force) {
+            Ok(_) => {}
+            Err(result) => return result,
+        }
+
+        info!("{}: [{}] Reattached", self, session);
+
+        gateway::accept(&session);
+
+        // Catch the client up with the world it missed.
+        session.send_with(&ZoneTransfer {
+            zone_id: self.id,
+        }, Priority::Critical);
+        session.send_with(&movement::snapshot(&mut self.world), Priority::Cosmetic);
+
+        ReattachResult::Reattached
+    }
+}
+
+impl Zone {
+    pub(super) fn reattach(&mut self, session: Session, force: bool) -> Result<Entity, ReattachResult> {
+        let Some(&entity) = self.world.resource::<Characters>().map.get(&session.entry.character_id) else {
+            return Err(ReattachResult::NotFound(session));
+        };
+        let Ok(mut entity_mut) = self.world.get_entity_mut(entity) else {
+            return Err(ReattachResult::NotFound(session));
+        };
+
+        let account_id = match (entity_mut.get::<Linkdead>(), entity_mut.get::<Session>()) {
+            (Some(linkdead), _) => linkdead.entry.account_id,
+            // The previous connection may not have been noticed dead yet.
+            (None, Some(_)) if !force => return Err(ReattachResult::Connected(session)),
+            (None, Some(previous)) => previous.entry.account_id,
+            (None, None) => return Err(ReattachResult::NotFound(session)),
+        };
+        if account_id != session.entry.account_id {
+            return Err(ReattachResult::NotFound(session));
+        }
+
+        if let Some(previous) = entity_mut.take::<Session>() {
+            previous.stop();
+        }
+        entity_mut.remove::<Linkdead>();
+
+        let entry = session.entry;
+        entity_mut.insert(session);
+
+        let tick = self.world.resource::<Time>().ticks;
+        if let Some(mut recorder) = self.world.get_resource_mut::<Recorder>() {
+            recorder.record(&Event::Reattach {
+                tick,
+                entity: entity.to_bits(),
+                entry,
+            });
+        }
+
+        Ok(entity)
+    }
+}
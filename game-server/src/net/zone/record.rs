@@ -0,0 +1,554 @@
+use crate::character::Character;
+use crate::character::path_tree::{PathNode, PathTree};
+use crate::net::session::Entry;
+use crate::player::PlayerData;
+use crate::world::transform::Transform;
+use bevy_ecs::prelude::*;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use data::character::PathTable;
+use data::prelude::*;
+use nalgebra::{Point3, UnitVector2, Vector2};
+use protocol::game::ProtocolId;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+use tracing::error;
+use util::id::Id;
+
+const MAGIC: &[u8; 4] = b"SPZR";
+const VERSION: u16 = 2;
+
+const EVENT_JOIN: u8 = 1;
+const EVENT_PROTOCOL: u8 = 2;
+const EVENT_LEAVE: u8 = 3;
+const EVENT_DETACH: u8 = 4;
+const EVENT_REATTACH: u8 = 5;
+
+/// Records every input of a zone into a log file, so that the zone can be replayed.
+///
+/// Layout: magic, version, header, then a sequence of events. All integers are big endian.
+#[derive(Resource)]
+pub struct Recorder {
+    writer: BufWriter<File>,
+    buffer: BytesMut,
+    failed: bool,
+}
+
+pub struct Header {
+    pub zone_id: Id,
+    pub seed: u64,
+    pub tick_interval: Duration,
+}
+
+pub enum Event {
+    /// A player is spawned before the tick.
+    Join {
+        tick: u64,
+        entity: u64,
+        entry: Entry,
+        snapshot: PlayerSnapshot,
+    },
+
+    /// A local protocol is handled during the tick.
+    Protocol {
+        tick: u64,
+        entity: u64,
+        id: ProtocolId,
+        body: Bytes,
+    },
+
+    /// A player is despawned during the tick.
+    Leave {
+        tick: u64,
+        entity: u64,
+    },
+
+    /// A player loses the connection during the tick and becomes linkdead.
+    Detach {
+        tick: u64,
+        entity: u64,
+    },
+
+    /// A linkdead or connected player is bound to a new session before the tick.
+    Reattach {
+        tick: u64,
+        entity: u64,
+        entry: Entry,
+    },
+}
+
+/// Player data at the moment of joining a zone. Also used to hand players over to other nodes.
+pub struct PlayerSnapshot {
+    pub character_id: Id,
+    pub name: String,
+    pub race: i32,
+    pub position: [f32; 3],
+    pub direction: [f32; 2],
+    pub paths: Vec<PathSnapshot>,
+}
+
+pub struct PathSnapshot {
+    pub data_id: u32,
+    pub is_active: bool,
+    pub level: u16,
+    pub exp: u32,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    IO(#[from] std::io::Error),
+
+    #[error("Invalid record: {0}")]
+    Invalid(&'static str),
+
+    #[error("Unsupported record version: {0}")]
+    Version(u16),
+
+    #[error("Unknown race: {0}")]
+    Race(i32),
+
+    #[error("Unknown path data: {0}")]
+    Path(u32),
+}
+
+impl Recorder {
+    pub fn create(path: &Path, header: &Header) -> Result<Self, Error> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let mut recorder = Self {
+            writer: BufWriter::new(File::create(path)?),
+            buffer: BytesMut::with_capacity(1024),
+            failed: false,
+        };
+
+        recorder.buffer.put_slice(MAGIC);
+        recorder.buffer.put_u16(VERSION);
+        recorder.buffer.put_i64(header.zone_id);
+        recorder.buffer.put_u64(header.seed);
+        recorder.buffer.put_u32(header.tick_interval.as_micros() as u32);
+        recorder.write_buffer()?;
+
+        Ok(recorder)
+    }
+
+    pub fn record(&mut self, event: &Event) {
+        if self.failed {
+            return;
+        }
+
+        event.encode(&mut self.buffer);
+        if let Err(e) = self.write_buffer() {
+            error!("Failed to record zone event, recording stopped: {}", e);
+            self.failed = true;
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if let Err(e) = self.writer.flush() {
+            error!("Failed to flush zone record: {}", e);
+        }
+    }
+
+    fn write_buffer(&mut self) -> Result<(), Error> {
+        let result = self.writer.write_all(&self.buffer);
+        self.buffer.clear();
+
+        Ok(result?)
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Read a whole record file.
+pub fn read(path: &Path) -> Result<(Header, Vec<Event>), Error> {
+    let mut buffer = Bytes::from(std::fs::read(path)?);
+
+    ensure(&buffer, MAGIC.len() + 2)?;
+    if &buffer.split_to(MAGIC.len())[..] != MAGIC {
+        return Err(Error::Invalid("magic"));
+    }
+    let version = buffer.get_u16();
+    if version != VERSION {
+        return Err(Error::Version(version));
+    }
+
+    ensure(&buffer, 8 + 8 + 4)?;
+    let header = Header {
+        zone_id: buffer.get_i64(),
+        seed: buffer.get_u64(),
+        tick_interval: Duration::from_micros(buffer.get_u32() as u64),
+    };
+
+    let mut events = Vec::new();
+    while buffer.has_remaining() {
+        events.push(Event::decode(&mut buffer)?);
+    }
+
+    Ok((header, events))
+}
+
+impl Event {
+    pub fn tick(&self) -> u64 {
+        match self {
+            Event::Join { tick, .. } => *tick,
+            Event::Protocol { tick, .. } => *tick,
+            Event::Leave { tick, .. } => *tick,
+            Event::Detach { tick, .. } => *tick,
+            Event::Reattach { tick, .. } => *tick,
+        }
+    }
+
+    fn encode(&self, buffer: &mut BytesMut) {
+        match self {
+            Event::Join { tick, entity, entry, snapshot } => {
+                buffer.put_u8(EVENT_JOIN);
+                buffer.put_u64(*tick);
+                buffer.put_u64(*entity);
+                buffer.put_i64(entry.account_id);
+                buffer.put_i64(entry.character_id);
+                snapshot.encode(buffer);
+            }
+            Event::Protocol { tick, entity, id, body } => {
+                buffer.put_u8(EVENT_PROTOCOL);
+                buffer.put_u64(*tick);
+                buffer.put_u64(*entity);
+                buffer.put_u16(*id);
+                buffer.put_u32(body.len() as u32);
+                buffer.put_slice(body);
+            }
+            Event::Leave { tick, entity } => {
+                buffer.put_u8(EVENT_LEAVE);
+                buffer.put_u64(*tick);
+                buffer.put_u64(*entity);
+            }
+            Event::Detach { tick, entity } => {
+                buffer.put_u8(EVENT_DETACH);
+                buffer.put_u64(*tick);
+                buffer.put_u64(*entity);
+            }
+            Event::Reattach { tick, entity, entry } => {
+                buffer.put_u8(EVENT_REATTACH);
+                buffer.put_u64(*tick);
+                buffer.put_u64(*entity);
+                buffer.put_i64(entry.account_id);
+                buffer.put_i64(entry.character_id);
+            }
+        }
+    }
+
+    fn decode(buffer: &mut Bytes) -> Result<Self, Error> {
+        ensure(buffer, 1 + 8 + 8)?;
+        let kind = buffer.get_u8();
+        let tick = buffer.get_u64();
+        let entity = buffer.get_u64();
+
+        Ok(match kind {
+            EVENT_JOIN => {
+                ensure(buffer, 8 + 8)?;
+                let entry = Entry {
+                    account_id: buffer.get_i64(),
+                    character_id: buffer.get_i64(),
+                };
+                let snapshot = PlayerSnapshot::decode(buffer)?;
+
+                Event::Join { tick, entity, entry, snapshot }
+            }
+            EVENT_PROTOCOL => {
+                ensure(buffer, 2 + 4)?;
+                let id = buffer.get_u16();
+                let length = buffer.get_u32() as usize;
+                ensure(buffer, length)?;
+                let body = buffer.split_to(length);
+
+                Event::Protocol { tick, entity, id, body }
+            }
+            EVENT_LEAVE => Event::Leave { tick, entity },
+            EVENT_DETACH => Event::Detach { tick, entity },
+            EVENT_REATTACH => {
+                ensure(buffer, 8 + 8)?;
+                let entry = Entry {
+                    account_id: buffer.get_i64(),
+                    character_id: buffer.get_i64(),
+                };
+
+                Event::Reattach { tick, entity, entry }
+            }
+            _ => return Err(Error::Invalid("event kind")),
+        })
+    }
+}
+
+impl PlayerSnapshot {
+    pub fn capture(player_data: &PlayerData) -> Self {
+        let race: protocol::Race = player_data.character.race.into();
+        let transform = &player_data.transform;
+
+        let mut paths: Vec<PathSnapshot> = player_data.path_tree.nodes
+            .values()
+            .map(|node| PathSnapshot {
+                data_id: *node.data.id,
+                is_active: node.is_active,
+                level: node.level,
+                exp: node.exp,
+            })
+            .collect();
+        // Keep the record stable regardless of the hash map order.
+        paths.sort_by_key(|path| path.data_id);
+
+        Self {
+            character_id: player_data.character.id,
+            name: player_data.character.name.clone(),
+            race: race.into(),
+            position: [transform.position.x, transform.position.y, transform.position.z],
+            direction: [transform.direction.x, transform.direction.y],
+            paths,
+        }
+    }
+
+    pub fn restore(self) -> Result<PlayerData, Error> {
+        let race = protocol::Race::try_from(self.race)
+            .map_err(|_| Error::Race(self.race))?
+            .into();
+
+        let mut path_tree = PathTree::default();
+        for path in self.paths {
+            let Some(data) = PathTable::get(&DataId::from(path.data_id)) else {
+                return Err(Error::Path(path.data_id));
+            };
+
+            path_tree.nodes.insert(data.id, PathNode {
+                data,
+                is_active: path.is_active,
+                level: path.level,
+                exp: path.exp,
+            });
+        }
+
+        let [x, y, z] = self.position;
+        let [dx, dy] = self.direction;
+
+        Ok(PlayerData {
+            character: Character {
+                id: self.character_id,
+                name: self.name,
+                race,
+            },
+            path_tree,
+            transform: Transform {
+                position: Point3::new(x, y, z),
+                direction: UnitVector2::new_normalize(Vector2::new(dx, dy)),
+            },
+        })
+    }
+
+    pub fn encode(&self, buffer: &mut BytesMut) {
+        buffer.put_i64(self.character_id);
+        buffer.put_u16(self.name.len() as u16);
+        buffer.put_slice(self.name.as_bytes());
+        buffer.put_i32(self.race);
+        for value in self.position.iter().chain(self.direction.iter()) {
+            buffer.put_f32(*value);
+        }
+
+        buffer.put_u16(self.paths.len() as u16);
+        for path in &self.paths {
+            buffer.put_u32(path.data_id);
+            buffer.put_u8(path.is_active as u8);
+            buffer.put_u16(path.level);
+            buffer.put_u32(path.exp);
+        }
+    }
+
+    pub fn decode(buffer: &mut Bytes) -> Result<Self, Error> {
+        ensure(buffer, 8 + 2)?;
+        let character_id = buffer.get_i64();
+        let name_length = buffer.get_u16() as usize;
+        ensure(buffer, name_length)?;
+        let name = String::from_utf8(buffer.split_to(name_length).to_vec())
+            .map_err(|_| Error::Invalid("name"))?;
+
+        ensure(buffer, 4 + 4 * 5 + 2)?;
+        let race = buffer.get_i32();
+        let position = [buffer.get_f32(), buffer.get_f32(), buffer.get_f32()];
+        let direction = [buffer.get_f32(), buffer.get_f32()];
+
+        let path_count = buffer.get_u16() as usize;
+        let mut paths = Vec::with_capacity(path_count);
+        for _ in 0..path_count {
+            ensure(buffer, 4 + 1 + 2 + 4)?;
+            paths.push(PathSnapshot {
+                data_id: buffer.get_u32(),
+                is_active: buffer.get_u8() != 0,
+                level: buffer.get_u16(),
+                exp: buffer.get_u32(),
+            });
+        }
+
+        Ok(Self {
+            character_id,
+            name,
+            race,
+            position,
+            direction,
+            paths,
+        })
+    }
+}
+
+fn ensure(buffer: &Bytes, length: usize) -> Result<(), Error> {
+    if buffer.remaining() < length {
+        return Err(Error::Invalid("unexpected end of record"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const HEADER: Header = Header {
+        zone_id: 7,
+        seed: 42,
+        tick_interval: Duration::from_millis(50),
+    };
+
+    /// A file of its own per test, as tests run in parallel.
+    fn path(test: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("spire-record-{}-{}.bin", std::process::id(), test))
+    }
+
+    fn entry() -> Entry {
+        Entry {
+            account_id: 1,
+            character_id: 2,
+        }
+    }
+
+    fn events() -> Vec<Event> {
+        vec![
+            Event::Join {
+                tick: 1,
+                entity: 10,
+                entry: entry(),
+                snapshot: PlayerSnapshot {
+                    character_id: 2,
+                    name: "player".to_string(),
+                    race: 1,
+                    position: [1.0, 2.0, 3.0],
+                    direction: [0.0, 1.0],
+                    paths: vec![PathSnapshot {
+                        data_id: 100,
+                        is_active: true,
+                        level: 3,
+                        exp: 40,
+                    }],
+                },
+            },
+            Event::Protocol {
+                tick: 2,
+                entity: 10,
+                id: 5,
+                body: Bytes::from_static(b"body"),
+            },
+            Event::Detach { tick: 3, entity: 10 },
+            Event::Reattach { tick: 4, entity: 10, entry: entry() },
+            Event::Leave { tick: 5, entity: 10 },
+        ]
+    }
+
+    fn encode(events: &[Event]) -> BytesMut {
+        let mut buffer = BytesMut::new();
+        events.iter().for_each(|event| event.encode(&mut buffer));
+        buffer
+    }
+
+    /// Write a file of the magic and version given, followed by a valid header.
+    fn write(path: &Path, magic: &[u8; 4], version: u16) {
+        let mut buffer = BytesMut::new();
+        buffer.put_slice(magic);
+        buffer.put_u16(version);
+        buffer.put_i64(HEADER.zone_id);
+        buffer.put_u64(HEADER.seed);
+        buffer.put_u32(HEADER.tick_interval.as_micros() as u32);
+        std::fs::write(path, buffer).unwrap();
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = path("round_trip");
+        let events = events();
+
+        let mut recorder = Recorder::create(&path, &HEADER).unwrap();
+        events.iter().for_each(|event| recorder.record(event));
+        drop(recorder);
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[..4], MAGIC);
+        assert_eq!(u16::from_be_bytes([bytes[4], bytes[5]]), VERSION);
+
+        let result = read(&path);
+        std::fs::remove_file(&path).unwrap();
+        let (header, decoded) = result.unwrap();
+
+        assert_eq!(header.zone_id, HEADER.zone_id);
+        assert_eq!(header.seed, HEADER.seed);
+        assert_eq!(header.tick_interval, HEADER.tick_interval);
+        assert!(matches!(decoded[..], [
+            Event::Join { .. },
+            Event::Protocol { .. },
+            Event::Detach { .. },
+            Event::Reattach { .. },
+            Event::Leave { .. },
+        ]));
+        assert_eq!(decoded.iter().map(Event::tick).collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+        // Every field survives, as the events encode again to the same bytes.
+        assert_eq!(encode(&decoded), encode(&events));
+
+        let Event::Join { snapshot, .. } = &decoded[0] else { unreachable!() };
+        assert_eq!(snapshot.name, "player");
+        assert_eq!(snapshot.position, [1.0, 2.0, 3.0]);
+        assert_eq!(snapshot.paths.len(), 1);
+    }
+
+    #[test]
+    fn refuses_bad_magic() {
+        let path = path("bad_magic");
+        write(&path, b"SPZX", VERSION);
+
+        let result = read(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(Error::Invalid("magic"))));
+    }
+
+    #[test]
+    fn refuses_other_version() {
+        let path = path("other_version");
+        write(&path, MAGIC, VERSION + 1);
+
+        let result = read(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(Error::Version(version)) if version == VERSION + 1));
+    }
+
+    #[test]
+    fn refuses_truncated_event() {
+        let mut buffer = encode(&events()).freeze();
+        buffer.truncate(buffer.len() - 1);
+
+        let mut result = Ok(());
+        while buffer.has_remaining() && result.is_ok() {
+            result = Event::decode(&mut buffer).map(|_| ());
+        }
+        assert!(matches!(result, Err(Error::Invalid(_))));
+    }
+}
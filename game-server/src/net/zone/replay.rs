@@ -0,0 +1,108 @@
+use super::record::{self, Event};
+use super::Zone;
+use crate::net::session::{Linkdead, ReconnectGrace, Session};
+use crate::world::time::Time;
+use bevy_ecs::prelude::*;
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::{info, warn};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Record(#[from] record::Error),
+
+    #[error(transparent)]
+    Protocol(#[from] protocol::game::Error),
+
+    #[error("Unknown entity {entity} at tick {tick}")]
+    Entity { tick: u64, entity: u64 },
+}
+
+/// Rebuild a zone from a record and run it tick by tick without network.
+///
+/// Async tasks dispatched by handlers (e.g. DB queries) are not part of the record, so zones
+/// depending on their results may diverge.
+pub fn run(path: &Path) -> Result<Zone, Error> {
+    let (header, events) = record::read(path)?;
+    info!(
+        "Replaying Zone[{}] with seed {} and {} events",
+        header.zone_id,
+        header.seed,
+        events.len(),
+    );
+
+    let mut zone = Zone::with_seed(header.zone_id, header.seed);
+    zone.world.insert_resource(Time::new(header.tick_interval));
+    // Linkdead players expire only when recorded so, regardless of the current config. Players
+    // leaving at once are expired by their `Leave` events.
+    zone.world.insert_resource(ReconnectGrace { ticks: u64::MAX });
+
+    // Entities of the record mapped to the entities of the replay.
+    let mut entities = HashMap::<u64, Entity>::new();
+    let mut events = events.into_iter().peekable();
+
+    while events.peek().is_some() {
+        let tick = zone.world.resource::<Time>().ticks;
+
+        while let Some(event) = events.next_if(|event| event.tick() == tick) {
+            match event {
+                Event::Join { entity, entry, snapshot, .. } => {
+                    let player_data = snapshot.restore()?;
+                    let replayed = zone.spawn_player(Session::detached(entry), player_data);
+                    entities.insert(entity, replayed);
+                }
+                Event::Protocol { entity, id, body, .. } => {
+                    let replayed = *entities.get(&entity).ok_or(Error::Entity { tick, entity })?;
+                    let Some(session) = zone.world.get::<Session>(replayed).cloned() else {
+                        warn!("Replayed entity {} at tick {} has no session", entity, tick);
+                        continue;
+                    };
+
+                    let protocol = protocol::game::decode_local(id, body)?;
+                    zone.protocols_buffer.push_back((replayed, session, protocol));
+                }
+                Event::Leave { entity, .. } => {
+                    let replayed = entities.remove(&entity).ok_or(Error::Entity { tick, entity })?;
+
+                    // Let the session systems despawn the entity during the tick, as they did.
+                    // Expiring it now skips the grace a finished session would get.
+                    if let Some(session) = zone.world.get::<Session>(replayed) {
+                        let entry = session.entry;
+                        zone.world.entity_mut(replayed)
+                            .remove::<Session>()
+                            .insert(Linkdead { entry, expire_tick: tick });
+                    } else if let Some(mut linkdead) = zone.world.get_mut::<Linkdead>(replayed) {
+                        linkdead.expire_tick = tick;
+                    }
+                }
+                Event::Detach { entity, .. } => {
+                    let replayed = *entities.get(&entity).ok_or(Error::Entity { tick, entity })?;
+
+                    if let Some(session) = zone.world.get::<Session>(replayed) {
+                        session.finish();
+                    }
+                }
+                Event::Reattach { entity, entry, .. } => {
+                    let replayed = *entities.get(&entity).ok_or(Error::Entity { tick, entity })?;
+
+                    if zone.reattach(Session::detached(entry), true).ok() != Some(replayed) {
+                        warn!("Replayed entity {} at tick {} failed to reattach", entity, tick);
+                    }
+                }
+            }
+        }
+
+        zone.tick();
+    }
+
+    let players = zone.world.query::<&Session>().iter(&zone.world).count();
+    info!(
+        "Replayed {} for {} ticks, {} players remaining",
+        zone,
+        zone.world.resource::<Time>().ticks,
+        players,
+    );
+
+    Ok(zone)
+}
@@ -0,0 +1,112 @@
+mod spawn_zone;
+
+pub use spawn_zone::SpawnZone;
+
+use crate::config;
+use crate::config::app::ZonePlacement;
+use crate::net::zone::ZoneLoad;
+use actix::prelude::*;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+use util::id::Id;
+
+/// Places zones on a pool of arbiters, so that a slow zone does not stall the others.
+pub struct ZonePool {
+    workers: Vec<Worker>,
+    next: usize,
+}
+
+struct Worker {
+    arbiter: Arbiter,
+    zones: Vec<(Id, Arc<ZoneLoad>)>,
+}
+
+impl ZonePool {
+    fn place(&mut self, zone_id: Id) -> usize {
+        let pool_config = &config!(app).zone.pool;
+
+        if pool_config.dedicated {
+            self.workers.push(Worker::new());
+            return self.workers.len() - 1;
+        }
+
+        if let Some(pin) = pool_config.pins.iter().find(|pin| pin.zone == zone_id) {
+            if pin.thread < self.workers.len() {
+                return pin.thread;
+            }
+
+            warn!(
+                "Zone {} is pinned to thread {} out of {} threads",
+                zone_id,
+                pin.thread,
+                self.workers.len(),
+            );
+        }
+
+        match pool_config.placement {
+            ZonePlacement::RoundRobin => {
+                let index = self.next;
+                self.next = (self.next + 1) % self.workers.len();
+                index
+            }
+            ZonePlacement::LeastLoaded => {
+                self.workers
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, worker)| (worker.load(), worker.zones.len()))
+                    .map(|(index, _)| index)
+                    .unwrap()
+            }
+        }
+    }
+}
+
+impl Worker {
+    fn new() -> Self {
+        Self {
+            arbiter: Arbiter::new(),
+            zones: Vec::new(),
+        }
+    }
+
+    /// Sum of the average tick costs of the zones.
+    fn load(&self) -> Duration {
+        self.zones.iter().map(|(_, load)| load.tick_cost()).sum()
+    }
+}
+
+impl Default for ZonePool {
+    fn default() -> Self {
+        let pool_config = &config!(app).zone.pool;
+
+        let threads = if pool_config.dedicated {
+            0
+        } else if pool_config.threads > 0 {
+            pool_config.threads
+        } else {
+            std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+        };
+
+        info!("Zone pool started with {} threads", threads);
+
+        Self {
+            workers: (0..threads).map(|_| Worker::new()).collect(),
+            next: 0,
+        }
+    }
+}
+
+impl Actor for ZonePool {
+    type Context = Context<Self>;
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        for worker in &self.workers {
+            worker.arbiter.stop();
+        }
+    }
+}
+
+impl Supervised for ZonePool {}
+
+impl SystemService for ZonePool {}
@@ -0,0 +1,34 @@
+use super::ZonePool;
+use crate::net::gateway::{Gateway, NewZone};
+use crate::net::zone::Zone;
+use actix::prelude::*;
+use tracing::info;
+use util::id::Id;
+
+#[derive(actix::Message)]
+#[rtype(result = "Addr<Zone>")]
+pub struct SpawnZone {
+    pub id: Id,
+}
+
+impl Handler<SpawnZone> for ZonePool {
+    type Result = MessageResult<SpawnZone>;
+
+    fn handle(&mut self, msg: SpawnZone, _: &mut Self::Context) -> Self::Result {
+        let index = self.place(msg.id);
+        let worker = &mut self.workers[index];
+
+        let zone = Zone::new(msg.id);
+        worker.zones.push((msg.id, zone.load()));
+        let zone = Zone::start_in_arbiter(&worker.arbiter.handle(), move |_| zone);
+
+        info!("Zone {} spawned on thread {}", msg.id, index);
+
+        Gateway::from_registry().do_send(NewZone {
+            id: msg.id,
+            zone: zone.clone(),
+        });
+
+        MessageResult(zone)
+    }
+}
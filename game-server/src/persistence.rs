@@ -0,0 +1,244 @@
+pub mod auction;
+pub mod ledger;
+pub mod mail;
+pub mod memory;
+pub mod postgres;
+
+use crate::config;
+use crate::config::db::Backend;
+use data::character::Race;
+use futures::future::BoxFuture;
+use auction::{Filter, Listing};
+use ledger::{Operation, Outcome};
+use mail::Mail;
+use std::sync::OnceLock;
+use tracing::info;
+use util::id::Id;
+
+static REPOSITORIES: OnceLock<Repositories> = OnceLock::new();
+
+/// Storage of players, so the server runs the same on Postgres or in memory.
+pub struct Repositories {
+    pub account: Box<dyn AccountRepository>,
+    pub character: Box<dyn CharacterRepository>,
+    pub path: Box<dyn PathRepository>,
+    pub talent: Box<dyn TalentRepository>,
+    pub item: Box<dyn ItemRepository>,
+    pub ledger: Box<dyn LedgerRepository>,
+    pub auction: Box<dyn AuctionRepository>,
+    pub mail: Box<dyn MailRepository>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Db(#[from] db::Error),
+
+    #[error("Character {0} not found")]
+    CharacterNotFound(Id),
+
+    #[error("Not enough gold for character {0}")]
+    InsufficientGold(Id),
+
+    #[error("Not enough of item {item_id} for character {character_id}")]
+    InsufficientItem { character_id: Id, item_id: Id },
+
+    #[error("Operation {0} not found")]
+    OperationNotFound(Id),
+
+    #[error("Listing {0} not found")]
+    ListingNotFound(Id),
+
+    #[error("Mail {0} not found")]
+    MailNotFound(Id),
+
+    #[error("Mail {0} has unclaimed attachments")]
+    MailUnclaimed(Id),
+}
+
+#[derive(Debug, Clone)]
+pub struct CharacterRecord {
+    pub id: Id,
+    pub account_id: Id,
+    pub name: String,
+    pub race: Race,
+}
+
+#[derive(Debug, Clone)]
+pub struct PathRecord {
+    pub data_id: i32,
+    pub is_active: bool,
+    pub level: i16,
+    pub exp: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct TalentRecord {
+    pub data_id: i32,
+    pub level: i16,
+    pub exp: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct ItemRecord {
+    pub id: Id,
+    pub character_id: Id,
+    pub data_id: i32,
+    pub count: i32,
+    pub level: i16,
+    pub is_bound: bool,
+}
+
+pub trait AccountRepository: Send + Sync {
+    fn create(&self, account_id: Id) -> BoxFuture<'_, Result<(), Error>>;
+}
+
+pub trait CharacterRepository: Send + Sync {
+    fn get(&self, character_id: Id) -> BoxFuture<'_, Result<CharacterRecord, Error>>;
+
+    /// Whether the character belongs to the account.
+    fn is_owned(&self, account_id: Id, character_id: Id) -> BoxFuture<'_, Result<bool, Error>>;
+
+    fn create(&self, character: CharacterRecord) -> BoxFuture<'_, Result<(), Error>>;
+}
+
+pub trait PathRepository: Send + Sync {
+    fn list(&self, character_id: Id) -> BoxFuture<'_, Result<Vec<PathRecord>, Error>>;
+}
+
+pub trait TalentRepository: Send + Sync {
+    fn list(&self, character_id: Id) -> BoxFuture<'_, Result<Vec<TalentRecord>, Error>>;
+}
+
+pub trait ItemRepository: Send + Sync {
+    fn list(&self, character_id: Id) -> BoxFuture<'_, Result<Vec<ItemRecord>, Error>>;
+}
+
+/// The only way to change items and gold, so every change is recorded.
+pub trait LedgerRepository: Send + Sync {
+    /// Apply all the changes and record them, or none.
+    fn apply(&self, operation: Operation) -> BoxFuture<'_, Result<Outcome, Error>>;
+
+    /// Changes of the operation, in order.
+    fn entries(&self, operation_id: Id) -> BoxFuture<'_, Result<Vec<ledger::Entry>, Error>>;
+}
+
+/// Listings of the auction house. Their items and gold move through the ledger along with them.
+pub trait AuctionRepository: Send + Sync {
+    /// Take the items from the seller into escrow and list them.
+    fn create(&self, listing: Listing) -> BoxFuture<'_, Result<(), Error>>;
+
+    /// Listings not expired yet.
+    fn search(&self, filter: Filter) -> BoxFuture<'_, Result<Vec<Listing>, Error>>;
+
+    /// Give the items to the buyer, who pays the seller. Sellers can't buy their own listings.
+    /// Returns the mail paying the seller along with the listing.
+    fn buy(&self, listing_id: Id, buyer_id: Id) -> BoxFuture<'_, Result<(Listing, Mail), Error>>;
+
+    /// Return the items to the seller.
+    fn cancel(&self, listing_id: Id, seller_id: Id) -> BoxFuture<'_, Result<Listing, Error>>;
+
+    /// Mail the items of at most `limit` expired listings back to their sellers. Returns the mail.
+    fn expire(&self, limit: i64) -> BoxFuture<'_, Result<Vec<Mail>, Error>>;
+}
+
+/// Mailboxes. Attachments move through the ledger along with the mail.
+pub trait MailRepository: Send + Sync {
+    /// Send the mail, taking the attachments of a character sender into escrow.
+    fn send(&self, mail: Mail) -> BoxFuture<'_, Result<(), Error>>;
+
+    /// At most `limit` mail of the character not expired yet, newest first.
+    fn list(&self, recipient_id: Id, limit: i64) -> BoxFuture<'_, Result<Vec<Mail>, Error>>;
+
+    /// How many mail of the character are not expired yet.
+    fn count(&self, recipient_id: Id) -> BoxFuture<'_, Result<i64, Error>>;
+
+    fn read(&self, mail_id: Id, recipient_id: Id) -> BoxFuture<'_, Result<(), Error>>;
+
+    /// Give the attachments to the recipient, once.
+    fn claim(&self, mail_id: Id, recipient_id: Id) -> BoxFuture<'_, Result<Mail, Error>>;
+
+    /// Delete mail without unclaimed attachments.
+    fn delete(&self, mail_id: Id, recipient_id: Id) -> BoxFuture<'_, Result<(), Error>>;
+
+    /// Delete at most `limit` expired mail, mailing unclaimed attachments back to the characters
+    /// who sent them. Returns how many were deleted.
+    fn expire(&self, limit: i64) -> BoxFuture<'_, Result<usize, Error>>;
+}
+
+impl Repositories {
+    /// Repositories on the global `db` pool, which must be initialized.
+    pub fn postgres() -> Self {
+        Self {
+            account: Box::new(postgres::Postgres),
+            character: Box::new(postgres::Postgres),
+            path: Box::new(postgres::Postgres),
+            talent: Box::new(postgres::Postgres),
+            item: Box::new(postgres::Postgres),
+            ledger: Box::new(postgres::Postgres),
+            auction: Box::new(postgres::Postgres),
+            mail: Box::new(postgres::Postgres),
+        }
+    }
+
+    /// Repositories sharing one store, lost on exit.
+    pub fn memory(store: memory::Memory) -> Self {
+        Self {
+            account: Box::new(store.clone()),
+            character: Box::new(store.clone()),
+            path: Box::new(store.clone()),
+            talent: Box::new(store.clone()),
+            item: Box::new(store.clone()),
+            ledger: Box::new(store.clone()),
+            auction: Box::new(store.clone()),
+            mail: Box::new(store),
+        }
+    }
+}
+
+impl From<db::QueryError> for Error {
+    fn from(e: db::QueryError) -> Self {
+        Error::Db(e.into())
+    }
+}
+
+/// Connect the repositories of the configured backend. Does nothing once initialized.
+pub async fn init() -> Result<(), db::Error> {
+    if REPOSITORIES.get().is_some() {
+        return Ok(());
+    }
+
+    let db_config = config!(db);
+
+    let repositories = match db_config.backend {
+        Backend::Postgres => {
+            db::init(
+                &db_config.user,
+                &db_config.password,
+                &db_config.host,
+                db_config.port,
+                &db_config.name,
+            ).await?;
+
+            let mut conn = db::conn().await?;
+            if db_config.migrate {
+                for name in db::migration::run(&mut conn).await? {
+                    info!("Applied migration {}", name);
+                }
+            }
+            db::migration::check(&mut conn).await?;
+
+            Repositories::postgres()
+        }
+        Backend::Memory => Repositories::memory(memory::Memory::new(db_config.create_missing)),
+    };
+
+    // Initialized concurrently otherwise, keeping the first.
+    _ = REPOSITORIES.set(repositories);
+
+    Ok(())
+}
+
+pub fn repositories() -> &'static Repositories {
+    REPOSITORIES.get().unwrap()
+}
@@ -0,0 +1,93 @@
+use super::ledger::{Change, ItemKind, Operation, Reason};
+use super::mail::{Attachment, Mail};
+use util::id::Id;
+
+// Shared with the lobby, which searches the same listings.
+pub use db::auction::{Category, Filter, Sort};
+
+/// Items for sale, held in escrow until bought, cancelled or expired.
+#[derive(Debug, Clone)]
+pub struct Listing {
+    pub id: Id,
+    pub seller_id: Id,
+    pub item: ItemKind,
+    pub count: i32,
+    pub category: Category,
+    /// Gold for all of the items.
+    pub price: i64,
+    /// Unix milliseconds.
+    pub expire_at: i64,
+}
+
+impl Listing {
+    /// Take the items from the seller, once per listing.
+    pub fn escrow(&self) -> Operation {
+        self.operation(self.id, vec![self.items(self.seller_id, -self.count)])
+    }
+
+    /// Give the items to the buyer. The seller is paid by `proceeds`.
+    pub fn sale(&self, buyer_id: Id) -> Operation {
+        self.operation(util::id::universal(), vec![
+            Change::Gold { character_id: buyer_id, delta: -self.price },
+            self.items(buyer_id, self.count),
+        ])
+    }
+
+    /// Mail paying the seller, who may be offline.
+    pub fn proceeds(&self) -> Mail {
+        Mail {
+            is_kept: true,
+            ..Mail::system(self.seller_id, "Auction sold".to_string(), String::new(), self.price, Vec::new())
+        }
+    }
+
+    /// Give the items back to the seller, when cancelled.
+    pub fn giveback(&self) -> Operation {
+        self.operation(util::id::universal(), vec![self.items(self.seller_id, self.count)])
+    }
+
+    /// Mail returning the items to the seller, when expired.
+    pub fn unsold(&self) -> Mail {
+        let items = vec![Attachment { item: self.item, count: self.count }];
+
+        Mail {
+            is_kept: true,
+            ..Mail::system(self.seller_id, "Auction expired".to_string(), String::new(), 0, items)
+        }
+    }
+
+    fn items(&self, character_id: Id, delta: i32) -> Change {
+        Change::Item { character_id, item: self.item, delta }
+    }
+
+    fn operation(&self, id: Id, changes: Vec<Change>) -> Operation {
+        Operation {
+            id,
+            reason: Reason::Auction,
+            source: Some(self.id.to_string()),
+            changes,
+        }
+    }
+}
+
+/// The category of listings of the item.
+pub fn category_of(item: &data::item::Item) -> Category {
+    match item {
+        data::item::Item::Equipment(_) => Category::Equipment,
+        data::item::Item::RandomBox(_) => Category::RandomBox,
+        #[allow(unreachable_patterns)]
+        _ => Category::Other,
+    }
+}
+
+impl Listing {
+    /// Whether the filter matches, as `db::auction::search` does.
+    pub fn matches(&self, filter: &Filter) -> bool {
+        filter.category.is_none_or(|category| self.category == category)
+            && filter.data_id.is_none_or(|data_id| self.item.data_id == data_id)
+            && filter.min_level.is_none_or(|level| self.item.level >= level)
+            && filter.max_level.is_none_or(|level| self.item.level <= level)
+            && filter.max_price.is_none_or(|price| self.price <= price)
+            && filter.seller_id.is_none_or(|seller_id| self.seller_id == seller_id)
+    }
+}
@@ -0,0 +1,129 @@
+use super::{repositories, Error, LedgerRepository};
+use util::id::Id;
+
+/// Changes of items and gold applied together, at most once per id.
+#[derive(Debug, Clone)]
+pub struct Operation {
+    /// Applying an operation again with the same id does nothing.
+    pub id: Id,
+    pub reason: Reason,
+    /// What caused it, e.g. the other character of a trade.
+    pub source: Option<String>,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Change {
+    Gold {
+        character_id: Id,
+        delta: i64,
+    },
+    /// Add to the count of the item, creating it or deleting it at 0.
+    Item {
+        character_id: Id,
+        item: ItemKind,
+        delta: i32,
+    },
+}
+
+/// An item, regardless of its owner and count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItemKind {
+    pub id: Id,
+    pub data_id: i32,
+    pub level: i16,
+    pub is_bound: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    Cheat,
+    Pickup,
+    Drop,
+    Trade,
+    Auction,
+    Mail,
+    Quest,
+    Rollback,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Applied,
+    /// Already applied before.
+    Duplicate,
+}
+
+/// A change as recorded, with the resulting gold or item count.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub operation_id: Id,
+    pub character_id: Id,
+    /// `None` for gold.
+    pub item: Option<ItemKind>,
+    pub delta: i64,
+    pub balance: i64,
+}
+
+impl Reason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Reason::Cheat => "cheat",
+            Reason::Pickup => "pickup",
+            Reason::Drop => "drop",
+            Reason::Trade => "trade",
+            Reason::Auction => "auction",
+            Reason::Mail => "mail",
+            Reason::Quest => "quest",
+            Reason::Rollback => "rollback",
+        }
+    }
+}
+
+impl Change {
+    pub fn character_id(&self) -> Id {
+        match self {
+            Change::Gold { character_id, .. } | Change::Item { character_id, .. } => *character_id,
+        }
+    }
+}
+
+impl Entry {
+    fn inverse(&self) -> Change {
+        match self.item {
+            None => Change::Gold {
+                character_id: self.character_id,
+                delta: -self.delta,
+            },
+            Some(item) => Change::Item {
+                character_id: self.character_id,
+                item,
+                delta: -self.delta as i32,
+            },
+        }
+    }
+}
+
+/// Undo an operation with a new one, which fails if the items or gold are gone since.
+pub async fn rollback(operation_id: Id, id: Id) -> Result<Outcome, Error> {
+    rollback_with(repositories().ledger.as_ref(), operation_id, id).await
+}
+
+/// Same as `rollback`, on the given repository.
+pub(super) async fn rollback_with(
+    ledger: &dyn LedgerRepository,
+    operation_id: Id,
+    id: Id,
+) -> Result<Outcome, Error> {
+    let entries = ledger.entries(operation_id).await?;
+    if entries.is_empty() {
+        return Err(Error::OperationNotFound(operation_id));
+    }
+
+    ledger.apply(Operation {
+        id,
+        reason: Reason::Rollback,
+        source: Some(operation_id.to_string()),
+        changes: entries.iter().rev().map(Entry::inverse).collect(),
+    }).await
+}
@@ -0,0 +1,129 @@
+use super::ledger::{Change, ItemKind, Operation, Reason};
+use std::time::Duration;
+use util::id::Id;
+
+/// Sender name of mail sent by the server.
+pub const SYSTEM_SENDER: &str = "System";
+
+/// How long mail is kept. Unclaimed attachments are returned to the characters who sent them.
+pub const LIFETIME: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// A message to a character, holding its attached items and gold in escrow until claimed.
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub id: Id,
+    pub recipient_id: Id,
+    /// `None` for system mail.
+    pub sender_id: Option<Id>,
+    pub sender_name: String,
+    pub subject: String,
+    pub body: String,
+    pub gold: i64,
+    pub items: Vec<Attachment>,
+    pub is_read: bool,
+    pub is_claimed: bool,
+    /// The attachments belong to the recipient, e.g. auction proceeds. Mailed to them again on
+    /// expiry rather than lost.
+    pub is_kept: bool,
+    /// Unix milliseconds.
+    pub expire_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attachment {
+    pub item: ItemKind,
+    pub count: i32,
+}
+
+impl Attachment {
+    pub fn new(item_id: Id, data_id: i32, level: i16, is_bound: bool, count: i32) -> Self {
+        Self {
+            item: ItemKind { id: item_id, data_id, level, is_bound },
+            count,
+        }
+    }
+}
+
+impl Mail {
+    /// Mail from the server, whose attachments are taken from nobody.
+    pub fn system(recipient_id: Id, subject: String, body: String, gold: i64, items: Vec<Attachment>) -> Self {
+        Self {
+            id: util::id::universal(),
+            recipient_id,
+            sender_id: None,
+            sender_name: SYSTEM_SENDER.to_string(),
+            subject,
+            body,
+            gold,
+            items,
+            is_read: false,
+            is_claimed: false,
+            is_kept: false,
+            expire_at: expire_at(),
+        }
+    }
+
+    pub fn has_attachments(&self) -> bool {
+        self.gold > 0 || !self.items.is_empty()
+    }
+
+    /// Take the attachments from the sender, once per mail. System mail takes nothing.
+    pub fn escrow(&self) -> Option<Operation> {
+        let sender_id = self.sender_id?;
+        if !self.has_attachments() {
+            return None;
+        }
+
+        Some(self.operation(self.id, sender_id, -1))
+    }
+
+    /// Give the attachments to the recipient.
+    pub fn claim(&self) -> Operation {
+        self.operation(util::id::universal(), self.recipient_id, 1)
+    }
+
+    /// Mail sending unclaimed attachments back to the character who sent them, on expiry.
+    /// Kept mail is sent again to its recipient instead.
+    pub fn returned(&self) -> Option<Mail> {
+        if self.is_claimed || !self.has_attachments() {
+            return None;
+        }
+        if self.is_kept {
+            return Some(Mail {
+                id: util::id::universal(),
+                is_read: false,
+                expire_at: expire_at(),
+                ..self.clone()
+            });
+        }
+
+        let sender_id = self.sender_id?;
+        Some(Mail::system(sender_id, self.subject.clone(), String::new(), self.gold, self.items.clone()))
+    }
+
+    fn operation(&self, id: Id, character_id: Id, sign: i32) -> Operation {
+        let mut changes: Vec<_> = self.items
+            .iter()
+            .map(|attachment| Change::Item {
+                character_id,
+                item: attachment.item,
+                delta: sign * attachment.count,
+            })
+            .collect();
+        if self.gold > 0 {
+            changes.push(Change::Gold { character_id, delta: sign as i64 * self.gold });
+        }
+
+        Operation {
+            id,
+            reason: Reason::Mail,
+            source: Some(self.id.to_string()),
+            changes,
+        }
+    }
+}
+
+/// Expiry of mail sent now.
+pub fn expire_at() -> i64 {
+    chrono::Utc::now().timestamp_millis() + LIFETIME.as_millis() as i64
+}
@@ -0,0 +1,739 @@
+use super::*;
+use super::auction::Sort;
+use super::ledger::{Change, Entry, ItemKind};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Repositories in memory, for running without a database.
+#[derive(Clone, Default)]
+pub struct Memory {
+    state: Arc<Mutex<State>>,
+    /// Characters unknown on login are created for the account, e.g. for load bots.
+    create_missing: bool,
+}
+
+#[derive(Default)]
+struct State {
+    accounts: HashSet<Id>,
+    characters: HashMap<Id, CharacterRecord>,
+    paths: HashMap<Id, Vec<PathRecord>>,
+    talents: HashMap<Id, Vec<TalentRecord>>,
+    items: HashMap<Id, Vec<ItemRecord>>,
+    gold: HashMap<Id, i64>,
+    operations: HashSet<Id>,
+    ledger: Vec<Entry>,
+    listings: HashMap<Id, Listing>,
+    mails: HashMap<Id, Mail>,
+}
+
+impl Memory {
+    pub fn new(create_missing: bool) -> Self {
+        Self {
+            state: Arc::default(),
+            create_missing,
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl AccountRepository for Memory {
+    fn create(&self, account_id: Id) -> BoxFuture<'_, Result<(), Error>> {
+        self.state().accounts.insert(account_id);
+
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
+
+impl CharacterRepository for Memory {
+    fn get(&self, character_id: Id) -> BoxFuture<'_, Result<CharacterRecord, Error>> {
+        let result = self.state()
+            .characters
+            .get(&character_id)
+            .cloned()
+            .ok_or(Error::CharacterNotFound(character_id));
+
+        Box::pin(std::future::ready(result))
+    }
+
+    fn is_owned(&self, account_id: Id, character_id: Id) -> BoxFuture<'_, Result<bool, Error>> {
+        let mut state = self.state();
+
+        if self.create_missing && !state.characters.contains_key(&character_id) {
+            state.accounts.insert(account_id);
+            state.characters.insert(character_id, CharacterRecord {
+                id: character_id,
+                account_id,
+                name: format!("{character_id}"),
+                race: Race::Human,
+            });
+        }
+
+        let owned = state.characters
+            .get(&character_id)
+            .is_some_and(|character| character.account_id == account_id);
+
+        Box::pin(std::future::ready(Ok(owned)))
+    }
+
+    fn create(&self, character: CharacterRecord) -> BoxFuture<'_, Result<(), Error>> {
+        self.state().characters.insert(character.id, character);
+
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
+
+impl PathRepository for Memory {
+    fn list(&self, character_id: Id) -> BoxFuture<'_, Result<Vec<PathRecord>, Error>> {
+        let paths = self.state().paths.get(&character_id).cloned().unwrap_or_default();
+
+        Box::pin(std::future::ready(Ok(paths)))
+    }
+}
+
+impl TalentRepository for Memory {
+    fn list(&self, character_id: Id) -> BoxFuture<'_, Result<Vec<TalentRecord>, Error>> {
+        let talents = self.state().talents.get(&character_id).cloned().unwrap_or_default();
+
+        Box::pin(std::future::ready(Ok(talents)))
+    }
+}
+
+impl ItemRepository for Memory {
+    fn list(&self, character_id: Id) -> BoxFuture<'_, Result<Vec<ItemRecord>, Error>> {
+        let items = self.state().items.get(&character_id).cloned().unwrap_or_default();
+
+        Box::pin(std::future::ready(Ok(items)))
+    }
+}
+
+impl LedgerRepository for Memory {
+    fn apply(&self, operation: Operation) -> BoxFuture<'_, Result<Outcome, Error>> {
+        let result = apply_operation(&mut self.state(), &operation);
+
+        Box::pin(std::future::ready(result))
+    }
+
+    fn entries(&self, operation_id: Id) -> BoxFuture<'_, Result<Vec<Entry>, Error>> {
+        let entries = self.state()
+            .ledger
+            .iter()
+            .filter(|entry| entry.operation_id == operation_id)
+            .cloned()
+            .collect();
+
+        Box::pin(std::future::ready(Ok(entries)))
+    }
+}
+
+impl AuctionRepository for Memory {
+    fn create(&self, listing: Listing) -> BoxFuture<'_, Result<(), Error>> {
+        let mut state = self.state();
+
+        let result = apply_operation(&mut state, &listing.escrow()).map(|outcome| {
+            if outcome == Outcome::Applied {
+                state.listings.insert(listing.id, listing);
+            }
+        });
+
+        Box::pin(std::future::ready(result))
+    }
+
+    fn search(&self, filter: Filter) -> BoxFuture<'_, Result<Vec<Listing>, Error>> {
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let mut listings: Vec<_> = self.state()
+            .listings
+            .values()
+            .filter(|listing| listing.expire_at > now && listing.matches(&filter))
+            .cloned()
+            .collect();
+        match filter.sort {
+            Sort::PriceAscending => listings.sort_by_key(|listing| (listing.price, listing.id)),
+            Sort::PriceDescending => listings.sort_by_key(|listing| (-listing.price, listing.id)),
+            Sort::ExpireAt => listings.sort_by_key(|listing| (listing.expire_at, listing.id)),
+        }
+
+        let listings = listings
+            .into_iter()
+            .skip(filter.offset.max(0) as usize)
+            .take(filter.limit.max(0) as usize)
+            .collect();
+
+        Box::pin(std::future::ready(Ok(listings)))
+    }
+
+    fn buy(&self, listing_id: Id, buyer_id: Id) -> BoxFuture<'_, Result<(Listing, Mail), Error>> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut state = self.state();
+
+        let result = match state.listings.get(&listing_id) {
+            Some(listing) if listing.seller_id != buyer_id && listing.expire_at > now => {
+                let listing = listing.clone();
+                apply_operation(&mut state, &listing.sale(buyer_id)).map(|_| {
+                    let proceeds = listing.proceeds();
+                    state.mails.insert(proceeds.id, proceeds.clone());
+                    state.listings.remove(&listing_id);
+                    (listing, proceeds)
+                })
+            }
+            _ => Err(Error::ListingNotFound(listing_id)),
+        };
+
+        Box::pin(std::future::ready(result))
+    }
+
+    fn cancel(&self, listing_id: Id, seller_id: Id) -> BoxFuture<'_, Result<Listing, Error>> {
+        let mut state = self.state();
+
+        let result = match state.listings.get(&listing_id) {
+            Some(listing) if listing.seller_id == seller_id => {
+                let listing = listing.clone();
+                apply_operation(&mut state, &listing.giveback()).map(|_| {
+                    state.listings.remove(&listing_id);
+                    listing
+                })
+            }
+            _ => Err(Error::ListingNotFound(listing_id)),
+        };
+
+        Box::pin(std::future::ready(result))
+    }
+
+    fn expire(&self, limit: i64) -> BoxFuture<'_, Result<Vec<Mail>, Error>> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut state = self.state();
+
+        let mut expired: Vec<_> = state.listings
+            .values()
+            .filter(|listing| listing.expire_at <= now)
+            .cloned()
+            .collect();
+        expired.sort_by_key(|listing| listing.expire_at);
+        expired.truncate(limit.max(0) as usize);
+
+        let unsold: Vec<_> = expired.iter().map(Listing::unsold).collect();
+        for (listing, unsold) in expired.iter().zip(&unsold) {
+            state.mails.insert(unsold.id, unsold.clone());
+            state.listings.remove(&listing.id);
+        }
+
+        Box::pin(std::future::ready(Ok(unsold)))
+    }
+}
+
+impl MailRepository for Memory {
+    fn send(&self, mail: Mail) -> BoxFuture<'_, Result<(), Error>> {
+        let mut state = self.state();
+
+        let result = match mail.escrow() {
+            Some(operation) => apply_operation(&mut state, &operation).map(|outcome| {
+                if outcome == Outcome::Applied {
+                    state.mails.insert(mail.id, mail);
+                }
+            }),
+            None => {
+                state.mails.insert(mail.id, mail);
+                Ok(())
+            }
+        };
+
+        Box::pin(std::future::ready(result))
+    }
+
+    fn list(&self, recipient_id: Id, limit: i64) -> BoxFuture<'_, Result<Vec<Mail>, Error>> {
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let mut mails: Vec<_> = self.state()
+            .mails
+            .values()
+            .filter(|mail| mail.recipient_id == recipient_id && mail.expire_at > now)
+            .cloned()
+            .collect();
+        mails.sort_by_key(|mail| std::cmp::Reverse(mail.id));
+        mails.truncate(limit.max(0) as usize);
+
+        Box::pin(std::future::ready(Ok(mails)))
+    }
+
+    fn count(&self, recipient_id: Id) -> BoxFuture<'_, Result<i64, Error>> {
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let count = self.state()
+            .mails
+            .values()
+            .filter(|mail| mail.recipient_id == recipient_id && mail.expire_at > now)
+            .count();
+
+        Box::pin(std::future::ready(Ok(count as i64)))
+    }
+
+    fn read(&self, mail_id: Id, recipient_id: Id) -> BoxFuture<'_, Result<(), Error>> {
+        let result = match self.state().mails.get_mut(&mail_id) {
+            Some(mail) if mail.recipient_id == recipient_id => {
+                mail.is_read = true;
+                Ok(())
+            }
+            _ => Err(Error::MailNotFound(mail_id)),
+        };
+
+        Box::pin(std::future::ready(result))
+    }
+
+    fn claim(&self, mail_id: Id, recipient_id: Id) -> BoxFuture<'_, Result<Mail, Error>> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut state = self.state();
+
+        let result = match state.mails.get(&mail_id) {
+            Some(mail) if mail.recipient_id == recipient_id && !mail.is_claimed && mail.expire_at > now => {
+                let mut mail = mail.clone();
+                let outcome = if mail.has_attachments() {
+                    apply_operation(&mut state, &mail.claim()).map(|_| ())
+                } else {
+                    Ok(())
+                };
+                outcome.map(|_| {
+                    mail.is_read = true;
+                    mail.is_claimed = true;
+                    state.mails.insert(mail_id, mail.clone());
+                    mail
+                })
+            }
+            _ => Err(Error::MailNotFound(mail_id)),
+        };
+
+        Box::pin(std::future::ready(result))
+    }
+
+    fn delete(&self, mail_id: Id, recipient_id: Id) -> BoxFuture<'_, Result<(), Error>> {
+        let mut state = self.state();
+
+        let result = match state.mails.get(&mail_id) {
+            Some(mail) if mail.recipient_id == recipient_id => {
+                if mail.has_attachments() && !mail.is_claimed {
+                    Err(Error::MailUnclaimed(mail_id))
+                } else {
+                    state.mails.remove(&mail_id);
+                    Ok(())
+                }
+            }
+            _ => Err(Error::MailNotFound(mail_id)),
+        };
+
+        Box::pin(std::future::ready(result))
+    }
+
+    fn expire(&self, limit: i64) -> BoxFuture<'_, Result<usize, Error>> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut state = self.state();
+
+        let mut expired: Vec<_> = state.mails
+            .values()
+            .filter(|mail| mail.expire_at <= now)
+            .cloned()
+            .collect();
+        expired.sort_by_key(|mail| mail.expire_at);
+        expired.truncate(limit.max(0) as usize);
+
+        for mail in &expired {
+            state.mails.remove(&mail.id);
+
+            // Like the foreign key setting the sender of Postgres to null.
+            let returned = mail.returned()
+                .filter(|returned| state.characters.contains_key(&returned.recipient_id));
+            if let Some(returned) = returned {
+                state.mails.insert(returned.id, returned);
+            }
+        }
+
+        Box::pin(std::future::ready(Ok(expired.len())))
+    }
+}
+
+/// Apply all the changes of the operation, or none.
+fn apply_operation(state: &mut State, operation: &Operation) -> Result<Outcome, Error> {
+    if state.operations.contains(&operation.id) {
+        return Ok(Outcome::Duplicate);
+    }
+
+    if let Some(change) = operation.changes.iter().find(|change| !state.characters.contains_key(&change.character_id())) {
+        return Err(Error::CharacterNotFound(change.character_id()));
+    }
+
+    // Changed on copies, kept only if all changes apply.
+    let mut items = state.items.clone();
+    let mut gold = state.gold.clone();
+
+    let entries = operation.changes
+        .iter()
+        .map(|change| {
+            let balance = match change {
+                Change::Gold { character_id, delta } => {
+                    apply_gold(&mut gold, *character_id, *delta)?
+                }
+                Change::Item { character_id, item, delta } => {
+                    apply_item(&mut items, *character_id, item, *delta)?
+                }
+            };
+
+            Ok(entry(operation.id, change, balance))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    state.items = items;
+    state.gold = gold;
+    state.operations.insert(operation.id);
+    state.ledger.extend(entries);
+
+    Ok(Outcome::Applied)
+}
+
+fn apply_gold(gold: &mut HashMap<Id, i64>, character_id: Id, delta: i64) -> Result<i64, Error> {
+    let balance = gold.entry(character_id).or_default();
+    if *balance + delta < 0 {
+        return Err(Error::InsufficientGold(character_id));
+    }
+    *balance += delta;
+
+    Ok(*balance)
+}
+
+fn apply_item(
+    items: &mut HashMap<Id, Vec<ItemRecord>>,
+    character_id: Id,
+    kind: &ItemKind,
+    delta: i32,
+) -> Result<i64, Error> {
+    let owned = items.entry(character_id).or_default();
+
+    let index = match owned.iter().position(|item| item.id == kind.id) {
+        Some(index) => index,
+        None if delta > 0 => {
+            owned.push(ItemRecord {
+                id: kind.id,
+                character_id,
+                data_id: kind.data_id,
+                count: 0,
+                level: kind.level,
+                is_bound: kind.is_bound,
+            });
+            owned.len() - 1
+        }
+        None => return Err(Error::InsufficientItem { character_id, item_id: kind.id }),
+    };
+
+    let count = owned[index].count + delta;
+    if count < 0 {
+        return Err(Error::InsufficientItem { character_id, item_id: kind.id });
+    }
+    if count == 0 {
+        owned.remove(index);
+    } else {
+        owned[index].count = count;
+    }
+
+    Ok(count as i64)
+}
+
+fn entry(operation_id: Id, change: &Change, balance: i64) -> Entry {
+    let (item, delta) = match change {
+        Change::Gold { delta, .. } => (None, *delta),
+        Change::Item { item, delta, .. } => (Some(*item), *delta as i64),
+    };
+
+    Entry {
+        operation_id,
+        character_id: change.character_id(),
+        item,
+        delta,
+        balance,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::auction::Category;
+    use super::super::ledger::{rollback_with, Reason};
+    use super::super::mail::Attachment;
+    use futures::executor::block_on;
+
+    const SELLER: Id = 1;
+    const BUYER: Id = 2;
+
+    fn init() {
+        static INIT: std::sync::Once = std::sync::Once::new();
+        INIT.call_once(|| util::id::init(0));
+    }
+
+    /// Memory with the seller and the buyer.
+    fn memory() -> Memory {
+        let memory = Memory::new(false);
+        for character_id in [SELLER, BUYER] {
+            block_on(CharacterRepository::create(&memory, CharacterRecord {
+                id: character_id,
+                account_id: character_id,
+                name: format!("{character_id}"),
+                race: Race::Human,
+            })).unwrap();
+        }
+
+        memory
+    }
+
+    fn give(memory: &Memory, changes: Vec<Change>) {
+        block_on(memory.apply(Operation {
+            id: util::id::universal(),
+            reason: Reason::Cheat,
+            source: None,
+            changes,
+        })).unwrap();
+    }
+
+    #[test]
+    fn auction_escrows_and_sells() {
+        init();
+
+        let memory = memory();
+        let item = ItemKind { id: 100, data_id: 1, level: 0, is_bound: false };
+        give(&memory, vec![
+            Change::Item { character_id: SELLER, item, delta: 3 },
+            Change::Gold { character_id: BUYER, delta: 50 },
+        ]);
+
+        let listing = Listing {
+            id: 10,
+            seller_id: SELLER,
+            item,
+            count: 2,
+            category: Category::Other,
+            price: 40,
+            expire_at: i64::MAX,
+        };
+        block_on(AuctionRepository::create(&memory, listing)).unwrap();
+        assert_eq!(block_on(ItemRepository::list(&memory, SELLER)).unwrap()[0].count, 1);
+
+        assert!(matches!(block_on(memory.buy(10, SELLER)), Err(Error::ListingNotFound(10))));
+        block_on(memory.buy(10, BUYER)).unwrap();
+
+        assert_eq!(block_on(ItemRepository::list(&memory, BUYER)).unwrap()[0].count, 2);
+        assert_eq!(memory.state().gold[&BUYER], 10);
+        assert!(block_on(memory.search(Filter { limit: 10, ..Default::default() })).unwrap().is_empty());
+
+        let proceeds = block_on(MailRepository::list(&memory, SELLER, 10)).unwrap();
+        assert_eq!(proceeds[0].gold, 40);
+        block_on(memory.claim(proceeds[0].id, SELLER)).unwrap();
+        assert_eq!(memory.state().gold[&SELLER], 40);
+    }
+
+    #[test]
+    fn mail_escrows_until_claimed() {
+        init();
+
+        let memory = memory();
+        let item = ItemKind { id: 100, data_id: 1, level: 0, is_bound: false };
+        give(&memory, vec![
+            Change::Item { character_id: SELLER, item, delta: 1 },
+            Change::Gold { character_id: SELLER, delta: 30 },
+        ]);
+
+        let mail = Mail {
+            sender_id: Some(SELLER),
+            sender_name: "Seller".to_string(),
+            ..Mail::system(BUYER, String::new(), String::new(), 20, vec![Attachment { item, count: 1 }])
+        };
+        let mail_id = mail.id;
+        block_on(memory.send(mail)).unwrap();
+        assert_eq!(memory.state().gold[&SELLER], 10);
+        assert!(block_on(ItemRepository::list(&memory, SELLER)).unwrap().is_empty());
+
+        assert!(matches!(block_on(memory.delete(mail_id, BUYER)), Err(Error::MailUnclaimed(_))));
+        assert!(matches!(block_on(memory.claim(mail_id, SELLER)), Err(Error::MailNotFound(_))));
+        block_on(memory.claim(mail_id, BUYER)).unwrap();
+        assert!(matches!(block_on(memory.claim(mail_id, BUYER)), Err(Error::MailNotFound(_))));
+
+        assert_eq!(memory.state().gold[&BUYER], 20);
+        assert_eq!(block_on(ItemRepository::list(&memory, BUYER)).unwrap()[0].count, 1);
+        block_on(memory.delete(mail_id, BUYER)).unwrap();
+    }
+
+    #[test]
+    fn expired_mail_returns_attachments() {
+        init();
+
+        let memory = memory();
+        give(&memory, vec![Change::Gold { character_id: SELLER, delta: 30 }]);
+
+        let mail = Mail {
+            sender_id: Some(SELLER),
+            expire_at: 0,
+            ..Mail::system(BUYER, "Gift".to_string(), String::new(), 30, Vec::new())
+        };
+        block_on(memory.send(mail)).unwrap();
+        assert_eq!(block_on(MailRepository::expire(&memory, 10)).unwrap(), 1);
+
+        assert!(block_on(MailRepository::list(&memory, BUYER, 10)).unwrap().is_empty());
+        let returned = block_on(MailRepository::list(&memory, SELLER, 10)).unwrap();
+        assert_eq!((returned[0].sender_id, returned[0].gold), (None, 30));
+    }
+
+    #[test]
+    fn expired_mail_of_deleted_sender_is_dropped() {
+        init();
+
+        let memory = memory();
+        give(&memory, vec![Change::Gold { character_id: SELLER, delta: 30 }]);
+
+        for gold in [10, 20] {
+            let mail = Mail {
+                sender_id: Some(SELLER),
+                expire_at: 0,
+                ..Mail::system(BUYER, "Gift".to_string(), String::new(), gold, Vec::new())
+            };
+            block_on(memory.send(mail)).unwrap();
+        }
+        memory.state().characters.remove(&SELLER);
+
+        assert_eq!(block_on(MailRepository::expire(&memory, 10)).unwrap(), 2);
+        assert!(memory.state().mails.is_empty());
+    }
+
+    #[test]
+    fn expired_auction_mail_is_kept() {
+        init();
+
+        let memory = memory();
+        let item = ItemKind { id: 100, data_id: 1, level: 0, is_bound: false };
+        give(&memory, vec![Change::Item { character_id: SELLER, item, delta: 1 }]);
+
+        let listing = Listing {
+            id: 10,
+            seller_id: SELLER,
+            item,
+            count: 1,
+            category: Category::Other,
+            price: 40,
+            expire_at: 0,
+        };
+        block_on(AuctionRepository::create(&memory, listing)).unwrap();
+        let unsold = block_on(AuctionRepository::expire(&memory, 10)).unwrap();
+        assert_eq!(unsold.len(), 1);
+
+        memory.state().mails.get_mut(&unsold[0].id).unwrap().expire_at = 0;
+        assert_eq!(block_on(MailRepository::expire(&memory, 10)).unwrap(), 1);
+
+        let renewed = block_on(MailRepository::list(&memory, SELLER, 10)).unwrap();
+        assert_ne!(renewed[0].id, unsold[0].id);
+        assert_eq!((renewed[0].is_kept, renewed[0].items.clone()), (true, unsold[0].items.clone()));
+    }
+
+    fn gold(memory: &Memory, character_id: Id) -> i64 {
+        memory.state().gold.get(&character_id).copied().unwrap_or_default()
+    }
+
+    #[test]
+    fn operation_applies_once() {
+        init();
+
+        let memory = memory();
+        let operation = Operation {
+            id: util::id::universal(),
+            reason: Reason::Cheat,
+            source: None,
+            changes: vec![Change::Gold { character_id: SELLER, delta: 30 }],
+        };
+        assert!(matches!(block_on(memory.apply(operation.clone())), Ok(Outcome::Applied)));
+        assert!(matches!(block_on(memory.apply(operation.clone())), Ok(Outcome::Duplicate)));
+
+        assert_eq!(gold(&memory, SELLER), 30);
+        assert_eq!(block_on(memory.entries(operation.id)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn failing_change_applies_nothing() {
+        init();
+
+        let memory = memory();
+        let item = ItemKind { id: 100, data_id: 1, level: 0, is_bound: false };
+        give(&memory, vec![Change::Gold { character_id: SELLER, delta: 10 }]);
+
+        let operation = Operation {
+            id: util::id::universal(),
+            reason: Reason::Cheat,
+            source: None,
+            changes: vec![
+                Change::Gold { character_id: BUYER, delta: 20 },
+                Change::Item { character_id: BUYER, item, delta: 1 },
+                Change::Gold { character_id: SELLER, delta: -20 },
+            ],
+        };
+        assert!(matches!(block_on(memory.apply(operation.clone())), Err(Error::InsufficientGold(SELLER))));
+
+        assert_eq!((gold(&memory, SELLER), gold(&memory, BUYER)), (10, 0));
+        assert!(block_on(ItemRepository::list(&memory, BUYER)).unwrap().is_empty());
+        assert!(block_on(memory.entries(operation.id)).unwrap().is_empty());
+
+        // Not even recorded as applied.
+        give(&memory, vec![Change::Gold { character_id: SELLER, delta: 10 }]);
+        assert!(matches!(block_on(memory.apply(operation)), Ok(Outcome::Applied)));
+    }
+
+    #[test]
+    fn unknown_character_is_not_credited() {
+        init();
+
+        let memory = memory();
+        let result = block_on(memory.apply(Operation {
+            id: util::id::universal(),
+            reason: Reason::Cheat,
+            source: None,
+            changes: vec![
+                Change::Gold { character_id: SELLER, delta: 10 },
+                Change::Gold { character_id: 3, delta: 10 },
+            ],
+        }));
+
+        assert!(matches!(result, Err(Error::CharacterNotFound(3))));
+        assert_eq!(gold(&memory, SELLER), 0);
+        assert!(!memory.state().gold.contains_key(&3));
+    }
+
+    #[test]
+    fn rollback_restores_balances() {
+        init();
+
+        let memory = memory();
+        let item = ItemKind { id: 100, data_id: 1, level: 0, is_bound: false };
+        give(&memory, vec![
+            Change::Item { character_id: SELLER, item, delta: 2 },
+            Change::Gold { character_id: BUYER, delta: 50 },
+        ]);
+
+        let operation_id = util::id::universal();
+        block_on(memory.apply(Operation {
+            id: operation_id,
+            reason: Reason::Cheat,
+            source: None,
+            changes: vec![
+                Change::Item { character_id: SELLER, item, delta: -2 },
+                Change::Item { character_id: BUYER, item, delta: 2 },
+                Change::Gold { character_id: BUYER, delta: -40 },
+                Change::Gold { character_id: SELLER, delta: 40 },
+            ],
+        })).unwrap();
+
+        let rollback_id = util::id::universal();
+        let outcome = block_on(rollback_with(&memory, operation_id, rollback_id));
+        assert!(matches!(outcome, Ok(Outcome::Applied)));
+
+        assert_eq!((gold(&memory, SELLER), gold(&memory, BUYER)), (0, 50));
+        assert_eq!(block_on(ItemRepository::list(&memory, SELLER)).unwrap()[0].count, 2);
+        assert!(block_on(ItemRepository::list(&memory, BUYER)).unwrap().is_empty());
+        assert_eq!(block_on(memory.entries(rollback_id)).unwrap().len(), 4);
+
+        let outcome = block_on(rollback_with(&memory, util::id::universal(), 1));
+        assert!(matches!(outcome, Err(Error::OperationNotFound(_))));
+    }
+}
@@ -0,0 +1,849 @@
+use super::*;
+use super::ledger::{Change, Entry, ItemKind};
+use super::mail::Attachment;
+use db::auction::Listing as ListingModel;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+
+/// Repositories on the global `db` pool.
+pub struct Postgres;
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = db::schema::character)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct CharacterModel {
+    id: i64,
+    account_id: i64,
+    name: String,
+    race: Race,
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = db::schema::character_path)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct PathModel {
+    data_id: i32,
+    is_active: bool,
+    level: i16,
+    exp: i32,
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = db::schema::character_talent)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct TalentModel {
+    data_id: i32,
+    level: i16,
+    exp: i32,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = db::schema::item)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct ItemModel {
+    id: i64,
+    character_id: i64,
+    data_id: i32,
+    count: i32,
+    level: i16,
+    is_bound: bool,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = db::schema::mail)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct MailModel {
+    id: i64,
+    recipient_id: i64,
+    sender_id: Option<i64>,
+    sender_name: String,
+    subject: String,
+    body: String,
+    gold: i64,
+    is_read: bool,
+    is_claimed: bool,
+    is_kept: bool,
+    expire_at: i64,
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = db::schema::mail_attachment)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct AttachmentModel {
+    mail_id: i64,
+    item_id: i64,
+    item_data_id: i32,
+    item_level: i16,
+    item_is_bound: bool,
+    item_count: i32,
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = db::schema::ledger)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+struct LedgerModel {
+    operation_id: i64,
+    character_id: i64,
+    item_id: Option<i64>,
+    item_data_id: Option<i32>,
+    item_level: Option<i16>,
+    item_is_bound: Option<bool>,
+    delta: i64,
+    balance: i64,
+}
+
+impl AccountRepository for Postgres {
+    fn create(&self, account_id: Id) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            use db::schema::account::dsl::*;
+
+            let mut conn = db::conn().await?;
+            diesel::insert_into(account)
+                .values(id.eq(account_id))
+                .execute(&mut conn)
+                .await
+                .map_err(db::Error::from)?;
+
+            Ok(())
+        })
+    }
+}
+
+impl CharacterRepository for Postgres {
+    fn get(&self, character_id: Id) -> BoxFuture<'_, Result<CharacterRecord, Error>> {
+        Box::pin(async move {
+            use db::schema::character::dsl::*;
+
+            let mut conn = db::conn().await?;
+            let model = character
+                .select(CharacterModel::as_select())
+                .filter(id.eq(character_id))
+                .first(&mut conn)
+                .await
+                .optional()
+                .map_err(db::Error::from)?
+                .ok_or(Error::CharacterNotFound(character_id))?;
+
+            Ok(model.into())
+        })
+    }
+
+    fn is_owned(&self, owner_id: Id, character_id: Id) -> BoxFuture<'_, Result<bool, Error>> {
+        Box::pin(async move {
+            use db::schema::character::dsl::*;
+
+            let mut conn = db::conn().await?;
+            let count: i64 = character
+                .filter(id.eq(character_id))
+                .filter(account_id.eq(owner_id))
+                .count()
+                .get_result(&mut conn)
+                .await
+                .map_err(db::Error::from)?;
+
+            Ok(count > 0)
+        })
+    }
+
+    fn create(&self, record: CharacterRecord) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            use db::schema::character::dsl::*;
+
+            let mut conn = db::conn().await?;
+            diesel::insert_into(character)
+                .values(CharacterModel::from(record))
+                .execute(&mut conn)
+                .await
+                .map_err(db::Error::from)?;
+
+            Ok(())
+        })
+    }
+}
+
+impl PathRepository for Postgres {
+    fn list(&self, owner_id: Id) -> BoxFuture<'_, Result<Vec<PathRecord>, Error>> {
+        Box::pin(async move {
+            use db::schema::character_path::dsl::*;
+
+            let mut conn = db::conn().await?;
+            let models = character_path
+                .filter(character_id.eq(owner_id))
+                .select(PathModel::as_select())
+                .load(&mut conn)
+                .await
+                .map_err(db::Error::from)?;
+
+            Ok(models.into_iter().map(|model| PathRecord {
+                data_id: model.data_id,
+                is_active: model.is_active,
+                level: model.level,
+                exp: model.exp,
+            }).collect())
+        })
+    }
+}
+
+impl TalentRepository for Postgres {
+    fn list(&self, owner_id: Id) -> BoxFuture<'_, Result<Vec<TalentRecord>, Error>> {
+        Box::pin(async move {
+            use db::schema::character_talent::dsl::*;
+
+            let mut conn = db::conn().await?;
+            let models = character_talent
+                .filter(character_id.eq(owner_id))
+                .select(TalentModel::as_select())
+                .load(&mut conn)
+                .await
+                .map_err(db::Error::from)?;
+
+            Ok(models.into_iter().map(|model| TalentRecord {
+                data_id: model.data_id,
+                level: model.level,
+                exp: model.exp,
+            }).collect())
+        })
+    }
+}
+
+impl ItemRepository for Postgres {
+    fn list(&self, owner_id: Id) -> BoxFuture<'_, Result<Vec<ItemRecord>, Error>> {
+        Box::pin(async move {
+            use db::schema::item::dsl::*;
+
+            let mut conn = db::conn().await?;
+            let models = item
+                .filter(character_id.eq(owner_id))
+                .select(ItemModel::as_select())
+                .load(&mut conn)
+                .await
+                .map_err(db::Error::from)?;
+
+            Ok(models.into_iter().map(ItemRecord::from).collect())
+        })
+    }
+}
+
+impl LedgerRepository for Postgres {
+    fn apply(&self, operation: Operation) -> BoxFuture<'_, Result<Outcome, Error>> {
+        Box::pin(async move {
+            let mut conn = db::conn().await?;
+
+            conn.transaction::<_, Error, _>(|conn| async move {
+                apply_operation(conn, &operation).await
+            }.scope_boxed()).await
+        })
+    }
+
+    fn entries(&self, operation: Id) -> BoxFuture<'_, Result<Vec<Entry>, Error>> {
+        Box::pin(async move {
+            use db::schema::ledger::dsl::*;
+
+            let mut conn = db::conn().await?;
+            let models = ledger
+                .filter(operation_id.eq(operation))
+                .order(id)
+                .select(LedgerModel::as_select())
+                .load(&mut conn)
+                .await?;
+
+            Ok(models.into_iter().map(Entry::from).collect())
+        })
+    }
+}
+
+impl AuctionRepository for Postgres {
+    fn create(&self, listing: Listing) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            let mut conn = db::conn().await?;
+
+            conn.transaction::<_, Error, _>(|conn| async move {
+                use db::schema::auction_listing::dsl::*;
+
+                if apply_operation(conn, &listing.escrow()).await? == Outcome::Duplicate {
+                    return Ok(());
+                }
+
+                diesel::insert_into(auction_listing)
+                    .values(ListingModel::from(&listing))
+                    .execute(conn)
+                    .await?;
+
+                Ok(())
+            }.scope_boxed()).await
+        })
+    }
+
+    fn search(&self, filter: Filter) -> BoxFuture<'_, Result<Vec<Listing>, Error>> {
+        Box::pin(async move {
+            let mut conn = db::conn().await?;
+            let now = chrono::Utc::now().timestamp_millis();
+            let models = db::auction::search(&mut conn, &filter, now).await?;
+
+            Ok(models.into_iter().map(Listing::from).collect())
+        })
+    }
+
+    fn buy(&self, listing_id: Id, buyer_id: Id) -> BoxFuture<'_, Result<(Listing, Mail), Error>> {
+        Box::pin(async move {
+            let mut conn = db::conn().await?;
+
+            conn.transaction::<_, Error, _>(|conn| async move {
+                use db::schema::auction_listing::dsl::*;
+
+                let now = chrono::Utc::now().timestamp_millis();
+                let model = diesel::delete(auction_listing
+                    .find(listing_id)
+                    .filter(seller_id.ne(buyer_id))
+                    .filter(expire_at.gt(now)))
+                    .returning(ListingModel::as_returning())
+                    .get_result(conn)
+                    .await
+                    .optional()?
+                    .ok_or(Error::ListingNotFound(listing_id))?;
+
+                let listing = Listing::from(model);
+                apply_operation(conn, &listing.sale(buyer_id)).await?;
+                let proceeds = listing.proceeds();
+                insert_mail(conn, &proceeds).await?;
+
+                Ok((listing, proceeds))
+            }.scope_boxed()).await
+        })
+    }
+
+    fn cancel(&self, listing_id: Id, seller: Id) -> BoxFuture<'_, Result<Listing, Error>> {
+        Box::pin(async move {
+            let mut conn = db::conn().await?;
+
+            conn.transaction::<_, Error, _>(|conn| async move {
+                use db::schema::auction_listing::dsl::*;
+
+                let model = diesel::delete(auction_listing.find(listing_id).filter(seller_id.eq(seller)))
+                    .returning(ListingModel::as_returning())
+                    .get_result(conn)
+                    .await
+                    .optional()?
+                    .ok_or(Error::ListingNotFound(listing_id))?;
+
+                let listing = Listing::from(model);
+                apply_operation(conn, &listing.giveback()).await?;
+
+                Ok(listing)
+            }.scope_boxed()).await
+        })
+    }
+
+    fn expire(&self, limit: i64) -> BoxFuture<'_, Result<Vec<Mail>, Error>> {
+        Box::pin(async move {
+            let mut conn = db::conn().await?;
+
+            conn.transaction::<_, Error, _>(|conn| async move {
+                use db::schema::auction_listing::dsl::*;
+
+                // Other nodes expire the rest.
+                let models = auction_listing
+                    .filter(expire_at.le(chrono::Utc::now().timestamp_millis()))
+                    .order(expire_at)
+                    .limit(limit)
+                    .for_update()
+                    .skip_locked()
+                    .select(ListingModel::as_select())
+                    .load(conn)
+                    .await?;
+
+                let listings: Vec<_> = models.into_iter().map(Listing::from).collect();
+                let ids: Vec<_> = listings.iter().map(|listing| listing.id).collect();
+                diesel::delete(auction_listing.filter(id.eq_any(ids)))
+                    .execute(conn)
+                    .await?;
+                let unsold: Vec<_> = listings.iter().map(Listing::unsold).collect();
+                for unsold in &unsold {
+                    insert_mail(conn, unsold).await?;
+                }
+
+                Ok(unsold)
+            }.scope_boxed()).await
+        })
+    }
+}
+
+impl MailRepository for Postgres {
+    fn send(&self, mail: Mail) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            let mut conn = db::conn().await?;
+
+            conn.transaction::<_, Error, _>(|conn| async move {
+                if let Some(operation) = mail.escrow() {
+                    if apply_operation(conn, &operation).await? == Outcome::Duplicate {
+                        return Ok(());
+                    }
+                }
+
+                insert_mail(conn, &mail).await
+            }.scope_boxed()).await
+        })
+    }
+
+    fn list(&self, recipient: Id, limit: i64) -> BoxFuture<'_, Result<Vec<Mail>, Error>> {
+        Box::pin(async move {
+            use db::schema::mail::dsl::*;
+
+            let mut conn = db::conn().await?;
+            let models = mail
+                .filter(recipient_id.eq(recipient))
+                .filter(expire_at.gt(chrono::Utc::now().timestamp_millis()))
+                .order((created_at.desc(), id.desc()))
+                .limit(limit)
+                .select(MailModel::as_select())
+                .load(&mut conn)
+                .await?;
+
+            load_attachments(&mut conn, models).await
+        })
+    }
+
+    fn count(&self, recipient: Id) -> BoxFuture<'_, Result<i64, Error>> {
+        Box::pin(async move {
+            use db::schema::mail::dsl::*;
+
+            let mut conn = db::conn().await?;
+            let count = mail
+                .filter(recipient_id.eq(recipient))
+                .filter(expire_at.gt(chrono::Utc::now().timestamp_millis()))
+                .count()
+                .get_result(&mut conn)
+                .await?;
+
+            Ok(count)
+        })
+    }
+
+    fn read(&self, mail_id: Id, recipient: Id) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            use db::schema::mail::dsl::*;
+
+            let mut conn = db::conn().await?;
+            let updated = diesel::update(mail.find(mail_id).filter(recipient_id.eq(recipient)))
+                .set(is_read.eq(true))
+                .execute(&mut conn)
+                .await?;
+            if updated == 0 {
+                return Err(Error::MailNotFound(mail_id));
+            }
+
+            Ok(())
+        })
+    }
+
+    fn claim(&self, mail_id: Id, recipient: Id) -> BoxFuture<'_, Result<Mail, Error>> {
+        Box::pin(async move {
+            let mut conn = db::conn().await?;
+
+            conn.transaction::<_, Error, _>(|conn| async move {
+                use db::schema::mail::dsl::*;
+
+                let model = diesel::update(mail
+                    .find(mail_id)
+                    .filter(recipient_id.eq(recipient))
+                    .filter(is_claimed.eq(false))
+                    .filter(expire_at.gt(chrono::Utc::now().timestamp_millis())))
+                    .set((is_claimed.eq(true), is_read.eq(true)))
+                    .returning(MailModel::as_returning())
+                    .get_result(conn)
+                    .await
+                    .optional()?
+                    .ok_or(Error::MailNotFound(mail_id))?;
+
+                let claimed = load_attachments(conn, vec![model]).await?.remove(0);
+                if claimed.has_attachments() {
+                    apply_operation(conn, &claimed.claim()).await?;
+                }
+
+                Ok(claimed)
+            }.scope_boxed()).await
+        })
+    }
+
+    fn delete(&self, mail_id: Id, recipient: Id) -> BoxFuture<'_, Result<(), Error>> {
+        Box::pin(async move {
+            let mut conn = db::conn().await?;
+
+            conn.transaction::<_, Error, _>(|conn| async move {
+                use db::schema::mail::dsl::*;
+
+                let model = mail
+                    .find(mail_id)
+                    .filter(recipient_id.eq(recipient))
+                    .for_update()
+                    .select(MailModel::as_select())
+                    .get_result(conn)
+                    .await
+                    .optional()?
+                    .ok_or(Error::MailNotFound(mail_id))?;
+
+                let found = load_attachments(conn, vec![model]).await?.remove(0);
+                if found.has_attachments() && !found.is_claimed {
+                    return Err(Error::MailUnclaimed(mail_id));
+                }
+
+                diesel::delete(mail.find(mail_id)).execute(conn).await?;
+
+                Ok(())
+            }.scope_boxed()).await
+        })
+    }
+
+    fn expire(&self, limit: i64) -> BoxFuture<'_, Result<usize, Error>> {
+        Box::pin(async move {
+            let mut conn = db::conn().await?;
+
+            conn.transaction::<_, Error, _>(|conn| async move {
+                use db::schema::mail::dsl::*;
+
+                // Other nodes expire the rest.
+                let models = mail
+                    .filter(expire_at.le(chrono::Utc::now().timestamp_millis()))
+                    .order(expire_at)
+                    .limit(limit)
+                    .for_update()
+                    .skip_locked()
+                    .select(MailModel::as_select())
+                    .load(conn)
+                    .await?;
+
+                let expired = load_attachments(conn, models).await?;
+                let ids: Vec<_> = expired.iter().map(|expired| expired.id).collect();
+                diesel::delete(mail.filter(id.eq_any(ids)))
+                    .execute(conn)
+                    .await?;
+                for returned in expired.iter().filter_map(Mail::returned) {
+                    insert_mail(conn, &returned).await?;
+                }
+
+                Ok(expired.len())
+            }.scope_boxed()).await
+        })
+    }
+}
+
+/// Insert the mail with its attachments, already taken from the sender if any.
+async fn insert_mail(conn: &mut AsyncPgConnection, new_mail: &Mail) -> Result<(), Error> {
+    use db::schema::{mail, mail_attachment};
+
+    diesel::insert_into(mail::table)
+        .values(MailModel::from(new_mail))
+        .execute(conn)
+        .await?;
+
+    let attachments: Vec<_> = new_mail.items
+        .iter()
+        .map(|attachment| AttachmentModel {
+            mail_id: new_mail.id,
+            item_id: attachment.item.id,
+            item_data_id: attachment.item.data_id,
+            item_level: attachment.item.level,
+            item_is_bound: attachment.item.is_bound,
+            item_count: attachment.count,
+        })
+        .collect();
+    if !attachments.is_empty() {
+        diesel::insert_into(mail_attachment::table)
+            .values(&attachments)
+            .execute(conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn load_attachments(conn: &mut AsyncPgConnection, models: Vec<MailModel>) -> Result<Vec<Mail>, Error> {
+    use db::schema::mail_attachment::dsl::*;
+
+    let ids: Vec<_> = models.iter().map(|model| model.id).collect();
+    let attachments = mail_attachment
+        .filter(mail_id.eq_any(ids))
+        .select(AttachmentModel::as_select())
+        .load(conn)
+        .await?;
+
+    let mails = models
+        .into_iter()
+        .map(|model| {
+            let items = attachments
+                .iter()
+                .filter(|attachment| attachment.mail_id == model.id)
+                .map(|attachment| Attachment {
+                    item: ItemKind {
+                        id: attachment.item_id,
+                        data_id: attachment.item_data_id,
+                        level: attachment.item_level,
+                        is_bound: attachment.item_is_bound,
+                    },
+                    count: attachment.item_count,
+                })
+                .collect();
+
+            Mail {
+                id: model.id,
+                recipient_id: model.recipient_id,
+                sender_id: model.sender_id,
+                sender_name: model.sender_name,
+                subject: model.subject,
+                body: model.body,
+                gold: model.gold,
+                items,
+                is_read: model.is_read,
+                is_claimed: model.is_claimed,
+                is_kept: model.is_kept,
+                expire_at: model.expire_at,
+            }
+        })
+        .collect();
+
+    Ok(mails)
+}
+
+/// Apply the changes of the operation within a transaction.
+async fn apply_operation(conn: &mut AsyncPgConnection, operation: &Operation) -> Result<Outcome, Error> {
+    use db::schema::ledger_operation::dsl::*;
+
+    let inserted = diesel::insert_into(ledger_operation)
+        .values((
+            id.eq(operation.id),
+            reason.eq(operation.reason.as_str()),
+            source.eq(&operation.source),
+        ))
+        .on_conflict_do_nothing()
+        .execute(conn)
+        .await?;
+    if inserted == 0 {
+        return Ok(Outcome::Duplicate);
+    }
+
+    // Rows locked in the same order by every operation, so that concurrent ones never deadlock:
+    // by character, the gold before the items by id. Stable, keeping the order of the changes of
+    // a row.
+    let mut changes: Vec<&Change> = operation.changes.iter().collect();
+    changes.sort_by_key(|change| match change {
+        Change::Gold { character_id, .. } => (*character_id, None),
+        Change::Item { character_id, item, .. } => (*character_id, Some(item.id)),
+    });
+
+    for change in changes {
+        let balance = match change {
+            Change::Gold { character_id, delta } => {
+                apply_gold(conn, *character_id, *delta).await?
+            }
+            Change::Item { character_id, item, delta } => {
+                apply_item(conn, *character_id, item, *delta).await?
+            }
+        };
+
+        record(conn, operation.id, change, balance).await?;
+    }
+
+    Ok(Outcome::Applied)
+}
+
+/// New gold of the character, never below 0.
+async fn apply_gold(conn: &mut AsyncPgConnection, owner_id: Id, delta: i64) -> Result<i64, Error> {
+    use db::schema::character::dsl::*;
+
+    diesel::update(character.filter(id.eq(owner_id)).filter((gold + delta).ge(0)))
+        .set(gold.eq(gold + delta))
+        .returning(gold)
+        .get_result(conn)
+        .await
+        .optional()?
+        .ok_or(Error::InsufficientGold(owner_id))
+}
+
+/// New count of the item, deleted at 0.
+async fn apply_item(
+    conn: &mut AsyncPgConnection,
+    owner_id: Id,
+    kind: &ItemKind,
+    delta: i32,
+) -> Result<i64, Error> {
+    use db::schema::item::dsl::*;
+
+    let insufficient = Error::InsufficientItem { character_id: owner_id, item_id: kind.id };
+
+    let new_count: i32 = if delta > 0 {
+        diesel::insert_into(item)
+            .values(ItemModel {
+                id: kind.id,
+                character_id: owner_id,
+                data_id: kind.data_id,
+                count: delta,
+                level: kind.level,
+                is_bound: kind.is_bound,
+            })
+            .on_conflict((id, character_id))
+            .do_update()
+            .set(count.eq(count + excluded(count)))
+            .returning(count)
+            .get_result(conn)
+            .await?
+    } else {
+        diesel::update(item.find((kind.id, owner_id)).filter((count + delta).ge(0)))
+            .set(count.eq(count + delta))
+            .returning(count)
+            .get_result(conn)
+            .await
+            .optional()?
+            .ok_or(insufficient)?
+    };
+
+    if new_count == 0 {
+        diesel::delete(item.find((kind.id, owner_id)))
+            .execute(conn)
+            .await?;
+    }
+
+    Ok(new_count as i64)
+}
+
+async fn record(
+    conn: &mut AsyncPgConnection,
+    operation: Id,
+    change: &Change,
+    new_balance: i64,
+) -> Result<(), Error> {
+    use db::schema::ledger::dsl::*;
+
+    let (kind, change_delta) = match change {
+        Change::Gold { delta, .. } => (None, *delta),
+        Change::Item { item, delta, .. } => (Some(item), *delta as i64),
+    };
+
+    diesel::insert_into(ledger)
+        .values((
+            operation_id.eq(operation),
+            character_id.eq(change.character_id()),
+            item_id.eq(kind.map(|kind| kind.id)),
+            item_data_id.eq(kind.map(|kind| kind.data_id)),
+            item_level.eq(kind.map(|kind| kind.level)),
+            item_is_bound.eq(kind.map(|kind| kind.is_bound)),
+            delta.eq(change_delta),
+            balance.eq(new_balance),
+        ))
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+impl From<CharacterModel> for CharacterRecord {
+    fn from(model: CharacterModel) -> Self {
+        Self {
+            id: model.id,
+            account_id: model.account_id,
+            name: model.name,
+            race: model.race,
+        }
+    }
+}
+
+impl From<CharacterRecord> for CharacterModel {
+    fn from(record: CharacterRecord) -> Self {
+        Self {
+            id: record.id,
+            account_id: record.account_id,
+            name: record.name,
+            race: record.race,
+        }
+    }
+}
+
+impl From<ItemModel> for ItemRecord {
+    fn from(model: ItemModel) -> Self {
+        Self {
+            id: model.id,
+            character_id: model.character_id,
+            data_id: model.data_id,
+            count: model.count,
+            level: model.level,
+            is_bound: model.is_bound,
+        }
+    }
+}
+
+impl From<LedgerModel> for Entry {
+    fn from(model: LedgerModel) -> Self {
+        let item = match (model.item_id, model.item_data_id, model.item_level, model.item_is_bound) {
+            (Some(id), Some(data_id), Some(level), Some(is_bound)) => Some(ItemKind {
+                id,
+                data_id,
+                level,
+                is_bound,
+            }),
+            _ => None,
+        };
+
+        Self {
+            operation_id: model.operation_id,
+            character_id: model.character_id,
+            item,
+            delta: model.delta,
+            balance: model.balance,
+        }
+    }
+}
+
+impl From<ListingModel> for Listing {
+    fn from(model: ListingModel) -> Self {
+        Self {
+            id: model.id,
+            seller_id: model.seller_id,
+            item: ItemKind {
+                id: model.item_id,
+                data_id: model.item_data_id,
+                level: model.item_level,
+                // Bound items can't be listed.
+                is_bound: false,
+            },
+            count: model.item_count,
+            category: model.category(),
+            price: model.price,
+            expire_at: model.expire_at,
+        }
+    }
+}
+
+impl From<&Listing> for ListingModel {
+    fn from(listing: &Listing) -> Self {
+        Self {
+            id: listing.id,
+            seller_id: listing.seller_id,
+            item_id: listing.item.id,
+            item_data_id: listing.item.data_id,
+            item_level: listing.item.level,
+            item_count: listing.count,
+            item_category: listing.category.as_str().to_string(),
+            price: listing.price,
+            expire_at: listing.expire_at,
+        }
+    }
+}
+
+impl From<&Mail> for MailModel {
+    fn from(mail: &Mail) -> Self {
+        Self {
+            id: mail.id,
+            recipient_id: mail.recipient_id,
+            sender_id: mail.sender_id,
+            sender_name: mail.sender_name.clone(),
+            subject: mail.subject.clone(),
+            body: mail.body.clone(),
+            gold: mail.gold,
+            is_read: mail.is_read,
+            is_claimed: mail.is_claimed,
+            is_kept: mail.is_kept,
+            expire_at: mail.expire_at,
+        }
+    }
+}
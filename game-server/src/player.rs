@@ -3,6 +3,7 @@ use bevy_ecs::prelude::*;
 use crate::character::*;
 use crate::character::path_tree::PathTree;
 use crate::net::session::{Entry, Session};
+use crate::persistence;
 use crate::world::transform::Transform;
 // use crate::character::movement::MovementController;
 // use crate::character::stat::*;
@@ -21,12 +22,10 @@ pub struct PlayerData {
 }
 
 impl PlayerData {
-    pub async fn load(entry: &Entry) -> Result<Self, db::Error> {
-        let mut conn = db::conn().await?;
-        
+    pub async fn load(entry: &Entry) -> Result<Self, persistence::Error> {
         let character_id = entry.character_id;
-        let character = Character::load(&mut conn, character_id).await?;
-        let path_tree = PathTree::load(&mut conn, character_id).await?;
+        let character = Character::load(character_id).await?;
+        let path_tree = PathTree::load(character_id).await?;
         
         // let character_stat = CharacterStat::load(entry.character_id, client).await?;
 
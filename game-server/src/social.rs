@@ -1,4 +1,6 @@
 pub mod faction;
 pub mod guild;
+pub mod mail;
 pub mod party;
 pub mod quest;
+pub mod trade;
@@ -0,0 +1,138 @@
+mod send_system_mail;
+
+pub use send_system_mail::{Error as SendSystemMailError, SendSystemMail};
+
+use crate::config;
+use crate::net::gateway::send_to_character;
+use crate::persistence::mail::{Attachment, Mail};
+use crate::persistence::{self, repositories};
+use actix::prelude::*;
+use protocol::game::social::{mail_send_result, MailData, MailItem, MailReceived};
+use std::collections::BTreeMap;
+use tracing::{error, info};
+use util::id::Id;
+
+/// Deletes expired mail and sends system mail. Every node runs it, each taking its own batch.
+#[derive(Default)]
+pub struct PostOffice;
+
+impl Actor for PostOffice {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(config!(app).mail.expire_interval, |_, _| {
+            actix::spawn(expire());
+        });
+    }
+}
+
+impl Supervised for PostOffice {}
+
+impl SystemService for PostOffice {}
+
+async fn expire() {
+    match repositories().mail.expire(config!(app).mail.expire_batch as i64).await {
+        Ok(0) => {}
+        Ok(count) => info!("Deleted {} expired mail", count),
+        Err(e) => error!("Failed to expire mail: {}", e),
+    }
+}
+
+/// Send the mail from a character, taking the attachments from the sender.
+pub async fn send(
+    sender_id: Id,
+    sender_name: String,
+    recipient_id: Id,
+    subject: String,
+    body: String,
+    gold: i64,
+    items: Vec<(Id, i32)>,
+) -> Result<Result<Mail, mail_send_result::Error>, persistence::Error> {
+    use mail_send_result::Error;
+
+    if recipient_id == sender_id {
+        return Ok(Err(Error::InvalidRecipient));
+    }
+    match repositories().character.get(recipient_id).await {
+        Ok(_) => {}
+        Err(persistence::Error::CharacterNotFound(_)) => return Ok(Err(Error::InvalidRecipient)),
+        Err(e) => return Err(e),
+    }
+    // Checked ahead of sending, so mail racing it may go past the limit by a few.
+    if repositories().mail.count(recipient_id).await? >= config!(app).mail.max_mails as i64 {
+        return Ok(Err(Error::MailboxFull));
+    }
+
+    // One attachment per item, as an item is escrowed once.
+    let mut counts: BTreeMap<Id, i32> = BTreeMap::new();
+    for (item_id, count) in items {
+        if count <= 0 {
+            return Ok(Err(Error::InvalidItem));
+        }
+
+        let total = counts.entry(item_id).or_default();
+        let Some(sum) = total.checked_add(count) else {
+            return Ok(Err(Error::InvalidItem));
+        };
+        *total = sum;
+    }
+
+    let owned = repositories().item.list(sender_id).await?;
+    let mut attachments = Vec::with_capacity(counts.len());
+    for (item_id, count) in counts {
+        let Some(item) = owned.iter().find(|item| item.id == item_id) else {
+            return Ok(Err(Error::InvalidItem));
+        };
+        if item.is_bound || item.count < count {
+            return Ok(Err(Error::InvalidItem));
+        }
+
+        attachments.push(Attachment::new(item.id, item.data_id, item.level, false, count));
+    }
+
+    let mail = Mail {
+        sender_id: Some(sender_id),
+        sender_name,
+        ..Mail::system(recipient_id, subject, body, gold, attachments)
+    };
+
+    match repositories().mail.send(mail.clone()).await {
+        Ok(()) => {}
+        // Taken since it was checked.
+        Err(persistence::Error::InsufficientItem { .. }) => return Ok(Err(Error::InvalidItem)),
+        Err(persistence::Error::InsufficientGold(_)) => return Ok(Err(Error::InsufficientGold)),
+        Err(e) => return Err(e),
+    }
+    notify(&mail);
+
+    Ok(Ok(mail))
+}
+
+/// Tell the recipient about the new mail, if online anywhere in the cluster.
+pub fn notify(mail: &Mail) {
+    send_to_character(mail.recipient_id, &MailReceived { mail: Some(mail.into()) });
+}
+
+impl From<&Mail> for MailData {
+    fn from(mail: &Mail) -> Self {
+        Self {
+            id: mail.id,
+            sender_id: mail.sender_id,
+            sender_name: mail.sender_name.clone(),
+            subject: mail.subject.clone(),
+            body: mail.body.clone(),
+            gold: mail.gold,
+            items: mail.items
+                .iter()
+                .map(|attachment| MailItem {
+                    data_id: attachment.item.data_id,
+                    level: attachment.item.level as u32,
+                    count: attachment.count,
+                })
+                .collect(),
+            is_read: mail.is_read,
+            is_claimed: mail.is_claimed,
+            expire_at: mail.expire_at,
+        }
+    }
+}
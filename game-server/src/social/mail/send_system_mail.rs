@@ -0,0 +1,85 @@
+use super::PostOffice;
+use crate::config;
+use crate::persistence::mail::{Attachment, Mail};
+use crate::persistence::{self, repositories};
+use actix::prelude::*;
+use data::item::ItemTable;
+use data::prelude::*;
+use util::id::Id;
+
+/// Send mail from the server, e.g. rewards, with new items created on claim. The entry point of
+/// system mail for other actors, and of the control listener once it serves mail requests.
+#[derive(Message)]
+#[rtype(result = "Result<Id, Error>")]
+pub struct SendSystemMail {
+    pub recipient_id: Id,
+    pub subject: String,
+    pub body: String,
+    pub gold: i64,
+    /// Item data IDs with counts.
+    pub items: Vec<(i32, i32)>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Persistence(#[from] persistence::Error),
+
+    #[error("Item data {0} not found")]
+    ItemNotFound(i32),
+
+    #[error("Invalid count {1} of item data {0}")]
+    InvalidCount(i32, i32),
+
+    #[error("Invalid gold {0}")]
+    InvalidGold(i64),
+
+    #[error("Subject or body too long")]
+    InvalidText,
+}
+
+impl Handler<SendSystemMail> for PostOffice {
+    type Result = ResponseFuture<Result<Id, Error>>;
+
+    fn handle(&mut self, msg: SendSystemMail, _: &mut Self::Context) -> Self::Result {
+        let items = match attachments(&msg) {
+            Ok(items) => items,
+            Err(e) => return Box::pin(std::future::ready(Err(e))),
+        };
+        let mail = Mail::system(msg.recipient_id, msg.subject, msg.body, msg.gold, items);
+
+        Box::pin(async move {
+            repositories().mail.send(mail.clone()).await?;
+            super::notify(&mail);
+
+            Ok(mail.id)
+        })
+    }
+}
+
+/// New items of the mail, checked against the item data, once the text and gold are checked.
+fn attachments(msg: &SendSystemMail) -> Result<Vec<Attachment>, Error> {
+    let config = &config!(app).mail;
+    if msg.subject.chars().count() > config.max_subject_length as usize
+        || msg.body.chars().count() > config.max_body_length as usize
+    {
+        return Err(Error::InvalidText);
+    }
+    if msg.gold < 0 {
+        return Err(Error::InvalidGold(msg.gold));
+    }
+
+    msg.items
+        .iter()
+        .map(|&(data_id, count)| {
+            if ItemTable::get(&DataId::from(data_id)).is_none() {
+                return Err(Error::ItemNotFound(data_id));
+            }
+            if count <= 0 {
+                return Err(Error::InvalidCount(data_id, count));
+            }
+
+            Ok(Attachment::new(util::id::universal(), data_id, 0, false, count))
+        })
+        .collect()
+}
@@ -0,0 +1,458 @@
+use crate::character::Character;
+use crate::character::resource::health::{self, Health};
+use crate::character::status::combat::Combat;
+use crate::config;
+use crate::net::session::Session;
+use crate::persistence::{self, repositories, ItemRecord};
+use crate::persistence::ledger::{Change, ItemKind, Operation, Reason};
+use crate::task::Task;
+use crate::world::time::Time;
+use crate::world::transform::Transform;
+use bevy_ecs::prelude::*;
+use protocol::game::social::{trade_result, TradeItem, TradeOfferData, TradeResult, TradeUpdate};
+use std::collections::{BTreeMap, HashMap};
+use tracing::{info, warn};
+use util::id::Id;
+
+pub use trade_result::Error;
+
+/// Trades between the players of the zone, by id.
+#[derive(Resource, Default)]
+pub struct Trades {
+    pub map: HashMap<Id, Trade>,
+}
+
+/// A player in a trade, requested or open. A player is in at most one trade.
+#[derive(Component)]
+pub struct Trading {
+    pub trade_id: Id,
+}
+
+#[derive(Clone)]
+pub struct Trade {
+    pub id: Id,
+    pub state: State,
+    /// The requester first.
+    pub traders: [Trader; 2],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    /// Waiting for the other player to accept until the tick.
+    Requested { expire_tick: u64 },
+    /// Offers are placed, locked and confirmed.
+    Open,
+    /// Both confirmed. The items and gold are being moved.
+    Committing,
+}
+
+#[derive(Clone)]
+pub struct Trader {
+    pub entity: Entity,
+    pub character_id: Id,
+    pub offer: Offer,
+    /// The offer can no longer change.
+    pub locked: bool,
+    pub confirmed: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Offer {
+    /// Counts by item id.
+    pub items: BTreeMap<Id, i32>,
+    pub gold: i64,
+}
+
+impl Trade {
+    pub fn new(id: Id, requester: (Entity, Id), target: (Entity, Id), expire_tick: u64) -> Self {
+        Self {
+            id,
+            state: State::Requested { expire_tick },
+            traders: [Trader::new(requester.0, requester.1), Trader::new(target.0, target.1)],
+        }
+    }
+
+    pub fn index_of(&self, entity: Entity) -> Option<usize> {
+        self.traders.iter().position(|trader| trader.entity == entity)
+    }
+
+    /// Replace the offer of the trader. Both locks are lifted, so nobody confirms an offer they
+    /// have not seen.
+    pub fn set_offer(&mut self, index: usize, offer: Offer) -> Result<(), Error> {
+        if self.state != State::Open || self.traders[index].locked {
+            return Err(Error::InvalidState);
+        }
+        if offer.gold < 0 || offer.items.values().any(|&count| count <= 0) {
+            return Err(Error::InvalidOffer);
+        }
+
+        self.traders[index].offer = offer;
+        for trader in &mut self.traders {
+            trader.locked = false;
+            trader.confirmed = false;
+        }
+
+        Ok(())
+    }
+
+    pub fn lock(&mut self, index: usize) -> Result<(), Error> {
+        if self.state != State::Open {
+            return Err(Error::InvalidState);
+        }
+
+        self.traders[index].locked = true;
+
+        Ok(())
+    }
+
+    /// Confirm the offers once both are locked. Returns whether both confirmed.
+    pub fn confirm(&mut self, index: usize) -> Result<bool, Error> {
+        if self.state != State::Open || self.traders.iter().any(|trader| !trader.locked) {
+            return Err(Error::InvalidState);
+        }
+
+        self.traders[index].confirmed = true;
+
+        Ok(self.traders.iter().all(|trader| trader.confirmed))
+    }
+
+    /// Ledger operation moving the offers, given the items of each trader.
+    pub fn operation(&self, items: [Vec<ItemRecord>; 2]) -> Result<Operation, Error> {
+        let mut changes = Vec::new();
+
+        for (index, trader) in self.traders.iter().enumerate() {
+            let other = &self.traders[1 - index];
+
+            for (&item_id, &count) in &trader.offer.items {
+                let Some(record) = items[index].iter().find(|record| record.id == item_id) else {
+                    return Err(Error::InvalidOffer);
+                };
+                if record.is_bound || record.count < count {
+                    return Err(Error::InvalidOffer);
+                }
+
+                let item = ItemKind {
+                    id: record.id,
+                    data_id: record.data_id,
+                    level: record.level,
+                    is_bound: record.is_bound,
+                };
+                changes.push(Change::Item { character_id: trader.character_id, item, delta: -count });
+                changes.push(Change::Item { character_id: other.character_id, item, delta: count });
+            }
+
+            if trader.offer.gold > 0 {
+                changes.push(Change::Gold { character_id: trader.character_id, delta: -trader.offer.gold });
+                changes.push(Change::Gold { character_id: other.character_id, delta: trader.offer.gold });
+            }
+        }
+
+        Ok(Operation {
+            id: self.id,
+            reason: Reason::Trade,
+            source: None,
+            changes,
+        })
+    }
+
+    pub fn update(&self) -> TradeUpdate {
+        TradeUpdate {
+            trade_id: self.id,
+            offers: self.traders.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl Trader {
+    fn new(entity: Entity, character_id: Id) -> Self {
+        Self {
+            entity,
+            character_id,
+            offer: Offer::default(),
+            locked: false,
+            confirmed: false,
+        }
+    }
+}
+
+impl From<&Trader> for TradeOfferData {
+    fn from(trader: &Trader) -> Self {
+        Self {
+            character_id: trader.character_id,
+            items: trader.offer.items
+                .iter()
+                .map(|(&item_id, &count)| TradeItem { item_id, count })
+                .collect(),
+            gold: trader.offer.gold,
+            locked: trader.locked,
+            confirmed: trader.confirmed,
+        }
+    }
+}
+
+/// Counts of the same item are summed. Each must be positive, and so must their sum fit.
+impl TryFrom<&[TradeItem]> for Offer {
+    type Error = Error;
+
+    fn try_from(items: &[TradeItem]) -> Result<Self, Error> {
+        let mut offer = Offer::default();
+        for item in items {
+            if item.count <= 0 {
+                return Err(Error::InvalidOffer);
+            }
+
+            let count = offer.items.entry(item.item_id).or_default();
+            *count = count.checked_add(item.count).ok_or(Error::InvalidOffer)?;
+        }
+
+        Ok(offer)
+    }
+}
+
+/// Whether the player can trade at all.
+pub fn check_state(world: &World, entity: Entity) -> Result<(), Error> {
+    let Ok(entity) = world.get_entity(entity) else {
+        return Err(Error::NotFound);
+    };
+
+    if !entity.contains::<Session>() {
+        return Err(Error::Disconnected);
+    }
+    if entity.get::<Health>().is_some_and(|health| health.state != health::State::Alive) {
+        return Err(Error::Dead);
+    }
+    if entity.contains::<Combat>() {
+        return Err(Error::InCombat);
+    }
+
+    Ok(())
+}
+
+/// Whether the players are close enough to trade.
+pub fn check_range(world: &World, a: Entity, b: Entity) -> Result<(), Error> {
+    let (Some(a), Some(b)) = (world.get::<Transform>(a), world.get::<Transform>(b)) else {
+        return Err(Error::NotFound);
+    };
+
+    if nalgebra::distance(&a.position, &b.position) > config!(app).trade.range {
+        return Err(Error::OutOfRange);
+    }
+
+    Ok(())
+}
+
+/// Whether both players are still in the zone and can go on trading.
+pub fn check(world: &World, trade: &Trade) -> Result<(), Error> {
+    for trader in &trade.traders {
+        // The entity of a player gone may be reused by another.
+        if world.get::<Character>(trader.entity).is_none_or(|character| character.id != trader.character_id) {
+            return Err(Error::Disconnected);
+        }
+        check_state(world, trader.entity)?;
+    }
+
+    let [a, b] = &trade.traders;
+    check_range(world, a.entity, b.entity)
+}
+
+/// Send the offers to both players.
+pub fn broadcast(world: &World, trade: &Trade) {
+    let update = trade.update();
+    for trader in &trade.traders {
+        if let Some(session) = world.get::<Session>(trader.entity) {
+            session.send(&update);
+        }
+    }
+}
+
+/// Run `f` on the trade of the player.
+pub fn with_trade<T>(
+    world: &mut World,
+    entity: Entity,
+    trade_id: Id,
+    f: impl FnOnce(&World, &mut Trade, usize) -> Result<T, Error>,
+) -> Result<T, Error> {
+    world.resource_scope(|world, mut trades: Mut<Trades>| {
+        let Some(trade) = trades.map.get_mut(&trade_id) else {
+            return Err(Error::NotFound);
+        };
+        let Some(index) = trade.index_of(entity) else {
+            return Err(Error::NotFound);
+        };
+
+        f(world, trade, index)
+    })
+}
+
+pub fn refuse(session: &Session, trade_id: Id, error: Error) {
+    session.send(&TradeResult {
+        trade_id,
+        error: Some(error.into()),
+    });
+}
+
+/// Move both offers in one ledger operation, then end the trade.
+pub fn commit(world: &mut World, trade: Trade) {
+    let trade_id = trade.id;
+
+    let future = async move {
+        let item = &repositories().item;
+        let items = [
+            item.list(trade.traders[0].character_id).await?,
+            item.list(trade.traders[1].character_id).await?,
+        ];
+        let operation = match trade.operation(items) {
+            Ok(operation) => operation,
+            Err(error) => return Ok(Err(error)),
+        };
+
+        // The items or gold were spent since they were offered.
+        let outcome = match repositories().ledger.apply(operation).await {
+            Ok(outcome) => outcome,
+            Err(persistence::Error::InsufficientItem { .. } | persistence::Error::InsufficientGold(_)) => {
+                return Ok(Err(Error::InvalidOffer));
+            }
+            Err(e) => return Err(e),
+        };
+
+        Ok::<_, persistence::Error>(Ok(outcome))
+    };
+    let task = Task::serial_with_return(future, move |result, world, entity| {
+        world.despawn(entity);
+
+        let error = match result {
+            Ok(Ok(_)) => None,
+            Ok(Err(error)) => Some(error),
+            Err(e) => {
+                warn!("Failed to commit trade {}: {}", trade_id, e);
+                Some(Error::Failed)
+            }
+        };
+        finish(world, trade_id, error);
+    });
+
+    // The task outlives either player leaving.
+    let entity = world.spawn_empty().id();
+    task.dispatch(world, entity);
+}
+
+/// End the trade, telling both players. No error when it went through.
+pub fn finish(world: &mut World, trade_id: Id, error: Option<Error>) {
+    let Some(trade) = world.resource_mut::<Trades>().map.remove(&trade_id) else {
+        return;
+    };
+
+    match error {
+        Some(error) => info!("Trade {} cancelled: {:?}", trade_id, error),
+        None => info!("Trade {} completed", trade_id),
+    }
+
+    let result = TradeResult {
+        trade_id,
+        error: error.map(Into::into),
+    };
+    for trader in &trade.traders {
+        let Ok(mut entity) = world.get_entity_mut(trader.entity) else {
+            continue;
+        };
+        if entity.get::<Character>().is_none_or(|character| character.id != trader.character_id) {
+            continue;
+        }
+
+        if entity.get::<Trading>().is_some_and(|trading| trading.trade_id == trade_id) {
+            entity.remove::<Trading>();
+        }
+        if let Some(session) = entity.get::<Session>() {
+            session.send(&result);
+        }
+    }
+}
+
+pub fn register(schedule: &mut Schedule) {
+    schedule.add_systems(watch);
+}
+
+/// Cancel the trades a player left, by disconnecting or changing zones, or can no longer make.
+fn watch(world: &mut World) {
+    let ticks = world.resource::<Time>().ticks;
+
+    let cancelled: Vec<_> = world.resource::<Trades>().map
+        .values()
+        .filter_map(|trade| {
+            let error = match trade.state {
+                // The operation is in flight. Its result stands either way.
+                State::Committing => return None,
+                State::Requested { expire_tick } if ticks >= expire_tick => Error::Expired,
+                _ => check(world, trade).err()?,
+            };
+
+            Some((trade.id, error))
+        })
+        .collect();
+
+    for (trade_id, error) in cancelled {
+        finish(world, trade_id, Some(error));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: Id, character_id: Id, count: i32, is_bound: bool) -> ItemRecord {
+        ItemRecord { id, character_id, data_id: 1, count, level: 0, is_bound }
+    }
+
+    fn open_trade() -> Trade {
+        let mut trade = Trade::new(1, (Entity::from_raw_u32(0).unwrap(), 10), (Entity::from_raw_u32(1).unwrap(), 20), 0);
+        trade.state = State::Open;
+        trade
+    }
+
+    #[test]
+    fn offer_change_lifts_locks() {
+        let mut trade = open_trade();
+        trade.lock(0).unwrap();
+        trade.lock(1).unwrap();
+        assert!(!trade.confirm(0).unwrap());
+
+        trade.traders[1].locked = false;
+        trade.set_offer(1, Offer { gold: 5, ..Default::default() }).unwrap();
+
+        assert!(trade.traders.iter().all(|trader| !trader.locked && !trader.confirmed));
+        assert_eq!(trade.confirm(1), Err(Error::InvalidState));
+    }
+
+    #[test]
+    fn operation_moves_offers() {
+        let mut trade = open_trade();
+        trade.traders[0].offer = Offer { items: BTreeMap::from([(100, 2)]), gold: 0 };
+        trade.traders[1].offer = Offer { items: BTreeMap::new(), gold: 50 };
+
+        let operation = trade.operation([vec![item(100, 10, 3, false)], vec![]]).unwrap();
+        assert_eq!(operation.id, 1);
+        assert_eq!(operation.changes.len(), 4);
+
+        let bound = trade.operation([vec![item(100, 10, 3, true)], vec![]]);
+        assert_eq!(bound.err(), Some(Error::InvalidOffer));
+
+        let short = trade.operation([vec![item(100, 10, 1, false)], vec![]]);
+        assert_eq!(short.err(), Some(Error::InvalidOffer));
+    }
+
+    #[test]
+    fn offer_sums_positive_counts() {
+        let trade_item = |item_id, count| TradeItem { item_id, count };
+
+        let offer = Offer::try_from([trade_item(100, 2), trade_item(101, 1), trade_item(100, 3)].as_slice());
+        assert_eq!(offer.unwrap().items, BTreeMap::from([(100, 5), (101, 1)]));
+
+        for items in [
+            vec![trade_item(100, 0)],
+            vec![trade_item(100, 3), trade_item(100, -1)],
+            vec![trade_item(100, i32::MAX), trade_item(100, 1)],
+        ] {
+            assert_eq!(Offer::try_from(items.as_slice()), Err(Error::InvalidOffer));
+        }
+    }
+}
@@ -40,6 +40,9 @@ pub enum Error {
     #[error(transparent)]
     DB(#[from] db::Error),
 
+    #[error(transparent)]
+    Persistence(#[from] crate::persistence::Error),
+
     #[error("Task execution panicked or failed")]
     Join,
 
@@ -1,6 +1,8 @@
 pub mod biome;
 pub mod cell;
 pub mod item;
+pub mod random;
+pub mod rewind;
 pub mod time;
 pub mod transform;
 pub mod weather;
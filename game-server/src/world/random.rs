@@ -0,0 +1,21 @@
+use bevy_ecs::prelude::*;
+use rand::prelude::*;
+
+/// Seeded random source of a zone.
+///
+/// Every random decision of the simulation must be drawn from here, so that a
+/// zone can be replayed with the same seed.
+#[derive(Resource)]
+pub struct Random {
+    pub seed: u64,
+    pub rng: StdRng,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
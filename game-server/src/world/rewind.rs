@@ -0,0 +1,163 @@
+use crate::config;
+use crate::net::session::Session;
+use crate::world::time::Time;
+use crate::world::transform::Transform;
+use bevy_ecs::prelude::*;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Recent transforms of an entity, one per tick, to judge hits as the clients saw them.
+#[derive(Component, Default)]
+pub struct TransformHistory {
+    entries: VecDeque<(u64, Transform)>,
+}
+
+/// How far the world may be rewound, and how far behind clients render others.
+#[derive(Resource)]
+pub struct RewindWindow {
+    /// Ticks of history kept. Clients claiming to see older states are clamped to it.
+    pub max_ticks: u64,
+    pub interpolation_ticks: u64,
+}
+
+/// Offset from the clock of a client to the simulated time of the zone, estimated from the
+/// timestamps of its commands.
+#[derive(Component, Default)]
+pub struct ClientClock {
+    offset_millis: Option<f64>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HitError {
+    #[error("No transform of {0} at tick {1}")]
+    Transform(Entity, u64),
+
+    #[error("Out of range: {distance} > {range}")]
+    Range { distance: f32, range: f32 },
+}
+
+impl TransformHistory {
+    fn push(&mut self, tick: u64, transform: Transform, capacity: usize) {
+        while self.entries.len() >= capacity.max(1) {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back((tick, transform));
+    }
+
+    /// Transform at the tick, or the closest one before it. The oldest one if the tick is older
+    /// than the history.
+    pub fn at(&self, tick: u64) -> Option<&Transform> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|(entry_tick, _)| *entry_tick <= tick)
+            .or(self.entries.front())
+            .map(|(_, transform)| transform)
+    }
+}
+
+impl RewindWindow {
+    pub fn new(tick_interval: Duration) -> Self {
+        let zone_config = &config!(app).zone;
+        let tick_nanos = tick_interval.as_nanos().max(1);
+
+        Self {
+            max_ticks: (zone_config.max_rewind.as_nanos() / tick_nanos) as u64,
+            interpolation_ticks: (zone_config.interpolation_delay.as_nanos() / tick_nanos) as u64,
+        }
+    }
+}
+
+impl ClientClock {
+    /// Update with a command sent at `client_timestamp`, received at `server_millis`.
+    pub fn update(&mut self, client_timestamp: i64, server_millis: i64, one_way: Duration) {
+        let sample = (server_millis - client_timestamp) as f64 - one_way.as_secs_f64() * 1000.0;
+
+        self.offset_millis = Some(match self.offset_millis {
+            Some(offset) => offset + (sample - offset) / 8.0,
+            None => sample,
+        });
+    }
+
+    /// The simulated time of the zone when the client was at `client_timestamp`.
+    pub fn to_server_millis(&self, client_timestamp: i64) -> Option<i64> {
+        self.offset_millis.map(|offset| client_timestamp + offset.round() as i64)
+    }
+}
+
+pub fn register(schedule: &mut Schedule) {
+    schedule.add_systems(record_history);
+}
+
+fn record_history(
+    mut commands: Commands,
+    time: Res<Time>,
+    window: Res<RewindWindow>,
+    mut query: Query<(Entity, &Transform, Option<&mut TransformHistory>)>,
+) {
+    let capacity = window.max_ticks as usize + 1;
+
+    for (entity, transform, history) in query.iter_mut() {
+        match history {
+            Some(mut history) => history.push(time.ticks, *transform, capacity),
+            None => {
+                let mut history = TransformHistory::default();
+                history.push(time.ticks, *transform, capacity);
+                commands.entity(entity).insert(history);
+            }
+        }
+    }
+}
+
+/// The tick the client of the entity was seeing when acting at `client_timestamp`.
+///
+/// Without the timestamp or a clock estimate, half the RTT is assumed. The result is never
+/// older than the rewind window allows.
+pub fn view_tick(
+    world: &World,
+    entity: Entity,
+    session: &Session,
+    client_timestamp: Option<i64>,
+) -> u64 {
+    let time = world.resource::<Time>();
+    let window = world.resource::<RewindWindow>();
+    let tick_millis = (time.delta().as_millis() as i64).max(1);
+
+    let acted_at = client_timestamp
+        .and_then(|timestamp| world.get::<ClientClock>(entity)?.to_server_millis(timestamp));
+    let behind_millis = match acted_at {
+        Some(acted_at) => (time.elapsed().as_millis() as i64 - acted_at).max(0),
+        None => session.rtt().estimate().as_millis() as i64 / 2,
+    };
+
+    let behind_ticks = (behind_millis / tick_millis) as u64 + window.interpolation_ticks;
+    time.ticks.saturating_sub(behind_ticks.min(window.max_ticks))
+}
+
+/// Check that the target was in range of the attacker, as seen at the view tick.
+/// The attacker is judged at its current transform, which its own client predicts.
+pub fn check_range(
+    world: &World,
+    attacker: Entity,
+    target: Entity,
+    view_tick: u64,
+    range: f32,
+) -> Result<f32, HitError> {
+    let ticks = world.resource::<Time>().ticks;
+    let attacker_transform = world
+        .get::<Transform>(attacker)
+        .ok_or(HitError::Transform(attacker, ticks))?;
+    let target_transform = world
+        .get::<TransformHistory>(target)
+        .and_then(|history| history.at(view_tick))
+        .or_else(|| world.get::<Transform>(target))
+        .ok_or(HitError::Transform(target, view_tick))?;
+
+    let distance = nalgebra::distance(&attacker_transform.position, &target_transform.position);
+    if distance > range {
+        return Err(HitError::Range { distance, range });
+    }
+
+    Ok(distance)
+}
@@ -1,22 +1,40 @@
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 use bevy_ecs::prelude::*;
 
+/// Simulation time of a zone, advanced by a fixed step on every tick.
+///
+/// Systems must read time from here rather than the wall clock, so that a zone
+/// produces the same result given the same inputs.
 #[derive(Resource)]
 pub struct Time {
-    pub last_tick: Instant,
     pub ticks: u64,
+    delta: Duration,
 }
 
 impl Time {
-    pub fn new() -> Self {
+    pub fn new(delta: Duration) -> Self {
         Self {
-            last_tick: Instant::now(),
             ticks: 0,
+            delta,
         }
     }
 
+    /// The fixed duration of a tick.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
     pub fn delta_secs(&self) -> f32 {
-        self.last_tick.elapsed().as_secs_f32()
+        self.delta.as_secs_f32()
+    }
+
+    /// Simulated duration since the zone has started.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos((self.delta.as_nanos() as u64).saturating_mul(self.ticks))
+    }
+
+    pub(crate) fn advance(&mut self) {
+        self.ticks += 1;
     }
 }
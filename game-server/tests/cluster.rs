@@ -0,0 +1,101 @@
+mod common;
+
+use common::{expect, TestNode};
+use game_client::IngressClientProtocol;
+use game_server::net::cluster::registry;
+use protocol::game::auth::{login, Login};
+use protocol::game::net::ZoneTransferReady;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+/// Nodes load their data and register within this.
+const REGISTER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Wait until the registry lists the node, and owning `zone_id` if given.
+async fn registered(node: &TestNode, zone_id: Option<i64>) {
+    let node_id = node.node_id as i16;
+
+    timeout(REGISTER_TIMEOUT, async {
+        loop {
+            let mut conn = db::conn().await.expect("Failed to connect to the database");
+            let (nodes, zones) = registry::load(&mut conn).await.expect("Failed to load the registry");
+
+            let has_node = nodes.iter().any(|node| node.id == node_id);
+            let has_zone = zone_id.is_none_or(|zone_id| zones.contains(&(zone_id, node_id)));
+            if has_node && has_zone {
+                return;
+            }
+
+            sleep(Duration::from_millis(200)).await;
+        }
+    })
+        .await
+        .expect("Timed out waiting for the node to register");
+}
+
+#[tokio::test]
+async fn transfer_to_the_node_owning_the_zone() {
+    let server = common::server();
+    if !server.has_database {
+        eprintln!("Skipped: clusters need SPIRE_TEST_DB_HOST");
+        return;
+    }
+
+    // Started first, the owner claims zone 0, which the other node then finds taken.
+    let owner = server.spawn_node(2, "0");
+    registered(&owner, Some(0)).await;
+    let gateway = server.spawn_node(3, "0");
+    registered(&gateway, None).await;
+    // Let the gateway load the zones of the registry after registering.
+    sleep(Duration::from_secs(1)).await;
+
+    let player = server.create_player("transferred").await;
+    let mut client = server.connect_to(gateway.address).await;
+    let result = client.login(Login {
+        token: player.token.clone(),
+        character_id: player.character_id,
+        kind: login::Kind::Enter.into(),
+        ..Default::default()
+    }).await.expect("Failed to log in");
+    assert_eq!(result.error, None);
+
+    let transfer = expect(&mut client, |protocol| match protocol {
+        IngressClientProtocol::ServerTransfer(transfer) => Some(transfer),
+        _ => None,
+    }).await;
+    assert_eq!(transfer.zone_id, 0);
+    assert_eq!(transfer.address.parse::<SocketAddr>().ok(), Some(owner.address));
+
+    let mut client = server.connect_to(owner.address).await;
+    let result = client.login(Login {
+        token: player.token.clone(),
+        character_id: player.character_id,
+        kind: login::Kind::Transfer.into(),
+        ..Default::default()
+    }).await.expect("Failed to log in");
+    assert_eq!(result.error, None);
+
+    expect(&mut client, |protocol| match protocol {
+        IngressClientProtocol::ZoneTransfer(transfer) => Some(transfer),
+        _ => None,
+    }).await;
+    client.send(&ZoneTransferReady::default()).await.expect("Failed to send");
+
+    // Located on the owner, for the other nodes to relay to it.
+    timeout(REGISTER_TIMEOUT, async {
+        loop {
+            let mut conn = db::conn().await.expect("Failed to connect to the database");
+            let node_id = registry::find_character(&mut conn, player.character_id)
+                .await
+                .expect("Failed to find the character");
+            if node_id == Some(owner.node_id as i16) {
+                return;
+            }
+
+            sleep(Duration::from_millis(200)).await;
+        }
+    })
+        .await
+        .expect("Timed out waiting for the character to be located");
+}
@@ -0,0 +1,356 @@
+//! In-process game server for integration tests.
+//!
+//! The config, data and database pool of the server are global, so the server of a test binary
+//! is started once on its own thread and shared by its tests. It runs against an ephemeral
+//! database created on the PostgreSQL server given by `SPIRE_TEST_DB_HOST`, `SPIRE_TEST_DB_PORT`,
+//! `SPIRE_TEST_DB_USER` and `SPIRE_TEST_DB_PASSWORD`, or in memory without it.
+//!
+//! Cluster nodes run in processes of their own, spawned with the environment of the server.
+
+#![allow(dead_code)]
+
+use actix::prelude::*;
+use bevy_ecs::world::World;
+use data::character::Race;
+use diesel_async::{AsyncConnection, AsyncPgConnection, SimpleAsyncConnection};
+use game_client::{Client, IngressClientProtocol};
+use game_server::config;
+use game_server::net::zone::{Inspect, Zone};
+use game_server::net::zone_pool::{SpawnZone, ZonePool};
+use game_server::persistence::{self, repositories, CharacterRecord};
+use game_server::social::mail::PostOffice;
+use game_server::world::time::Time;
+use jsonwebtoken::EncodingKey;
+use protocol::game::auth::{login, Login};
+use protocol::game::net::ZoneTransferReady;
+use rustls::pki_types::CertificateDer;
+use std::env;
+use std::fs;
+use std::net::{SocketAddr, UdpSocket};
+use std::process::{Child, Command, Stdio};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+use tokio::time::timeout;
+
+const SERVER_NAME: &str = "localhost";
+const APPLICATION_PROTOCOL: &str = "spire-test";
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+static SERVER: OnceLock<TestServer> = OnceLock::new();
+
+pub struct TestServer {
+    pub address: SocketAddr,
+    pub zone: Addr<Zone>,
+    pub post_office: Addr<PostOffice>,
+    /// Whether it runs against a database rather than in memory.
+    pub has_database: bool,
+    cert: CertificateDer<'static>,
+    token_key: EncodingKey,
+}
+
+/// A node of a cluster, running the server binary. Killed when dropped.
+pub struct TestNode {
+    pub node_id: u16,
+    pub address: SocketAddr,
+    process: Child,
+}
+
+/// An account with a character, in the database of the server.
+#[derive(Debug, Clone)]
+pub struct TestPlayer {
+    pub account_id: i64,
+    pub character_id: i64,
+    pub token: String,
+}
+
+struct TestDatabase {
+    host: String,
+    port: u16,
+    user: String,
+    password: String,
+    /// Dropped and created again on each run of the test binary.
+    name: String,
+}
+
+/// The server shared by the tests of the binary.
+pub fn server() -> &'static TestServer {
+    SERVER.get_or_init(|| {
+        TestServer::start(TestDatabase::from_env()).expect("Failed to start test server")
+    })
+}
+
+/// Wait for the first protocol picked by `f`, skipping the others.
+pub async fn expect<T>(
+    client: &mut Client,
+    mut f: impl FnMut(IngressClientProtocol) -> Option<T>,
+) -> T {
+    timeout(TIMEOUT, async {
+        loop {
+            let protocol = client.recv().await.expect("Failed to receive");
+            if let Some(value) = f(protocol) {
+                return value;
+            }
+        }
+    })
+        .await
+        .expect("Timed out waiting for protocol")
+}
+
+impl TestServer {
+    fn start(database: Option<TestDatabase>) -> Result<Self, String> {
+        let dir = env::temp_dir().join(format!("spire-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+        let certified = rcgen::generate_simple_self_signed(vec![SERVER_NAME.to_string()])
+            .map_err(|e| e.to_string())?;
+        let token_key = format!("{:032x}", rand::random::<u128>());
+        let port = free_port().map_err(|e| e.to_string())?;
+
+        for (file, content) in [
+            ("cert.pem", certified.cert.pem()),
+            ("key.pem", certified.key_pair.serialize_pem()),
+            ("token.key", token_key.clone()),
+        ] {
+            fs::write(dir.join(file), content).map_err(|e| e.to_string())?;
+        }
+
+        let mut vars = vec![
+            ("SPIRE_GAME_SERVER_NODE_ID", "1".to_string()),
+            ("SPIRE_GAME_SERVER_PORT", port.to_string()),
+            ("SPIRE_GAME_SERVER_CONTROL_PORT", "0".to_string()),
+            ("SPIRE_APPLICATION_PROTOCOL", APPLICATION_PROTOCOL.to_string()),
+            ("SPIRE_GAME_SERVER_TLS_CERT_FILE", dir.join("cert.pem").display().to_string()),
+            ("SPIRE_GAME_SERVER_TLS_KEY_FILE", dir.join("key.pem").display().to_string()),
+            ("SPIRE_TOKEN_KEY_FILE", dir.join("token.key").display().to_string()),
+        ];
+        match &database {
+            Some(database) => {
+                fs::write(dir.join("db-password.key"), &database.password).map_err(|e| e.to_string())?;
+
+                vars.extend([
+                    ("SPIRE_DB_BACKEND", "postgres".to_string()),
+                    ("SPIRE_DB_HOST", database.host.clone()),
+                    ("SPIRE_DB_PORT", database.port.to_string()),
+                    ("SPIRE_DB_USER", database.user.clone()),
+                    ("SPIRE_DB_NAME", database.name.clone()),
+                    ("SPIRE_DB_PASSWORD_FILE", dir.join("db-password.key").display().to_string()),
+                ]);
+            }
+            None => vars.push(("SPIRE_DB_BACKEND", "memory".to_string())),
+        }
+        for (key, value) in vars {
+            // SAFETY: Set once, before the server thread starts reading the environment.
+            unsafe { env::set_var(key, value) };
+        }
+
+        let has_database = database.is_some();
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        thread::spawn(move || {
+            let system = System::new();
+            let result = system.block_on(boot(database));
+            let failed = result.is_err();
+            _ = sender.send(result);
+
+            if !failed {
+                _ = system.run();
+            }
+        });
+
+        let (zone, post_office) = receiver.recv().map_err(|e| e.to_string())??;
+
+        Ok(Self {
+            address: SocketAddr::from(([127, 0, 0, 1], port)),
+            zone,
+            post_office,
+            has_database,
+            cert: certified.cert.der().clone(),
+            token_key: EncodingKey::from_secret(token_key.as_bytes()),
+        })
+    }
+
+    /// A new account with a character.
+    pub async fn create_player(&self, name: &str) -> TestPlayer {
+        let account_id = util::id::universal();
+        let character_id = util::id::universal();
+
+        repositories().account.create(account_id).await.expect("Failed to create account");
+        repositories().character.create(CharacterRecord {
+            id: character_id,
+            account_id,
+            name: name.to_string(),
+            race: Race::Human,
+        }).await.expect("Failed to create character");
+
+        let token = util::token::generate(account_id, &self.token_key, Duration::from_secs(60 * 60))
+            .expect("Failed to generate token");
+
+        TestPlayer {
+            account_id,
+            character_id,
+            token,
+        }
+    }
+
+    /// A client connected over loopback, not logged in yet.
+    pub async fn connect(&self) -> Client {
+        self.connect_to(self.address).await
+    }
+
+    /// A client connected to a node sharing the certificate of the server, e.g. a [`TestNode`].
+    pub async fn connect_to(&self, address: SocketAddr) -> Client {
+        let endpoint = game_client::client::endpoint(APPLICATION_PROTOCOL, [self.cert.clone()])
+            .expect("Failed to create endpoint");
+
+        timeout(TIMEOUT, Client::connect(&endpoint, address, SERVER_NAME))
+            .await
+            .expect("Timed out connecting")
+            .expect("Failed to connect")
+    }
+
+    /// A client of a new player, logged in and in the zone.
+    pub async fn enter(&self, name: &str) -> (Client, TestPlayer) {
+        let player = self.create_player(name).await;
+        let mut client = self.connect().await;
+
+        let result = timeout(TIMEOUT, client.login(Login {
+            token: player.token.clone(),
+            character_id: player.character_id,
+            kind: login::Kind::Enter.into(),
+            ..Default::default()
+        }))
+            .await
+            .expect("Timed out logging in")
+            .expect("Failed to log in");
+        assert_eq!(result.error, None, "Login refused");
+
+        expect(&mut client, |protocol| match protocol {
+            IngressClientProtocol::ZoneTransfer(transfer) => Some(transfer),
+            _ => None,
+        }).await;
+        client.send(&ZoneTransferReady::default()).await.expect("Failed to send");
+
+        (client, player)
+    }
+
+    /// Run a function on the world of the zone, between ticks.
+    pub async fn inspect<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut World) -> R + Send + 'static,
+    ) -> R {
+        self.zone.send(Inspect(f)).await.expect("Zone stopped")
+    }
+
+    /// Start a cluster node claiming `zones`, a comma separated list, with the certificate, token
+    /// key and database of the server. Clusters need the database.
+    pub fn spawn_node(&self, node_id: u16, zones: &str) -> TestNode {
+        assert!(self.has_database, "Cluster nodes need SPIRE_TEST_DB_HOST");
+
+        let port = free_port().expect("Failed to find a game port");
+        let cluster_port = free_port().expect("Failed to find a cluster port");
+
+        // The rest of the environment is inherited from this process.
+        let process = Command::new(env!("CARGO_BIN_EXE_game-server"))
+            .envs([
+                ("SPIRE_GAME_SERVER_NODE_ID", node_id.to_string()),
+                ("SPIRE_GAME_SERVER_PORT", port.to_string()),
+                ("SPIRE_CLUSTER__ENABLED", "true".to_string()),
+                ("SPIRE_CLUSTER__PORT", cluster_port.to_string()),
+                ("SPIRE_CLUSTER__ZONES", zones.to_string()),
+                ("SPIRE_CLUSTER__HEARTBEAT_INTERVAL_SECONDS", "1".to_string()),
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("Failed to start node");
+
+        TestNode {
+            node_id,
+            address: SocketAddr::from(([127, 0, 0, 1], port)),
+            process,
+        }
+    }
+
+    /// Wait until the zone has run `ticks` more ticks.
+    pub async fn wait_ticks(&self, ticks: u64) {
+        let until = self.inspect(|world| world.resource::<Time>().ticks).await + ticks;
+
+        timeout(TIMEOUT, async {
+            while self.inspect(|world| world.resource::<Time>().ticks).await < until {
+                tokio::time::sleep(config!(app).zone.tick_interval).await;
+            }
+        })
+            .await
+            .expect("Timed out waiting for ticks");
+    }
+}
+
+impl Drop for TestNode {
+    fn drop(&mut self) {
+        _ = self.process.kill();
+        _ = self.process.wait();
+    }
+}
+
+/// A UDP port free on loopback, for QUIC.
+fn free_port() -> std::io::Result<u16> {
+    Ok(UdpSocket::bind("127.0.0.1:0")?.local_addr()?.port())
+}
+
+async fn boot(database: Option<TestDatabase>) -> Result<(Addr<Zone>, Addr<PostOffice>), String> {
+    // Already installed if another test binary shares the process.
+    _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+
+    if let Some(database) = &database {
+        database.create().await?;
+    }
+
+    config::init(&None).map_err(|e| e.to_string())?;
+    util::id::init(config!(net).node_id);
+    data::init(&config!(app).data.dir).await.map_err(|e| e.to_string())?;
+    persistence::init().await.map_err(|e| e.to_string())?;
+
+    game_server::start();
+
+    let zone = ZonePool::from_registry()
+        .send(SpawnZone { id: 0 })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok((zone, PostOffice::from_registry()))
+}
+
+impl TestDatabase {
+    fn from_env() -> Option<Self> {
+        Some(Self {
+            host: env::var("SPIRE_TEST_DB_HOST").ok()?,
+            port: env::var("SPIRE_TEST_DB_PORT").ok().and_then(|port| port.parse().ok()).unwrap_or(5432),
+            user: env::var("SPIRE_TEST_DB_USER").unwrap_or_else(|_| "spire".to_string()),
+            password: env::var("SPIRE_TEST_DB_PASSWORD").unwrap_or_default(),
+            name: format!("spire_test_{}", env!("CARGO_CRATE_NAME")),
+        })
+    }
+
+    fn url(&self, name: &str) -> String {
+        format!("postgres://{}:{}@{}:{}/{}", self.user, self.password, self.host, self.port, name)
+    }
+
+    /// Create the database from scratch, with the migrations of `db/migrations`.
+    async fn create(&self) -> Result<(), String> {
+        let mut conn = AsyncPgConnection::establish(&self.url("postgres"))
+            .await
+            .map_err(|e| e.to_string())?;
+        conn.batch_execute(&format!(
+            "drop database if exists {0} with (force); create database {0};",
+            self.name,
+        )).await.map_err(|e| e.to_string())?;
+
+        let mut conn = AsyncPgConnection::establish(&self.url(&self.name))
+            .await
+            .map_err(|e| e.to_string())?;
+        db::migration::run(&mut conn).await.map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
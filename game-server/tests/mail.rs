@@ -0,0 +1,71 @@
+mod common;
+
+use data::item::ItemTable;
+use game_server::config;
+use game_server::persistence::repositories;
+use game_server::social::mail::{SendSystemMail, SendSystemMailError};
+use util::id::Id;
+
+fn system_mail(recipient_id: Id) -> SendSystemMail {
+    SendSystemMail {
+        recipient_id,
+        subject: "Reward".to_string(),
+        body: String::new(),
+        gold: 100,
+        items: Vec::new(),
+    }
+}
+
+#[tokio::test]
+async fn send_system_mail() {
+    let server = common::server();
+    let player = server.create_player("system_mail").await;
+
+    // Any item of the data, if there is one.
+    let items: Vec<_> = ItemTable::iter().take(1).map(|(data_id, _)| (**data_id as i32, 2)).collect();
+    let mail_id = server.post_office
+        .send(SendSystemMail { items: items.clone(), ..system_mail(player.character_id) })
+        .await
+        .expect("Post office stopped")
+        .expect("Failed to send system mail");
+
+    let mails = repositories().mail.list(player.character_id, 10).await.unwrap();
+    assert_eq!(mails.len(), 1);
+    assert_eq!((mails[0].id, mails[0].sender_id, mails[0].gold), (mail_id, None, 100));
+    assert_eq!(mails[0].items.len(), items.len());
+}
+
+#[tokio::test]
+async fn refuse_invalid_system_mail() {
+    let server = common::server();
+    let player = server.create_player("invalid_system_mail").await;
+    let mail = || system_mail(player.character_id);
+    let long = "a".repeat(config!(app).mail.max_body_length as usize + 1);
+
+    for (msg, expected) in [
+        (SendSystemMail { gold: -1, ..mail() }, "gold"),
+        (SendSystemMail { items: vec![(-1, 1)], ..mail() }, "item"),
+        (SendSystemMail { subject: long.clone(), ..mail() }, "text"),
+        (SendSystemMail { body: long.clone(), ..mail() }, "text"),
+    ] {
+        let result = server.post_office.send(msg).await.expect("Post office stopped");
+
+        let error = match result {
+            Err(SendSystemMailError::InvalidGold(_)) => "gold",
+            Err(SendSystemMailError::ItemNotFound(_)) => "item",
+            Err(SendSystemMailError::InvalidText) => "text",
+            other => panic!("Unexpected result {other:?}"),
+        };
+        assert_eq!(error, expected);
+    }
+
+    if let Some((data_id, _)) = ItemTable::iter().next() {
+        let result = server.post_office
+            .send(SendSystemMail { items: vec![(**data_id as i32, 0)], ..mail() })
+            .await
+            .expect("Post office stopped");
+        assert!(matches!(result, Err(SendSystemMailError::InvalidCount(_, 0))));
+    }
+
+    assert!(repositories().mail.list(player.character_id, 10).await.unwrap().is_empty());
+}
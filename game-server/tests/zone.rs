@@ -0,0 +1,104 @@
+mod common;
+
+use common::expect;
+use game_client::IngressClientProtocol;
+use game_server::character::Characters;
+use game_server::character::Character;
+use game_server::world::rewind::TransformHistory;
+use game_server::world::transform::Transform;
+use protocol::game::auth::{login, Login};
+use protocol::game::net::Ping;
+use protocol::game::play::{skill_use_result, SkillUse};
+
+#[tokio::test]
+async fn enter_zone() {
+    let server = common::server();
+    let (mut client, player) = server.enter("enter").await;
+
+    // The snapshot of the world it enters.
+    expect(&mut client, |protocol| match protocol {
+        IngressClientProtocol::MovementSync(sync) => Some(sync),
+        _ => None,
+    }).await;
+
+    server.wait_ticks(3).await;
+
+    let character_id = player.character_id;
+    let id = server.inspect(move |world| {
+        let entity = *world.resource::<Characters>().map.get(&character_id)?;
+        world.get::<Character>(entity).map(|character| character.id)
+    }).await;
+    assert_eq!(id, Some(character_id));
+}
+
+#[tokio::test]
+async fn ping() {
+    let server = common::server();
+    let (mut client, _) = server.enter("ping").await;
+
+    client.send(&Ping { timestamp: 42 }).await.unwrap();
+
+    let pong = expect(&mut client, |protocol| match protocol {
+        IngressClientProtocol::Pong(pong) => Some(pong),
+        _ => None,
+    }).await;
+    assert_eq!(pong.timestamp, 42);
+}
+
+#[tokio::test]
+async fn refuse_invalid_token() {
+    let server = common::server();
+    let player = server.create_player("invalid").await;
+    let mut client = server.connect().await;
+
+    let result = client.login(Login {
+        token: "invalid".to_string(),
+        character_id: player.character_id,
+        kind: login::Kind::Enter.into(),
+        ..Default::default()
+    }).await.unwrap();
+    assert!(result.error.is_some());
+}
+
+#[tokio::test]
+async fn skill_use_hits_in_range() {
+    let server = common::server();
+    let (mut attacker, _) = server.enter("attacker").await;
+    let (_target_client, target) = server.enter("target").await;
+    server.wait_ticks(3).await;
+
+    let target_id = target.character_id;
+    let target_entity = server.inspect(move |world| {
+        world.resource::<Characters>().map[&target_id].to_bits()
+    }).await;
+
+    let skill_use = SkillUse {
+        skill_id: 1,
+        target: target_entity,
+        timestamp: chrono::Utc::now().timestamp_millis(),
+    };
+    attacker.send(&skill_use).await.unwrap();
+
+    let result = expect(&mut attacker, |protocol| match protocol {
+        IngressClientProtocol::SkillUseResult(result) => Some(result),
+        _ => None,
+    }).await;
+    assert_eq!(result.error, None);
+
+    // Moved away long enough for the whole rewind window to see it.
+    server.inspect(move |world| {
+        let entity = world.resource::<Characters>().map[&target_id];
+        let mut entity = world.entity_mut(entity);
+        entity.get_mut::<Transform>().unwrap().position.x += 100.0;
+        entity.remove::<TransformHistory>();
+    }).await;
+    server.wait_ticks(3).await;
+
+    attacker.send(&SkillUse { timestamp: chrono::Utc::now().timestamp_millis(), ..skill_use }).await.unwrap();
+
+    let result = expect(&mut attacker, |protocol| match protocol {
+        IngressClientProtocol::SkillUseResult(result) => Some(result),
+        _ => None,
+    }).await;
+    assert_eq!(result.error, Some(skill_use_result::Error::OutOfRange.into()));
+}
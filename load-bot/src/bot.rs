@@ -0,0 +1,144 @@
+use crate::error::Error;
+use crate::lobby::Lobby;
+use crate::report::Metrics;
+use crate::Args;
+use game_client::{Client, IngressClientProtocol};
+use protocol::game::auth::{login, Login};
+use protocol::game::net::{Pong, ZoneTransferReady};
+use protocol::game::play::movement_command::{Command, Walk};
+use protocol::game::play::MovementCommand;
+use quinn::Endpoint;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
+
+const ENTER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A simulated player, walking in a circle.
+pub struct Bot {
+    index: usize,
+    args: Arc<Args>,
+    metrics: Arc<Metrics>,
+    /// Start of the client clock, for the timestamps of movement commands.
+    start: Instant,
+    /// Traffic already added to the metrics.
+    bytes_sent: u64,
+    bytes_received: u64,
+}
+
+impl Bot {
+    pub fn new(index: usize, args: Arc<Args>, metrics: Arc<Metrics>) -> Self {
+        Self {
+            index,
+            args,
+            metrics,
+            start: Instant::now(),
+            bytes_sent: 0,
+            bytes_received: 0,
+        }
+    }
+
+    pub async fn run(mut self, lobby: &Lobby, endpoint: &Endpoint) -> Result<(), Error> {
+        let dev_id = format!("{}{}", self.args.dev_id_prefix, self.index);
+        let (token, character_id) = lobby.enter(self.index, &dev_id).await?;
+
+        let connect_start = Instant::now();
+        let mut client = Client::connect(endpoint, self.args.game_address, &self.args.game_server_name).await?;
+        if let Err(e) = self.enter(&mut client, token, character_id).await {
+            client.close();
+            return Err(e);
+        }
+        self.metrics.connected(connect_start.elapsed());
+
+        let result = self.play(&mut client).await;
+        self.count_traffic(&client);
+        self.metrics.playing.fetch_sub(1, Ordering::Relaxed);
+        client.close();
+
+        result
+    }
+
+    /// Log in and complete the zone transfer.
+    async fn enter(
+        &self,
+        client: &mut Client,
+        token: String,
+        character_id: i64,
+    ) -> Result<(), Error> {
+        let result = timeout(ENTER_TIMEOUT, client.login(Login {
+            token,
+            character_id,
+            kind: login::Kind::Enter.into(),
+            ..Default::default()
+        })).await.map_err(|_| Error::Timeout("login result"))??;
+        if let Some(error) = result.error {
+            return Err(Error::Login(error));
+        }
+
+        timeout(ENTER_TIMEOUT, async {
+            loop {
+                match client.recv().await? {
+                    IngressClientProtocol::ZoneTransfer(_) => return Ok::<_, Error>(()),
+                    IngressClientProtocol::Ping(ping) => {
+                        client.send(&Pong { timestamp: ping.timestamp }).await?;
+                    }
+                    _ => {}
+                }
+            }
+        }).await.map_err(|_| Error::Timeout("zone transfer"))??;
+
+        client.send(&ZoneTransferReady::default()).await?;
+
+        Ok(())
+    }
+
+    async fn play(&mut self, client: &mut Client) -> Result<(), Error> {
+        let mut commands = tokio::time::interval(Duration::from_millis(self.args.command_interval));
+        let end = tokio::time::sleep(Duration::from_secs(self.args.duration));
+        tokio::pin!(end);
+
+        let mut last_sync: Option<Instant> = None;
+        let mut angle = self.index as f32;
+
+        loop {
+            tokio::select! {
+                _ = &mut end => return Ok(()),
+                _ = commands.tick() => {
+                    angle += 0.1;
+                    client.send(&MovementCommand {
+                        timestamp: self.start.elapsed().as_millis() as i64,
+                        command: Some(Command::Walk(Walk {
+                            direction: Some(protocol::Vector2 { x: angle.cos(), y: angle.sin() }),
+                        })),
+                    }).await?;
+
+                    self.count_traffic(client);
+                }
+                protocol = client.recv() => match protocol? {
+                    IngressClientProtocol::Ping(ping) => {
+                        client.send(&Pong { timestamp: ping.timestamp }).await?;
+                    }
+                    IngressClientProtocol::MovementSync(_) => {
+                        let now = Instant::now();
+                        if let Some(last_sync) = last_sync {
+                            self.metrics.synced(now - last_sync);
+                        }
+                        last_sync = Some(now);
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    fn count_traffic(&mut self, client: &Client) {
+        let bytes_sent = client.stats().bytes_sent.load(Ordering::Relaxed);
+        let bytes_received = client.stats().bytes_received.load(Ordering::Relaxed);
+
+        self.metrics.bytes_sent.fetch_add(bytes_sent - self.bytes_sent, Ordering::Relaxed);
+        self.metrics.bytes_received.fetch_add(bytes_received - self.bytes_received, Ordering::Relaxed);
+        self.bytes_sent = bytes_sent;
+        self.bytes_received = bytes_received;
+    }
+}
@@ -0,0 +1,32 @@
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    IO(#[from] std::io::Error),
+
+    #[error("Invalid lobby URL: {0}")]
+    LobbyUrl(String),
+
+    #[error("Lobby transport error: {0}")]
+    Transport(#[from] tonic::transport::Error),
+
+    #[error("Lobby error: {0}")]
+    Lobby(#[from] tonic::Status),
+
+    #[error("Invalid token: {0}")]
+    InvalidToken(#[from] tonic::metadata::errors::InvalidMetadataValue),
+
+    #[error("Token error: {0}")]
+    Token(#[from] jsonwebtoken::errors::Error),
+
+    #[error("Character not created")]
+    Character,
+
+    #[error("Game client error: {0}")]
+    Client(#[from] game_client::Error),
+
+    #[error("Login refused: {0}")]
+    Login(i32),
+
+    #[error("Timed out waiting for {0}")]
+    Timeout(&'static str),
+}
@@ -0,0 +1,96 @@
+use crate::error::Error;
+use crate::Args;
+use jsonwebtoken::EncodingKey;
+use protocol::lobby::characters_client::CharactersClient;
+use protocol::lobby::dev_auth_client::DevAuthClient;
+use protocol::lobby::{CreateCharacterRequest, GetDevAccountRequest, GetDevTokenRequest};
+use std::time::Duration;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig};
+use tonic::Request;
+
+const TOKEN_EXPIRATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Dev accounts and characters of the bots.
+pub struct Lobby {
+    /// Made up accounts and characters are used without it.
+    channel: Option<Channel>,
+    /// Signs tokens without asking the lobby, if given.
+    token_key: Option<EncodingKey>,
+}
+
+impl Lobby {
+    pub async fn connect(args: &Args) -> Result<Self, Error> {
+        let channel = if args.no_lobby {
+            None
+        } else {
+            let cert = Certificate::from_pem(std::fs::read(&args.lobby_cert)?);
+            let channel = Channel::from_shared(args.lobby_url.clone())
+                .map_err(|_| Error::LobbyUrl(args.lobby_url.clone()))?
+                .tls_config(ClientTlsConfig::new().ca_certificate(cert))?
+                .connect()
+                .await?;
+
+            Some(channel)
+        };
+
+        let token_key = match &args.token_key {
+            Some(path) => Some(EncodingKey::from_secret(util::io::read_file(path)?.as_bytes())),
+            None => None,
+        };
+
+        Ok(Self { channel, token_key })
+    }
+
+    /// Token and character of the dev account of the bot, creating both if needed.
+    pub async fn enter(&self, index: usize, dev_id: &str) -> Result<(String, i64), Error> {
+        let Some(channel) = &self.channel else {
+            // The account and character share an id made up from the index.
+            let id = index as i64 + 1;
+            let key = self.token_key.as_ref().expect("Signing tokens without lobby");
+
+            return Ok((util::token::generate(id, key, TOKEN_EXPIRATION)?, id));
+        };
+        let mut dev_auth = DevAuthClient::new(channel.clone());
+
+        let account_id = dev_auth
+            .get_dev_account(GetDevAccountRequest { dev_id: dev_id.to_string() })
+            .await?
+            .into_inner()
+            .account_id;
+
+        let token = match &self.token_key {
+            Some(key) => util::token::generate(account_id, key, TOKEN_EXPIRATION)?,
+            None => dev_auth
+                .get_dev_token(GetDevTokenRequest { account_id })
+                .await?
+                .into_inner()
+                .token,
+        };
+
+        let authentication = token.parse()?;
+        let mut characters = CharactersClient::with_interceptor(
+            channel.clone(),
+            move |mut request: Request<()>| {
+                request.metadata_mut().insert("authentication", authentication.clone());
+                Ok(request)
+            },
+        );
+
+        let existing = characters.list_characters(()).await?.into_inner().characters;
+        let character_id = match existing.first() {
+            Some(character) => character.id,
+            None => characters
+                .create_character(CreateCharacterRequest {
+                    name: dev_id.to_string(),
+                    ..Default::default()
+                })
+                .await?
+                .into_inner()
+                .character
+                .ok_or(Error::Character)?
+                .id,
+        };
+
+        Ok((token, character_id))
+    }
+}
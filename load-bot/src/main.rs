@@ -0,0 +1,133 @@
+mod bot;
+mod error;
+mod lobby;
+mod report;
+
+use crate::bot::Bot;
+use crate::error::Error;
+use crate::lobby::Lobby;
+use crate::report::Metrics;
+use clap::Parser;
+use rustls::crypto::aws_lc_rs;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process::exit;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tracing::{error, info};
+
+/// Simulated clients for load testing a game server
+#[derive(clap::Parser, Debug)]
+pub struct Args {
+    /// Number of bots
+    #[arg(long, default_value_t = 10)]
+    bots: usize,
+
+    /// Delay between spawning bots, in milliseconds
+    #[arg(long, default_value_t = 100)]
+    spawn_interval: u64,
+
+    /// How long each bot plays after entering, in seconds
+    #[arg(long, default_value_t = 60)]
+    duration: u64,
+
+    /// Delay between movement commands of a bot, in milliseconds
+    #[arg(long, default_value_t = 100)]
+    command_interval: u64,
+
+    /// Delay between reports, in seconds
+    #[arg(long, default_value_t = 5)]
+    report_interval: u64,
+
+    /// Prefix of the dev ids of bots, followed by their index
+    #[arg(long, default_value = "bot")]
+    dev_id_prefix: String,
+
+    #[arg(long, default_value = "127.0.0.1:6400")]
+    game_address: SocketAddr,
+
+    /// Name of the game server in its certificate
+    #[arg(long, default_value = "game.spire.localhost")]
+    game_server_name: String,
+
+    #[arg(long, default_value = "../secrets/game-server-cert.pem")]
+    game_cert: PathBuf,
+
+    /// ALPN of the game server
+    #[arg(long, default_value = "spire")]
+    application_protocol: String,
+
+    #[arg(long, default_value = "https://lobby.spire.localhost:8000")]
+    lobby_url: String,
+
+    #[arg(long, default_value = "../secrets/lobby-server-cert.pem")]
+    lobby_cert: PathBuf,
+
+    /// Sign tokens with the key, instead of asking the lobby
+    #[arg(long)]
+    token_key: Option<PathBuf>,
+
+    /// Skip the lobby and make up accounts and characters, e.g. against a game server with the
+    /// memory backend creating missing characters
+    #[arg(long, default_value_t = false, requires = "token_key")]
+    no_lobby: bool,
+}
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    if aws_lc_rs::default_provider().install_default().is_err() {
+        error!("Failed to install default provider");
+        exit(1);
+    }
+
+    if let Err(e) = run(Arc::new(Args::parse())).await {
+        error!("Failed to run: {}", e);
+        exit(1);
+    }
+}
+
+async fn run(args: Arc<Args>) -> Result<(), Error> {
+    let cert_bytes = std::fs::read(&args.game_cert)?;
+    let certs = rustls_pemfile::certs(&mut cert_bytes.as_slice()).collect::<Result<Vec<_>, _>>()?;
+    let endpoint = game_client::client::endpoint(&args.application_protocol, certs)?;
+    let lobby = Arc::new(Lobby::connect(&args).await?);
+    let metrics = Arc::new(Metrics::default());
+
+    info!("Spawning {} bots against {}", args.bots, args.game_address);
+
+    let reporter = {
+        let metrics = metrics.clone();
+        let interval = Duration::from_secs(args.report_interval);
+        tokio::spawn(async move { metrics.start_report(interval).await })
+    };
+
+    let mut bots = JoinSet::new();
+    for index in 0..args.bots {
+        let bot = Bot::new(index, args.clone(), metrics.clone());
+        let lobby = lobby.clone();
+        let endpoint = endpoint.clone();
+        let metrics = metrics.clone();
+
+        bots.spawn(async move {
+            if let Err(e) = bot.run(&lobby, &endpoint).await {
+                error!("Bot {} failed: {}", index, e);
+                metrics.failed.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+        metrics.spawned.fetch_add(1, Ordering::Relaxed);
+
+        tokio::time::sleep(Duration::from_millis(args.spawn_interval)).await;
+    }
+
+    while bots.join_next().await.is_some() {}
+    reporter.abort();
+    metrics.summary();
+
+    endpoint.wait_idle().await;
+
+    Ok(())
+}
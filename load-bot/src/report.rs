@@ -0,0 +1,146 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Measurements shared by every bot.
+#[derive(Default)]
+pub struct Metrics {
+    pub spawned: AtomicUsize,
+    pub playing: AtomicUsize,
+    pub failed: AtomicUsize,
+    pub bytes_sent: AtomicU64,
+    pub bytes_received: AtomicU64,
+    /// From connecting to completing the zone transfer.
+    connect_latencies: Mutex<Vec<Duration>>,
+    /// Between movement syncs of the zone, which are sent every tick. Drained by each report.
+    sync_intervals: Mutex<Vec<Duration>>,
+}
+
+/// Totals at the previous report, to report rates over the interval.
+struct Window {
+    at: Instant,
+    bytes_sent: u64,
+    bytes_received: u64,
+    connects: usize,
+}
+
+impl Metrics {
+    pub fn connected(&self, latency: Duration) {
+        self.connect_latencies.lock().unwrap().push(latency);
+        self.playing.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn synced(&self, interval: Duration) {
+        self.sync_intervals.lock().unwrap().push(interval);
+    }
+
+    /// Log a report every interval, until aborted.
+    pub async fn start_report(&self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+
+        let mut window = Window {
+            at: Instant::now(),
+            bytes_sent: 0,
+            bytes_received: 0,
+            connects: 0,
+        };
+
+        loop {
+            ticker.tick().await;
+            window = self.report(window);
+        }
+    }
+
+    fn report(&self, window: Window) -> Window {
+        let now = Instant::now();
+        let elapsed = now.duration_since(window.at).as_secs_f64();
+        let playing = self.playing.load(Ordering::Relaxed);
+        let bytes_sent = self.bytes_sent.load(Ordering::Relaxed);
+        let bytes_received = self.bytes_received.load(Ordering::Relaxed);
+
+        let (connects, connect) = {
+            let latencies = self.connect_latencies.lock().unwrap();
+            (latencies.len(), Summary::of(&latencies[window.connects..]))
+        };
+        let sync = Summary::of(&std::mem::take(&mut *self.sync_intervals.lock().unwrap()));
+
+        // Per client, to compare runs with different numbers of bots.
+        let per_client = |bytes: u64| (bytes as f64 / elapsed / playing.max(1) as f64) as u64;
+
+        info!(
+            "bots(spawned={}, playing={}, failed={}), connect(avg={:?}, p99={:?}, max={:?}), tick(rate={:.1}/s, avg={:?}, p99={:?}, max={:?}), bandwidth(out={}B/s, in={}B/s per client)",
+            self.spawned.load(Ordering::Relaxed),
+            playing,
+            self.failed.load(Ordering::Relaxed),
+            connect.avg,
+            connect.p99,
+            connect.max,
+            sync.rate(),
+            sync.avg,
+            sync.p99,
+            sync.max,
+            per_client(bytes_sent - window.bytes_sent),
+            per_client(bytes_received - window.bytes_received),
+        );
+
+        Window {
+            at: now,
+            bytes_sent,
+            bytes_received,
+            connects,
+        }
+    }
+
+    pub fn summary(&self) {
+        let connect = Summary::of(&self.connect_latencies.lock().unwrap());
+
+        info!(
+            "Done: bots(spawned={}, connected={}, failed={}), connect(avg={:?}, p99={:?}, max={:?}), traffic(out={}B, in={}B)",
+            self.spawned.load(Ordering::Relaxed),
+            connect.count,
+            self.failed.load(Ordering::Relaxed),
+            connect.avg,
+            connect.p99,
+            connect.max,
+            self.bytes_sent.load(Ordering::Relaxed),
+            self.bytes_received.load(Ordering::Relaxed),
+        );
+    }
+}
+
+#[derive(Default)]
+struct Summary {
+    count: usize,
+    avg: Duration,
+    p99: Duration,
+    max: Duration,
+}
+
+impl Summary {
+    fn of(durations: &[Duration]) -> Self {
+        if durations.is_empty() {
+            return Self::default();
+        }
+
+        let mut sorted = durations.to_vec();
+        sorted.sort();
+
+        Self {
+            count: sorted.len(),
+            avg: sorted.iter().sum::<Duration>() / sorted.len() as u32,
+            p99: sorted[(sorted.len() - 1) * 99 / 100],
+            max: sorted[sorted.len() - 1],
+        }
+    }
+
+    /// Ticks per second, taking the durations as intervals between ticks.
+    fn rate(&self) -> f64 {
+        if self.avg.is_zero() {
+            return 0.0;
+        }
+
+        1.0 / self.avg.as_secs_f64()
+    }
+}
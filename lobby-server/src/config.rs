@@ -33,8 +33,15 @@ pub struct Config {
 
     #[serde(alias = "lobby_server_node_id")]
     pub node_id: u16,
+
+    /// Listings returned at most by an auction search, as `auction.search_limit` of the game
+    /// server.
+    #[serde(default = "auction_search_limit_default")]
+    pub auction_search_limit: u16,
 }
 
+fn auction_search_limit_default() -> u16 { 50 }
+
 pub fn init() -> Result<(), Box<dyn std::error::Error>> {
     let config = config::Config::builder()
         .add_source(config::Environment::with_prefix("SPIRE"))
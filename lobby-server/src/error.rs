@@ -11,6 +11,9 @@ pub enum Error {
     #[error("Validation error: {0}")]
     Validation(String),
 
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+
     #[error("Not found: {0}")]
     NotFound(String),
 
@@ -30,6 +33,7 @@ impl From<Error> for tonic::Status {
                 tonic::Status::internal("Database query error")
             },
             Error::Validation(msg) => tonic::Status::unauthenticated(msg),
+            Error::InvalidArgument(msg) => tonic::Status::invalid_argument(msg),
             Error::NotFound(msg) => tonic::Status::not_found(msg),
             Error::UnknownEnumValue(_) => tonic::Status::invalid_argument("")
         }
@@ -5,8 +5,9 @@ mod service;
 
 use crate::config::config;
 use crate::middleware::authenticator::Authenticator;
-use crate::service::{characters, dev_auth};
+use crate::service::{auction, characters, dev_auth};
 use protocol::lobby::{
+    auction_server::AuctionServer,
     characters_server::CharactersServer,
     dev_auth_server::DevAuthServer,
 };
@@ -37,8 +38,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let authenticator = Authenticator::new();
 
     let authenticated_service = ServiceBuilder::new()
-        .layer(InterceptorLayer::new(authenticator))
+        .layer(InterceptorLayer::new(authenticator.clone()))
         .service(CharactersServer::new(characters::Server::new()));
+    let authenticated_auction_service = ServiceBuilder::new()
+        .layer(InterceptorLayer::new(authenticator))
+        .service(AuctionServer::new(auction::Server::new()));
 
     let addr = format!("[::]:{}", config().port).parse()?;
     info!("Serving on {}", addr);
@@ -49,6 +53,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .add_service(DevAuthServer::new(dev_auth::Server::new()))
         // .add_service(SteamAuthServer::new())
         .add_service(authenticated_service)
+        .add_service(authenticated_auction_service)
         .serve(addr)
         .await?;
 
@@ -1,3 +1,4 @@
+pub mod auction;
 pub mod characters;
 pub mod dev_auth;
 pub mod steam_auth;
@@ -0,0 +1,97 @@
+use crate::config::config;
+use crate::error::Error;
+use db::auction::{Category, Filter, Listing, Sort};
+use protocol::lobby::auction_server::Auction;
+use protocol::lobby::{SearchListingsRequest, SearchListingsResponse};
+use protocol::{AuctionCategory, AuctionListing, AuctionSort};
+use tonic::{Request, Response, Status};
+
+/// Browsing the auction house from the character select screen. Listing, buying and cancelling
+/// are done in game, which owns the items.
+pub struct Server;
+
+impl Server {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[tonic::async_trait]
+impl Auction for Server {
+    async fn search_listings(
+        &self,
+        request: Request<SearchListingsRequest>,
+    ) -> Result<Response<SearchListingsResponse>, Status> {
+        let filter = request.into_inner().filter.unwrap_or_default();
+        let search_limit = config().auction_search_limit as i64;
+        let level = |level: Option<u32>| {
+            level
+                .map(i16::try_from)
+                .transpose()
+                .map_err(|_| Error::InvalidArgument("Level out of range".to_string()))
+        };
+
+        let filter = Filter {
+            category: filter.category
+                .map(|category| AuctionCategory::try_from(category).map(category_of))
+                .transpose()
+                .map_err(Error::UnknownEnumValue)?,
+            data_id: filter.data_id,
+            min_level: level(filter.min_level)?,
+            max_level: level(filter.max_level)?,
+            max_price: filter.max_price,
+            seller_id: filter.seller_id,
+            sort: match filter.sort() {
+                AuctionSort::PriceAscending => Sort::PriceAscending,
+                AuctionSort::PriceDescending => Sort::PriceDescending,
+                AuctionSort::ExpireAt => Sort::ExpireAt,
+            },
+            offset: filter.offset as i64,
+            limit: match filter.limit as i64 {
+                0 => search_limit,
+                limit => limit.min(search_limit),
+            },
+        };
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as i64);
+
+        let mut conn = db::conn().await.map_err(Error::DatabaseConnection)?;
+        let listings = db::auction::search(&mut conn, &filter, now)
+            .await
+            .map_err(Error::DatabaseQuery)?;
+
+        let listings = listings.iter().map(listing).collect();
+        let response = SearchListingsResponse { listings };
+
+        Ok(Response::new(response))
+    }
+}
+
+fn category_of(category: AuctionCategory) -> Category {
+    match category {
+        AuctionCategory::Equipment => Category::Equipment,
+        AuctionCategory::RandomBox => Category::RandomBox,
+        AuctionCategory::Other => Category::Other,
+    }
+}
+
+fn listing(listing: &Listing) -> AuctionListing {
+    let category = match listing.category() {
+        Category::Equipment => AuctionCategory::Equipment,
+        Category::RandomBox => AuctionCategory::RandomBox,
+        Category::Other => AuctionCategory::Other,
+    };
+
+    AuctionListing {
+        id: listing.id,
+        seller_id: listing.seller_id,
+        data_id: listing.item_data_id,
+        level: listing.item_level as u32,
+        count: listing.item_count,
+        category: category.into(),
+        price: listing.price,
+        expire_at: listing.expire_at,
+    }
+}
@@ -4,15 +4,29 @@ use std::path::PathBuf;
 
 use glob::glob;
 
+/// Written by prost-build, for the fields of game protocols in the docs.
+const GAME_DESCRIPTOR_SET: &str = "game.descriptor.bin";
+
 fn main() -> Result<(), Box<dyn Error>> {
     compile()?;
 
+    // The schema hash and version are generated from them as well.
+    println!("cargo:rerun-if-changed=inner/schema/version.json");
+    println!("cargo:rerun-if-changed=inner/schema/game.lock.json");
+    for category in glob("inner/schema/game/*.json")?.filter_map(Result::ok) {
+        println!("cargo:rerun-if-changed={}", category.display());
+    }
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
     let config = protocol_generator::Config {
         schema_dir: PathBuf::from("inner/schema"),
-        gen_dir: PathBuf::from(env::var("OUT_DIR")?),
+        gen_dir: out_dir.clone(),
         docs_dir: Some(PathBuf::from("inner/docs")),
+        descriptor_set: Some(out_dir.join(GAME_DESCRIPTOR_SET)),
+        lock_file: Some(PathBuf::from("inner/schema/game.lock.json")),
         generate_impl: true,
         generate_handle: false,
+        generate_client: false,
     };
     config.generate()?;
 
@@ -43,7 +57,9 @@ pub fn compile() -> Result<(), Box<dyn Error>> {
         println!("cargo:rerun-if-changed={}", schema.display());
     }
 
-    prost_build::compile_protos(&schemas, &[&schema_base_dir, &schema_dir])?;
+    prost_build::Config::new()
+        .file_descriptor_set_path(PathBuf::from(env::var("OUT_DIR")?).join(GAME_DESCRIPTOR_SET))
+        .compile_protos(&schemas, &[&schema_base_dir, &schema_dir])?;
 
     // Lobby protocols
     let schema_dir = schema_base_dir.join("lobby");
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use prost::Message;
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{DescriptorProto, FieldDescriptorProto, FileDescriptorSet};
+use serde::Serialize;
+
+use crate::error::Error;
+
+/// Messages of the file descriptor set written by prost-build, by full name.
+#[derive(Debug, Default)]
+pub struct Descriptors {
+    messages: HashMap<String, DescriptorProto>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Field {
+    pub name: String,
+    pub number: i32,
+    /// Type as written in the schema, like `repeated uint64` or `map<string, Item>`.
+    #[serde(rename = "type")]
+    pub r#type: String,
+}
+
+impl Descriptors {
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let set = FileDescriptorSet::decode(fs::read(path)?.as_slice())?;
+        let mut descriptors = Self::default();
+
+        for file in set.file {
+            let package = file.package.unwrap_or_default();
+            for message in file.message_type {
+                descriptors.insert(&package, message);
+            }
+        }
+
+        Ok(descriptors)
+    }
+
+    fn insert(&mut self, scope: &str, message: DescriptorProto) {
+        let full_name = format!("{}.{}", scope, message.name());
+        for nested in message.nested_type.iter().cloned() {
+            self.insert(&full_name, nested);
+        }
+
+        self.messages.insert(full_name, message);
+    }
+
+    /// Fields of the message, in declaration order.
+    pub fn fields(&self, full_name: &str) -> Option<Vec<Field>> {
+        let message = self.messages.get(full_name)?;
+
+        Some(message.field
+            .iter()
+            .map(|field| Field {
+                name: field.name().to_string(),
+                number: field.number(),
+                r#type: self.field_type(field),
+            })
+            .collect())
+    }
+
+    fn field_type(&self, field: &FieldDescriptorProto) -> String {
+        let type_name = field.type_name().trim_start_matches('.');
+
+        // Maps are repeated fields of a generated entry message.
+        if let Some(entry) = self.messages.get(type_name)
+            && entry.options.as_ref().is_some_and(|options| options.map_entry())
+            && let [key, value] = entry.field.as_slice() {
+            return format!("map<{}, {}>", self.field_type(key), self.field_type(value));
+        }
+
+        let r#type = match field.r#type() {
+            Type::Message | Type::Enum | Type::Group => short_name(type_name).to_string(),
+            scalar => scalar.as_str_name().trim_start_matches("TYPE_").to_lowercase(),
+        };
+
+        match field.label() {
+            Label::Repeated => format!("repeated {}", r#type),
+            _ if field.proto3_optional() => format!("optional {}", r#type),
+            _ => r#type,
+        }
+    }
+}
+
+/// Name relative to the game package, e.g. `auth.Login` for `spire.protocol.game.auth.Login`.
+fn short_name(full_name: &str) -> &str {
+    full_name.strip_prefix("spire.protocol.game.").unwrap_or(full_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost_types::{FileDescriptorProto, MessageOptions};
+
+    fn field(name: &str, number: i32, r#type: Type, label: Label, type_name: Option<&str>) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            r#type: Some(r#type.into()),
+            label: Some(label.into()),
+            type_name: type_name.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn formats_field_types() {
+        let entry = DescriptorProto {
+            name: Some("ItemsEntry".to_string()),
+            field: vec![
+                field("key", 1, Type::Uint64, Label::Optional, None),
+                field("value", 2, Type::Message, Label::Optional, Some(".spire.protocol.game.play.Item")),
+            ],
+            options: Some(MessageOptions { map_entry: Some(true), ..Default::default() }),
+            ..Default::default()
+        };
+        let message = DescriptorProto {
+            name: Some("Inventory".to_string()),
+            field: vec![
+                field("owner", 1, Type::Uint64, Label::Optional, None),
+                field("tags", 2, Type::String, Label::Repeated, None),
+                field("items", 3, Type::Message, Label::Repeated, Some(".spire.protocol.game.play.Inventory.ItemsEntry")),
+            ],
+            nested_type: vec![entry],
+            ..Default::default()
+        };
+        let set = FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                package: Some("spire.protocol.game.play".to_string()),
+                message_type: vec![message],
+                ..Default::default()
+            }],
+        };
+
+        let path = std::env::temp_dir().join("protocol-generator-descriptor-test.bin");
+        fs::write(&path, set.encode_to_vec()).unwrap();
+        let descriptors = Descriptors::load(&path).unwrap();
+
+        let types: Vec<String> = descriptors
+            .fields("spire.protocol.game.play.Inventory")
+            .unwrap()
+            .into_iter()
+            .map(|field| field.r#type)
+            .collect();
+        assert_eq!(types, ["uint64", "repeated string", "map<uint64, play.Item>"]);
+    }
+}
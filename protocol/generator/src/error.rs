@@ -5,4 +5,37 @@ pub enum Error {
 
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
+
+    #[error("Descriptor error: {0}")]
+    Descriptor(#[from] prost::DecodeError),
+
+    #[error("Protocol id {1} of {0} is out of the range of its category")]
+    ProtocolId(String, u16),
+
+    #[error("Protocol id {0} is assigned to both {1} and {2}")]
+    DuplicateId(u16, String, String),
+
+    #[error("Range {1}..{2} of category {0} is out of protocol ids")]
+    CategoryRange(String, u16, u16),
+
+    #[error("Categories {0} and {1} overlap")]
+    OverlappingCategories(String, String),
+
+    #[error("Response {1} of protocol {0} does not exist")]
+    UnknownResponse(String, String),
+
+    #[error("Protocol {0} is locked to id {1}, but now has {2}")]
+    Renumbered(String, u16, u16),
+
+    #[error("Protocol {0} locked to id {1} was removed without being retired")]
+    Removed(String, u16),
+
+    #[error("Protocol {0} reuses id {1} of retired {2}")]
+    RetiredId(String, u16, String),
+
+    #[error("Path {0:?} is not valid UTF-8")]
+    NonUtf8Path(std::path::PathBuf),
+
+    #[error("Glob pattern error: {0}")]
+    Pattern(#[from] glob::PatternError),
 }
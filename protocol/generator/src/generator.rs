@@ -1,10 +1,15 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::fs::File;
 use std::io::{BufWriter, Write};
+use std::path::Path;
 use glob::glob;
 use serde::Deserialize;
 
 use crate::*;
+use crate::descriptor::Descriptors;
+use crate::lock::Lock;
+use crate::manifest::{Manifest, ManifestProtocol, ManifestRateLimit, ManifestVersion};
 
 const TAB: &str= "    ";
 
@@ -13,29 +18,75 @@ pub struct Generator {
     config: Config,
     categories: Vec<Category>,
     protocol_entries: Vec<ProtocolEntry>,
+    version: Version,
+    schema_hash: u64,
 }
 
+/// Version of the schema, bumped by hand in `version.json`. Clients of another major version
+/// are incompatible, while a minor version only adds to the previous one.
+#[derive(Debug, Default, Deserialize)]
+struct Version {
+    major: u32,
+    minor: u32,
+}
+
+/// Highest bit of the id field in the header flags compressed bodies.
+const MAX_PROTOCOL_ID: u16 = 0x7fff;
+
 #[derive(Debug, Deserialize)]
 struct Category {
     category: String,
     offset: u16,
+    /// Ids reserved for the category from `offset`. Up to the next category by default.
+    #[serde(default)]
+    capacity: Option<u16>,
+    /// QUIC stream carrying the protocols of the category. `0` is the main bi-directional
+    /// stream, others are uni-directional streams opened on demand.
+    #[serde(default)]
+    stream: u8,
+    /// Shared by every protocol of the category without its own limit.
+    #[serde(default)]
+    rate_limit: Option<RateLimit>,
     protocols: Vec<Protocol>,
+    /// Protocols removed on purpose. Their ids stay locked, never to be reused.
+    #[serde(default)]
+    retired: Vec<String>,
 }
 
 fn protocol_handle_default() -> bool { true }
 #[derive(Debug, Deserialize)]
 struct Protocol {
     protocol: String,
+    /// Following the previous protocol of the category by default.
+    #[serde(default)]
+    id: Option<u16>,
     target: ProtocolTarget,
     #[serde(default = "protocol_handle_default")]
     handle: bool,
     #[serde(default)]
     handler: ProtocolHandler,
-    
+    #[serde(default)]
+    rate_limit: Option<RateLimit>,
+    /// Protocol answering this one, in the same category unless given as `category.Protocol`.
+    /// `{protocol}Result` of the same category by default, if any.
+    #[serde(default)]
+    response: Option<String>,
+
     #[serde(default, rename = "box")]
     __box: bool,
 }
 
+fn rate_limit_penalty_default() -> f32 { 1.0 }
+/// Token bucket of protocols received from a client.
+#[derive(Debug, Deserialize, Clone, Copy)]
+struct RateLimit {
+    rate: f32,
+    capacity: f32,
+    /// Abuse score added on each violation.
+    #[serde(default = "rate_limit_penalty_default")]
+    penalty: f32,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "lowercase")]
 enum ProtocolTarget {
@@ -56,22 +107,80 @@ enum ProtocolHandler {
 struct ProtocolEntry {
     category: String,
     number: u16,
+    stream: u8,
+    /// Limit of the category, keyed by its offset.
+    category_rate_limit: Option<(u16, RateLimit)>,
     protocol: Protocol,
 }
 
+/// Give each protocol its explicit id, or the one following the previous protocol of the
+/// category, checking that ids stay in the range of their category and are unique.
+fn assign_ids(categories: Vec<Category>) -> Result<Vec<ProtocolEntry>, Error> {
+    let mut entries = Vec::new();
+    let mut assigned: HashMap<u16, String> = HashMap::new();
+
+    let ends: Vec<u16> = categories
+        .iter()
+        .enumerate()
+        .map(|(i, category)| {
+            let next_offset = categories.get(i + 1).map_or(MAX_PROTOCOL_ID + 1, |next| next.offset);
+            category.capacity.map_or(next_offset, |capacity| category.offset.saturating_add(capacity))
+        })
+        .collect();
+
+    for (i, category) in categories.iter().enumerate() {
+        if ends[i] > MAX_PROTOCOL_ID + 1 {
+            return Err(Error::CategoryRange(category.category.clone(), category.offset, ends[i]));
+        }
+
+        if let Some(next) = categories.get(i + 1) && ends[i] > next.offset {
+            return Err(Error::OverlappingCategories(category.category.clone(), next.category.clone()));
+        }
+    }
+
+    for (mut category, end) in categories.into_iter().zip(ends) {
+        let mut next_id = category.offset;
+
+        for protocol in category.protocols.drain(..) {
+            let name = format!("{}.{}", category.category, protocol.protocol);
+            let number = protocol.id.unwrap_or(next_id);
+
+            if number < category.offset || number >= end {
+                return Err(Error::ProtocolId(name, number));
+            }
+
+            if let Some(other) = assigned.insert(number, name.clone()) {
+                return Err(Error::DuplicateId(number, other, name));
+            }
+
+            entries.push(ProtocolEntry {
+                category: category.category.clone(),
+                number,
+                stream: category.stream,
+                category_rate_limit: category.rate_limit.map(|limit| (category.offset, limit)),
+                protocol,
+            });
+            next_id = number + 1;
+        }
+    }
+
+    Ok(entries)
+}
+
 impl Generator {
     pub fn new(config: Config) -> Self {
         Self {
             config,
             categories: Vec::new(),
             protocol_entries: Vec::new(),
+            version: Version::default(),
+            schema_hash: 0,
         }
     }
 
     pub fn collect(&mut self) -> Result<(), Error> {
-        let category_files: Vec<PathBuf> = glob(self.config.schema_dir.join("game/*.json").to_str().unwrap())
-            .unwrap()
-            .filter_map(Result::ok)
+        let category_files: Vec<PathBuf> = glob_paths(&self.config.schema_dir.join("game/*.json"))?
+            .into_iter()
             .filter(|path| {
                 !path.to_string_lossy().ends_with(".schema.json")
             })
@@ -84,22 +193,84 @@ impl Generator {
 
         self.categories.sort_by(|a, b| a.offset.cmp(&b.offset));
 
-        for mut category in self.categories.drain(..) {
-            let mut number = category.offset;
+        let retired: Vec<String> = self.categories
+            .iter()
+            .flat_map(|category| {
+                category.retired
+                    .iter()
+                    .map(|protocol| format!("{}.{}", category.category, protocol))
+            })
+            .collect();
+        self.protocol_entries = assign_ids(self.categories.drain(..).collect())?;
 
-            for protocol in category.protocols.drain(..) {
-                self.protocol_entries.push(ProtocolEntry {
-                    category: category.category.clone(),
-                    number,
-                    protocol,
-                });
-                number += 1;
+        if let Some(lock_file) = &self.config.lock_file {
+            let protocols: BTreeMap<String, u16> = self.protocol_entries
+                .iter()
+                .map(|entry| (format!("{}.{}", entry.category, entry.protocol.protocol), entry.number))
+                .collect();
+
+            let mut lock = Lock::load(lock_file)?;
+            if lock.update(&protocols, &retired)? {
+                lock.save(lock_file)?;
             }
         }
 
+        let version_file = self.config.schema_dir.join("version.json");
+        if version_file.exists() {
+            self.version = serde_json::from_str(&fs::read_to_string(&version_file)?)?;
+        }
+        self.schema_hash = self.hash_schema()?;
+
         Ok(())
     }
 
+    /// FNV-1a hash of the protocol table and the message definitions, ignoring comments and
+    /// formatting, so that builds agree on it only if they speak the same protocols.
+    fn hash_schema(&self) -> Result<u64, Error> {
+        const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+        const PRIME: u64 = 0x100000001b3;
+
+        let mut hash = OFFSET_BASIS;
+        let mut feed = |text: &str| {
+            for byte in text.bytes().chain(std::iter::once(b'\n')) {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(PRIME);
+            }
+        };
+
+        for entry in &self.protocol_entries {
+            feed(&format!(
+                "{} {} {} {:?} {:?} {} {} {}",
+                entry.category,
+                entry.number,
+                entry.protocol.protocol,
+                entry.protocol.target,
+                entry.protocol.handler,
+                entry.protocol.handle,
+                entry.protocol.__box,
+                entry.stream,
+            ));
+        }
+
+        let mut proto_files = glob_paths(&self.config.schema_dir.join("*.proto"))?;
+        proto_files.extend(glob_paths(&self.config.schema_dir.join("game/**/*.proto"))?);
+        proto_files.sort();
+
+        for proto_file in &proto_files {
+            let relative_path = proto_file.strip_prefix(&self.config.schema_dir).unwrap_or(proto_file);
+            feed(&relative_path.to_string_lossy().replace('\\', "/"));
+
+            for line in fs::read_to_string(proto_file)?.lines() {
+                let line = line.split("//").next().unwrap_or_default().trim();
+                if !line.is_empty() {
+                    feed(line);
+                }
+            }
+        }
+
+        Ok(hash)
+    }
+
     pub fn generate(&self) -> Result<(), Error> {
         if self.config.generate_impl {
             self.generate_impl()?;
@@ -109,6 +280,10 @@ impl Generator {
             self.generate_handle()?;
         }
 
+        if self.config.generate_client {
+            self.generate_client()?;
+        }
+
         self.generate_docs()?;
 
         Ok(())
@@ -121,10 +296,44 @@ impl Generator {
         let mut protocol_local_decodes = Vec::new();
         let mut protocol_global_decodes = Vec::new();
         let mut protocol_handler_enums = Vec::new();
+        let mut protocol_local_ids = Vec::new();
+        let mut protocol_local_encodes = Vec::new();
+        let mut protocol_streams = Vec::new();
+        let mut protocol_rate_limits = Vec::new();
+        let stream_count = self.protocol_entries
+            .iter()
+            .map(|entry| entry.stream as usize + 1)
+            .max()
+            .unwrap_or(1);
 
         for entry in &self.protocol_entries {
             let protocol_full_name = format!("{}::{}", entry.category, entry.protocol.protocol);
 
+            let rate_limit = match (entry.protocol.rate_limit, entry.category_rate_limit) {
+                (Some(limit), _) => Some((format!("Protocol({})", entry.number), limit)),
+                (None, Some((offset, limit))) => Some((format!("Category({})", offset), limit)),
+                (None, None) => None,
+            };
+            if let Some((key, limit)) = rate_limit {
+                protocol_rate_limits.push(format!(
+                    "{TAB}{TAB}{} => Some(RateLimit {{ key: RateLimitKey::{key}, rate: {:?}, capacity: {:?}, penalty: {:?} }}), // {}",
+                    entry.number,
+                    limit.rate,
+                    limit.capacity,
+                    limit.penalty,
+                    entry.protocol.protocol,
+                ));
+            }
+
+            if entry.stream != 0 {
+                protocol_streams.push(format!(
+                    "{TAB}{TAB}{} => {}, // {}",
+                    entry.number,
+                    entry.stream,
+                    entry.protocol.protocol,
+                ));
+            }
+
             protocol_impls.push(format!(r#"
 impl crate::game::Protocol for {protocol_full_name} {{
     fn protocol_id(&self) -> crate::game::ProtocolId {{ {} }}
@@ -182,11 +391,23 @@ impl crate::game::Protocol for {protocol_full_name} {{
             };
 
             match entry.protocol.handler {
-                ProtocolHandler::Local => push(
-                    &mut protocol_local_enums,
-                    &mut protocol_local_decodes,
-                    "Local",
-                ),
+                ProtocolHandler::Local => {
+                    push(
+                        &mut protocol_local_enums,
+                        &mut protocol_local_decodes,
+                        "Local",
+                    );
+
+                    protocol_local_ids.push(format!(
+                        "{TAB}{TAB}{TAB}{}(_) => {},",
+                        entry.protocol.protocol,
+                        entry.number,
+                    ));
+                    protocol_local_encodes.push(format!(
+                        "{TAB}{TAB}{TAB}{}(p) => p.encode_to_vec(),",
+                        entry.protocol.protocol,
+                    ));
+                },
                 ProtocolHandler::Global => push(
                     &mut protocol_global_enums,
                     &mut protocol_global_decodes,
@@ -205,6 +426,51 @@ pub enum IngressGlobalProtocol {{
 {protocol_global_enums_code}
 }}
 
+impl IngressLocalProtocol {{
+    pub fn protocol_id(&self) -> ProtocolId {{
+        use IngressLocalProtocol::*;
+
+        match self {{
+{protocol_local_ids_code}
+        }}
+    }}
+
+    /// Encode the protocol body without header.
+    pub fn encode_body(&self) -> Vec<u8> {{
+        use IngressLocalProtocol::*;
+
+        match self {{
+{protocol_local_encodes_code}
+        }}
+    }}
+}}
+
+/// Version of the protocol schema.
+pub const PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion {{ major: {version_major}, minor: {version_minor} }};
+
+/// Hash of the protocol table and the message definitions. Equal only between builds speaking
+/// the same protocols.
+pub const SCHEMA_HASH: u64 = {schema_hash:#018x};
+
+/// Number of streams protocols are carried on, including the main stream.
+pub const STREAM_COUNT: usize = {stream_count};
+
+/// The stream carrying the protocol.
+pub fn protocol_stream(id: ProtocolId) -> StreamId {{
+    match id {{
+{protocol_streams_code}
+        _ => 0,
+    }}
+}}
+
+/// Rate limit of the protocol received from a client, declared by it or its category.
+pub fn protocol_rate_limit(id: ProtocolId) -> Option<RateLimit> {{
+    match id {{
+{protocol_rate_limits_code}
+        _ => None,
+    }}
+}}
+
 pub fn protocol_handler(id: ProtocolId) -> Result<ProtocolHandler, Error> {{
     use ProtocolHandler::*;
 
@@ -246,6 +512,13 @@ pub fn decode_global(
             protocol_local_decodes_code = protocol_local_decodes.join("\n"),
             protocol_global_decodes_code = protocol_global_decodes.join("\n"),
             protocol_handler_enums_code = protocol_handler_enums.join("\n"),
+            protocol_local_ids_code = protocol_local_ids.join("\n"),
+            protocol_local_encodes_code = protocol_local_encodes.join("\n"),
+            protocol_streams_code = protocol_streams.join("\n"),
+            protocol_rate_limits_code = protocol_rate_limits.join("\n"),
+            version_major = self.version.major,
+            version_minor = self.version.minor,
+            schema_hash = self.schema_hash,
         );
 
         let gen_file = PathBuf::from(&self.config.gen_dir).join("spire.protocol.game.impl.rs");
@@ -322,13 +595,223 @@ pub fn handle_global(
         Ok(())
     }
 
-    fn generate_docs(&self) -> Result<(), Error> {
+    fn generate_client(&self) -> Result<(), Error> {
+        let mut server_protocol_impls = Vec::new();
+        let mut protocol_client_enums = Vec::new();
+        let mut protocol_client_ids = Vec::new();
+        let mut protocol_client_decodes = Vec::new();
+
+        for entry in &self.protocol_entries {
+            let protocol_full_name = format!("protocol::game::{}::{}", entry.category, entry.protocol.protocol);
+
+            if matches!(entry.protocol.target, ProtocolTarget::Server | ProtocolTarget::All) {
+                server_protocol_impls.push(format!("impl ServerProtocol for {protocol_full_name} {{}}"));
+            }
+
+            if !matches!(entry.protocol.target, ProtocolTarget::Client | ProtocolTarget::All) {
+                continue;
+            }
+
+            if entry.protocol.__box {
+                protocol_client_enums.push(format!(
+                    "{TAB}{}(Box<{}>),",
+                    entry.protocol.protocol,
+                    protocol_full_name,
+                ));
+
+                protocol_client_decodes.push(format!(
+                    "{TAB}{TAB}{} => {}(Box::new({protocol_full_name}::decode(data)?)),",
+                    entry.number,
+                    entry.protocol.protocol,
+                ));
+            } else {
+                protocol_client_enums.push(format!(
+                    "{TAB}{}({}),",
+                    entry.protocol.protocol,
+                    protocol_full_name,
+                ));
+
+                protocol_client_decodes.push(format!(
+                    "{TAB}{TAB}{} => {}({protocol_full_name}::decode(data)?),",
+                    entry.number,
+                    entry.protocol.protocol,
+                ));
+            }
+
+            protocol_client_ids.push(format!(
+                "{TAB}{TAB}{TAB}{}(_) => {},",
+                entry.protocol.protocol,
+                entry.number,
+            ));
+        }
+
+        let code = format!(r#"use prost::Message;
+use protocol::game::{{Error, ProtocolId}};
+
+/// Protocols a client may send to the server.
+pub trait ServerProtocol: protocol::game::Protocol + Message {{}}
+
+/// Protocols received by a client.
+#[derive(Debug)]
+pub enum IngressClientProtocol {{
+{protocol_client_enums_code}
+}}
+
+impl IngressClientProtocol {{
+    pub fn protocol_id(&self) -> ProtocolId {{
+        use IngressClientProtocol::*;
+
+        match self {{
+{protocol_client_ids_code}
+        }}
+    }}
+}}
+
+pub fn decode_client(
+    id: ProtocolId,
+    data: bytes::Bytes,
+) -> Result<IngressClientProtocol, Error> {{
+    use IngressClientProtocol::*;
 
+    Ok(match id {{
+{protocol_client_decodes_code}
+        _ => return Err(Error::ProtocolId(id)),
+    }})
+}}
+
+{server_protocol_impls_code}
+"#,
+            protocol_client_enums_code = protocol_client_enums.join("\n"),
+            protocol_client_ids_code = protocol_client_ids.join("\n"),
+            protocol_client_decodes_code = protocol_client_decodes.join("\n"),
+            server_protocol_impls_code = server_protocol_impls.join("\n"),
+        );
+
+        let gen_file = PathBuf::from(&self.config.gen_dir).join("spire.protocol.game.client.rs");
+        fs::write(gen_file, &code)?;
+
+        Ok(())
+    }
+
+    /// Pairs of request and response names, as `category.Protocol`.
+    fn responses(&self) -> Result<HashMap<String, String>, Error> {
+        let names: HashSet<String> = self.protocol_entries
+            .iter()
+            .map(|entry| format!("{}.{}", entry.category, entry.protocol.protocol))
+            .collect();
+        let mut responses = HashMap::new();
+
+        for entry in &self.protocol_entries {
+            let name = format!("{}.{}", entry.category, entry.protocol.protocol);
+
+            let response = match &entry.protocol.response {
+                Some(response) if response.contains('.') => response.clone(),
+                Some(response) => format!("{}.{}", entry.category, response),
+                None => {
+                    let response = format!("{}Result", name);
+                    if names.contains(&response) {
+                        responses.insert(name, response);
+                    }
+                    continue;
+                }
+            };
+
+            if !names.contains(&response) {
+                return Err(Error::UnknownResponse(name, response));
+            }
+            responses.insert(name, response);
+        }
+
+        Ok(responses)
+    }
+
+    fn manifest(&self) -> Result<Manifest, Error> {
+        let descriptors = match &self.config.descriptor_set {
+            Some(descriptor_set) => Some(Descriptors::load(descriptor_set)?),
+            None => None,
+        };
+        let responses = self.responses()?;
+        // A response may answer several requests.
+        let mut requests: BTreeMap<&String, Vec<&String>> = BTreeMap::new();
+        for (request, response) in &responses {
+            requests.entry(response).or_default().push(request);
+        }
+        for requests in requests.values_mut() {
+            requests.sort();
+        }
+
+        let protocols = self.protocol_entries
+            .iter()
+            .map(|entry| {
+                let name = format!("{}.{}", entry.category, entry.protocol.protocol);
+                let message = format!("spire.protocol.game.{}", name);
+
+                let target = match entry.protocol.target {
+                    ProtocolTarget::Client => "client",
+                    ProtocolTarget::Server => "server",
+                    ProtocolTarget::All => "all",
+                };
+
+                let handled = entry.protocol.handle
+                    && matches!(entry.protocol.target, ProtocolTarget::Server | ProtocolTarget::All);
+                let handler = handled.then_some(match entry.protocol.handler {
+                    ProtocolHandler::Local => "local",
+                    ProtocolHandler::Global => "global",
+                });
+
+                let rate_limit = match (entry.protocol.rate_limit, entry.category_rate_limit) {
+                    (Some(limit), _) => Some((limit, false)),
+                    (None, Some((_, limit))) => Some((limit, true)),
+                    (None, None) => None,
+                };
+
+                ManifestProtocol {
+                    category: entry.category.clone(),
+                    id: entry.number,
+                    fields: descriptors.as_ref().and_then(|descriptors| descriptors.fields(&message)),
+                    message,
+                    target,
+                    handler,
+                    boxed: entry.protocol.__box,
+                    stream: entry.stream,
+                    rate_limit: rate_limit.map(|(limit, shared)| ManifestRateLimit {
+                        rate: limit.rate,
+                        capacity: limit.capacity,
+                        penalty: limit.penalty,
+                        shared,
+                    }),
+                    requests: requests
+                        .get(&name)
+                        .map(|requests| requests.iter().map(|request| request.to_string()).collect())
+                        .unwrap_or_default(),
+                    response: responses.get(&name).cloned(),
+                    name,
+                }
+            })
+            .collect();
+
+        Ok(Manifest {
+            version: ManifestVersion {
+                major: self.version.major,
+                minor: self.version.minor,
+            },
+            schema_hash: format!("{:#018x}", self.schema_hash),
+            protocols,
+        })
+    }
+
+    fn generate_docs(&self) -> Result<(), Error> {
         let Some(docs_dir) = self.config.docs_dir.as_ref() else {
             return Ok(());
         };
         fs::create_dir_all(docs_dir)?;
 
+        let manifest = self.manifest()?;
+
+        let mut json = serde_json::to_string_pretty(&manifest)?;
+        json.push('\n');
+        fs::write(docs_dir.join("game.json"), json)?;
+
         let file = File::create(docs_dir.join("game.md"))?;
         let mut writer = BufWriter::new(file);
 
@@ -336,37 +819,208 @@ pub fn handle_global(
 r#"
 # Game
 
+Version {}.{}, schema hash `{}`. A machine-readable manifest of the protocols is in `game.json`.
+
 ## Protocol Header
 
 | Offset | Field | Size | Description |
 | :----:  | :--------: | :------: | :------------ |
 | 0 - 1 | Length | 2 Bytes | The total size of the protocol **excluding** header size. |
-| 2 - 3 | Protocol ID | 2 Bytes | An unique identifier for the protocol type. |
+| 2 - 3 | Protocol ID | 2 Bytes | An unique identifier for the protocol type. The highest bit is set if the body is compressed. |
+| 4 - 7 | Extended Length | 4 Bytes | Only if Length is `0xFFFF`: the total size of the protocol **excluding** header size. |
+
+Protocols of `0xFFFF` bytes or more set Length to `0xFFFF` and carry the actual size in Extended Length.
+
+A compressed body is an LZ4 block prefixed with its uncompressed size, as 4 bytes little endian.
+Compression is negotiated at login, and only bodies of at least the threshold are compressed.
+
+## Streams
+
+Stream `0` is the bi-directional stream opened by the client on login. Protocols of other streams
+are carried on uni-directional streams, opened by the sender on demand, whose first byte is the
+stream number. Protocols keep their header on every stream.
 
 ## Protocols
 
-| Category | ID | Name | Target |
-|:--------:|---:|:----:|:------:|
-"#
+| Category | ID | Name | Target | Handler | Stream | Rate Limit |
+|:--------:|---:|:----:|:------:|:-------:|:------:|:----------:|
+"#,
+            manifest.version.major,
+            manifest.version.minor,
+            manifest.schema_hash,
         )?;
 
-        for entry in &self.protocol_entries {
-            let target = match entry.protocol.target {
-                ProtocolTarget::Client => "Client",
-                ProtocolTarget::Server => "Server",
-                ProtocolTarget::All => "All",
-            };
-
-            write!(
+        for protocol in &manifest.protocols {
+            writeln!(
                 writer,
-                "|{}|{}|{}|{}|\n",
-                entry.category,
-                entry.number,
-                entry.protocol.protocol,
-                target,
+                "|{}|{}|[{}](#{})|{}|{}|{}|{}|",
+                protocol.category,
+                protocol.id,
+                protocol.name,
+                protocol.name,
+                protocol.target,
+                protocol.handler.unwrap_or(""),
+                protocol.stream,
+                rate_limit_docs(protocol),
             )?;
         }
 
+        writeln!(writer, "\n## Messages")?;
+
+        for protocol in &manifest.protocols {
+            writeln!(writer, "\n### <a id=\"{0}\"></a>{0}\n", protocol.name)?;
+            writeln!(writer, "`{}`, id {}, sent to the {}.", protocol.message, protocol.id, match protocol.target {
+                "client" => "client",
+                "server" => "server",
+                _ => "client and the server",
+            })?;
+
+            let mut notes = Vec::new();
+            if let Some(handler) = protocol.handler {
+                notes.push(format!("Handled {}", if handler == "local" { "by the zone" } else { "globally" }));
+            }
+            if protocol.boxed {
+                notes.push("boxed".to_string());
+            }
+            if protocol.stream != 0 {
+                notes.push(format!("on stream {}", protocol.stream));
+            }
+            if protocol.rate_limit.is_some() {
+                notes.push(format!("limited to {}", rate_limit_docs(protocol)));
+            }
+            if !notes.is_empty() {
+                let mut notes = notes.join(", ");
+                if let Some(first) = notes.get_mut(0..1) {
+                    first.make_ascii_uppercase();
+                }
+                writeln!(writer, "{}.", notes)?;
+            }
+
+            if !protocol.requests.is_empty() {
+                let requests: Vec<String> = protocol.requests
+                    .iter()
+                    .map(|request| format!("[{0}](#{0})", request))
+                    .collect();
+                writeln!(writer, "\nResponse to {}.", requests.join(", "))?;
+            }
+            if let Some(response) = &protocol.response {
+                writeln!(writer, "\nAnswered by [{0}](#{0}).", response)?;
+            }
+
+            match &protocol.fields {
+                Some(fields) if !fields.is_empty() => {
+                    writeln!(writer, "\n| Field | Number | Type |\n|:------|-------:|:-----|")?;
+                    for field in fields {
+                        writeln!(writer, "|{}|{}|`{}`|", field.name, field.number, field.r#type)?;
+                    }
+                }
+                Some(_) => writeln!(writer, "\nNo fields.")?,
+                None => {}
+            }
+        }
+
         Ok(())
     }
 }
+
+/// Rate per second and bucket capacity, suffixed by `*` if shared by the category.
+fn rate_limit_docs(protocol: &ManifestProtocol) -> String {
+    match &protocol.rate_limit {
+        Some(limit) if limit.shared => format!("{}/{}*", limit.rate, limit.capacity),
+        Some(limit) => format!("{}/{}", limit.rate, limit.capacity),
+        None => String::new(),
+    }
+}
+
+/// Paths matching the pattern. Unreadable entries are skipped.
+fn glob_paths(pattern: &Path) -> Result<Vec<PathBuf>, Error> {
+    let pattern = pattern.to_str().ok_or_else(|| Error::NonUtf8Path(pattern.to_path_buf()))?;
+
+    Ok(glob(pattern)?.filter_map(Result::ok).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn category(json: &str) -> Category {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn ids(entries: &[ProtocolEntry]) -> Vec<u16> {
+        entries.iter().map(|entry| entry.number).collect()
+    }
+
+    #[test]
+    fn assigns_explicit_and_following_ids() {
+        let entries = assign_ids(vec![category(r#"{
+            "category": "auth", "offset": 1,
+            "protocols": [
+                { "protocol": "Login", "target": "server" },
+                { "protocol": "Logout", "id": 5, "target": "server" },
+                { "protocol": "Ping", "target": "server" }
+            ]
+        }"#)]).unwrap();
+
+        assert_eq!(ids(&entries), [1, 5, 6]);
+    }
+
+    #[test]
+    fn rejects_id_out_of_category() {
+        let result = assign_ids(vec![
+            category(r#"{ "category": "auth", "offset": 1, "protocols": [
+                { "protocol": "Login", "id": 10, "target": "server" }
+            ] }"#),
+            category(r#"{ "category": "net", "offset": 10, "protocols": [] }"#),
+        ]);
+
+        assert!(matches!(result, Err(Error::ProtocolId(_, 10))));
+    }
+
+    #[test]
+    fn rejects_duplicate_id() {
+        let result = assign_ids(vec![category(r#"{ "category": "auth", "offset": 1, "protocols": [
+            { "protocol": "Login", "id": 2, "target": "server" },
+            { "protocol": "Logout", "id": 2, "target": "server" }
+        ] }"#)]);
+
+        assert!(matches!(result, Err(Error::DuplicateId(2, _, _))));
+    }
+
+    #[test]
+    fn rejects_overlapping_categories() {
+        let result = assign_ids(vec![
+            category(r#"{ "category": "auth", "offset": 1, "capacity": 10, "protocols": [] }"#),
+            category(r#"{ "category": "net", "offset": 10, "protocols": [] }"#),
+        ]);
+
+        assert!(matches!(result, Err(Error::OverlappingCategories(_, _))));
+    }
+
+    #[test]
+    fn lists_every_request_of_shared_response() {
+        let mut generator = Generator::new(Config {
+            schema_dir: PathBuf::new(),
+            gen_dir: PathBuf::new(),
+            docs_dir: None,
+            descriptor_set: None,
+            lock_file: None,
+            generate_impl: false,
+            generate_handle: false,
+            generate_client: false,
+        });
+        generator.protocol_entries = assign_ids(vec![category(r#"{ "category": "play", "offset": 1, "protocols": [
+            { "protocol": "SkillUse", "target": "server", "response": "SkillResult" },
+            { "protocol": "SkillCancel", "target": "server", "response": "SkillResult" },
+            { "protocol": "SkillResult", "target": "client" }
+        ] }"#)]).unwrap();
+
+        let manifest = generator.manifest().unwrap();
+        let result = manifest.protocols.iter().find(|protocol| protocol.name == "play.SkillResult").unwrap();
+        assert_eq!(result.requests, ["play.SkillCancel", "play.SkillUse"]);
+        assert!(manifest.protocols
+            .iter()
+            .filter(|protocol| protocol.name != "play.SkillResult")
+            .all(|protocol| protocol.response.as_deref() == Some("play.SkillResult")));
+    }
+}
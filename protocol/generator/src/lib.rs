@@ -1,5 +1,8 @@
+mod descriptor;
 mod error;
 mod generator;
+mod lock;
+mod manifest;
 
 use std::path::PathBuf;
 
@@ -11,8 +14,14 @@ pub struct Config {
     pub schema_dir: PathBuf,
     pub gen_dir: PathBuf,
     pub docs_dir: Option<PathBuf>,
+    /// File descriptor set of the game protocols, for the fields of messages in the docs.
+    pub descriptor_set: Option<PathBuf>,
+    /// Ids previously assigned to protocols, which must not change. Updated with new protocols.
+    pub lock_file: Option<PathBuf>,
     pub generate_impl: bool,
     pub generate_handle: bool,
+    /// Typed encoding and decoding for clients, on top of the `protocol` crate.
+    pub generate_client: bool,
 }
 
 impl Config {
@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// Ids assigned to protocols by previous builds, keyed by `category.Protocol`.
+/// A locked protocol may not change its id, nor disappear unless retired.
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Lock {
+    protocols: BTreeMap<String, u16>,
+    #[serde(default)]
+    retired: BTreeMap<String, u16>,
+}
+
+impl Lock {
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        fs::write(path, json)?;
+
+        Ok(())
+    }
+
+    /// Check the protocols against the lock, then lock new and retired ones.
+    /// Returns whether the lock changed.
+    pub fn update(
+        &mut self,
+        protocols: &BTreeMap<String, u16>,
+        retired: &[String],
+    ) -> Result<bool, Error> {
+        let mut changed = false;
+
+        for (name, &locked) in &self.protocols {
+            match protocols.get(name) {
+                Some(&id) if id != locked => return Err(Error::Renumbered(name.clone(), locked, id)),
+                Some(_) => {}
+                None if retired.contains(name) => {}
+                None => return Err(Error::Removed(name.clone(), locked)),
+            }
+        }
+
+        for name in retired {
+            if let Some(id) = self.protocols.remove(name) {
+                self.retired.insert(name.clone(), id);
+                changed = true;
+            }
+        }
+
+        for (name, &id) in protocols {
+            if let Some((retired, _)) = self.retired.iter().find(|&(_, &retired)| retired == id) {
+                return Err(Error::RetiredId(name.clone(), id, retired.clone()));
+            }
+
+            if self.protocols.insert(name.clone(), id).is_none() {
+                changed = true;
+            }
+        }
+
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn protocols(entries: &[(&str, u16)]) -> BTreeMap<String, u16> {
+        entries.iter().map(|&(name, id)| (name.to_string(), id)).collect()
+    }
+
+    #[test]
+    fn locks_new_protocols() {
+        let mut lock = Lock::default();
+
+        assert!(lock.update(&protocols(&[("auth.Login", 1), ("auth.Logout", 2)]), &[]).unwrap());
+        assert!(!lock.update(&protocols(&[("auth.Login", 1), ("auth.Logout", 2)]), &[]).unwrap());
+        assert!(lock.update(&protocols(&[("auth.Login", 1), ("auth.Logout", 2), ("auth.Ping", 3)]), &[]).unwrap());
+    }
+
+    #[test]
+    fn rejects_renumbered() {
+        let mut lock = Lock::default();
+        lock.update(&protocols(&[("auth.Login", 1), ("auth.Logout", 2)]), &[]).unwrap();
+
+        let result = lock.update(&protocols(&[("auth.Logout", 1), ("auth.Login", 2)]), &[]);
+        assert!(matches!(result, Err(Error::Renumbered(_, 1, 2))));
+    }
+
+    #[test]
+    fn rejects_removed_unless_retired() {
+        let mut lock = Lock::default();
+        lock.update(&protocols(&[("auth.Login", 1), ("auth.Logout", 2)]), &[]).unwrap();
+
+        let result = lock.update(&protocols(&[("auth.Login", 1)]), &[]);
+        assert!(matches!(result, Err(Error::Removed(_, 2))));
+
+        assert!(lock.update(&protocols(&[("auth.Login", 1)]), &["auth.Logout".to_string()]).unwrap());
+        assert_eq!(lock.retired.get("auth.Logout"), Some(&2));
+    }
+
+    #[test]
+    fn rejects_reused_retired_id() {
+        let mut lock = Lock::default();
+        lock.update(&protocols(&[("auth.Login", 1), ("auth.Logout", 2)]), &[]).unwrap();
+        lock.update(&protocols(&[("auth.Login", 1)]), &["auth.Logout".to_string()]).unwrap();
+
+        let result = lock.update(&protocols(&[("auth.Login", 1), ("auth.Leave", 2)]), &[]);
+        assert!(matches!(result, Err(Error::RetiredId(_, 2, _))));
+    }
+}
@@ -0,0 +1,48 @@
+use serde::Serialize;
+
+use crate::descriptor::Field;
+
+/// Machine-readable description of the game protocols, written next to the docs.
+#[derive(Debug, Serialize)]
+pub struct Manifest {
+    pub version: ManifestVersion,
+    /// Hexadecimal, as JSON numbers lose precision past 53 bits.
+    pub schema_hash: String,
+    pub protocols: Vec<ManifestProtocol>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManifestVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManifestProtocol {
+    /// `category.Protocol`, also used to refer to the protocol in `request` and `response`.
+    pub name: String,
+    pub category: String,
+    pub id: u16,
+    /// Full name of the message.
+    pub message: String,
+    pub target: &'static str,
+    /// `local` or `global` for protocols handled by the server.
+    pub handler: Option<&'static str>,
+    pub boxed: bool,
+    pub stream: u8,
+    pub rate_limit: Option<ManifestRateLimit>,
+    /// Requests answered by the protocol, sorted. A response may be shared by several.
+    pub requests: Vec<String>,
+    pub response: Option<String>,
+    /// Missing without a descriptor set.
+    pub fields: Option<Vec<Field>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManifestRateLimit {
+    pub rate: f32,
+    pub capacity: f32,
+    pub penalty: f32,
+    /// Shared by the protocols of the category.
+    pub shared: bool,
+}
@@ -23,10 +23,50 @@ pub mod social {
 use bytes::{BufMut, Bytes, BytesMut};
 
 pub type ProtocolId = u16;
+pub type StreamId = u8;
 
+/// Length of the body, then the protocol id, both big endian.
+///
+/// A body of `EXTENDED_LENGTH` bytes or more has `EXTENDED_LENGTH` in the length field, followed
+/// by the actual length in 4 more bytes. A compressed body has `COMPRESSED_FLAG` set in the id
+/// field.
 pub struct Header {
-    pub length: u16,
+    pub length: u32,
     pub id: ProtocolId,
+    pub compressed: bool,
+}
+
+/// LZ4 compression of protocol bodies, negotiated at login.
+#[derive(Debug, Clone, Copy)]
+pub struct Compression {
+    /// Bodies shorter than this are sent as is.
+    pub threshold: usize,
+}
+
+/// Version of the protocol schema. Protocols of a minor version are a superset of the previous
+/// minor versions of the same major version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+/// Token bucket limiting a protocol received from a client.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub key: RateLimitKey,
+    pub rate: f32,
+    pub capacity: f32,
+    /// Abuse score added on each violation.
+    pub penalty: f32,
+}
+
+/// Protocols with the same key share a bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateLimitKey {
+    Protocol(ProtocolId),
+    /// Shared by the protocols of the category starting at the id.
+    Category(ProtocolId),
 }
 
 pub trait Protocol {
@@ -39,46 +79,137 @@ pub enum ProtocolHandler {
 }
 
 impl Header {
+    pub const EXTENDED_LENGTH: u16 = u16::MAX;
+    /// Protocol ids are below it, leaving the bit free.
+    pub const COMPRESSED_FLAG: u16 = 0x8000;
+
+    /// Size of the header without extended length.
     pub const fn size() -> usize { 4 }
 
-    pub fn encode(buffer: &mut BytesMut, length: usize, id: ProtocolId) -> Result<(), Error> {
-        if buffer.remaining_mut() < Self::size() {
-            return Err(Error::NotEnoughBuffer(buffer.remaining_mut(), Self::size()));
+    pub const fn extended_size() -> usize { Self::size() + 4 }
+
+    /// Size of the header for a body of `length` bytes.
+    pub const fn encoded_size(length: usize) -> usize {
+        if length >= Self::EXTENDED_LENGTH as usize {
+            Self::extended_size()
+        } else {
+            Self::size()
+        }
+    }
+
+    /// Size of the header starting the buffer, which must hold at least `size()` bytes.
+    pub fn decoded_size(buffer: &[u8]) -> usize {
+        if buffer.len() >= 2 && u16::from_be_bytes([buffer[0], buffer[1]]) == Self::EXTENDED_LENGTH {
+            Self::extended_size()
+        } else {
+            Self::size()
+        }
+    }
+
+    pub fn encode(
+        buffer: &mut BytesMut,
+        length: usize,
+        id: ProtocolId,
+        compressed: bool,
+    ) -> Result<(), Error> {
+        let size = Self::encoded_size(length);
+        if buffer.remaining_mut() < size {
+            return Err(Error::NotEnoughBuffer(buffer.remaining_mut(), size));
         }
 
-        buffer.put_u8((length >> 8) as u8);
-        buffer.put_u8(length as u8);
-        buffer.put_u8((id >> 8) as u8);
-        buffer.put_u8(id as u8);
+        if id & Self::COMPRESSED_FLAG != 0 {
+            return Err(Error::ProtocolId(id));
+        }
+        let id = if compressed { id | Self::COMPRESSED_FLAG } else { id };
+
+        if size == Self::extended_size() {
+            let length = u32::try_from(length).map_err(|_| Error::ProtocolLength(length))?;
+
+            buffer.put_u16(Self::EXTENDED_LENGTH);
+            buffer.put_u16(id);
+            buffer.put_u32(length);
+        } else {
+            buffer.put_u16(length as u16);
+            buffer.put_u16(id);
+        }
 
         Ok(())
     }
 
     pub fn decode(buffer: &[u8]) -> Result<Self, Error> {
-        let length = ((buffer[0] as u16) << 8) | (buffer[1] as u16);
-        let id = ((buffer[2] as u16) << 8) | (buffer[3] as u16);
+        let size = Self::decoded_size(buffer);
+        if buffer.len() < size {
+            return Err(Error::NotEnoughBuffer(buffer.len(), size));
+        }
+
+        let length = u16::from_be_bytes([buffer[0], buffer[1]]);
+        let id = u16::from_be_bytes([buffer[2], buffer[3]]);
+        let length = if length == Self::EXTENDED_LENGTH {
+            u32::from_be_bytes([buffer[4], buffer[5], buffer[6], buffer[7]])
+        } else {
+            length as u32
+        };
 
         Ok(Self {
             length,
-            id,
+            id: id & !Self::COMPRESSED_FLAG,
+            compressed: id & Self::COMPRESSED_FLAG != 0,
         })
     }
 }
 
 pub fn encode(protocol: &(impl prost::Message + Protocol)) -> Result<Bytes, Error> {
+    encode_with(protocol, None)
+}
+
+/// Encode the protocol, compressing its body if at least the threshold of the compression.
+/// A body which does not shrink is sent as is.
+pub fn encode_with(
+    protocol: &(impl prost::Message + Protocol),
+    compression: Option<Compression>,
+) -> Result<Bytes, Error> {
     let length = protocol.encoded_len();
-    if length > u16::MAX as usize {
+    if length > u32::MAX as usize {
         return Err(Error::ProtocolLength(length));
     }
 
-    let mut buffer = BytesMut::with_capacity(Header::size() + length);
+    if let Some(compression) = compression
+        && length >= compression.threshold {
+        let compressed = lz4_flex::compress_prepend_size(&protocol.encode_to_vec());
+
+        if compressed.len() < length {
+            let mut buffer = BytesMut::with_capacity(Header::encoded_size(compressed.len()) + compressed.len());
+
+            Header::encode(&mut buffer, compressed.len(), protocol.protocol_id(), true)?;
+            buffer.put_slice(&compressed);
+
+            return Ok(buffer.freeze());
+        }
+    }
+
+    let mut buffer = BytesMut::with_capacity(Header::encoded_size(length) + length);
 
-    Header::encode(&mut buffer, length, protocol.protocol_id())?;
+    Header::encode(&mut buffer, length, protocol.protocol_id(), false)?;
     protocol.encode(&mut buffer)?;
 
     Ok(buffer.freeze())
 }
 
+/// Decompress a body with `Header::compressed`, refusing to inflate it past `max_length`.
+pub fn decompress(body: &[u8], max_length: usize) -> Result<Bytes, Error> {
+    // The uncompressed length prepended by `lz4_flex::compress_prepend_size`.
+    let Some((length, compressed)) = body.split_first_chunk::<4>() else {
+        return Err(Error::NotEnoughBuffer(body.len(), 4));
+    };
+
+    let length = u32::from_le_bytes(*length) as usize;
+    if length > max_length {
+        return Err(Error::ProtocolLength(length));
+    }
+
+    Ok(Bytes::from(lz4_flex::decompress(compressed, length)?))
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("Invalid protocol length: {0}")]
@@ -96,6 +227,114 @@ pub enum Error {
     #[error("Failed to decode: {0}")]
     Decode(#[from] prost::DecodeError),
 
+    #[error("Failed to decompress: {0}")]
+    Decompress(#[from] lz4_flex::block::DecompressError),
+
     #[error("Unhandled protocol id: {0}")]
     UnhandledProtocol(ProtocolId),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct Blob {
+        #[prost(bytes = "vec", tag = "1")]
+        data: Vec<u8>,
+    }
+
+    impl Protocol for Blob {
+        fn protocol_id(&self) -> ProtocolId { 1 }
+    }
+
+    fn round_trip(length: usize, id: ProtocolId, compressed: bool) -> Header {
+        let mut buffer = BytesMut::with_capacity(Header::encoded_size(length));
+        Header::encode(&mut buffer, length, id, compressed).unwrap();
+        assert_eq!(buffer.len(), Header::encoded_size(length));
+        assert_eq!(Header::decoded_size(&buffer), buffer.len());
+
+        Header::decode(&buffer).unwrap()
+    }
+
+    #[test]
+    fn header_round_trip() {
+        let header = round_trip(Header::EXTENDED_LENGTH as usize - 1, 7, false);
+        assert_eq!((header.length, header.id, header.compressed), (u16::MAX as u32 - 1, 7, false));
+        assert_eq!(Header::encoded_size(header.length as usize), Header::size());
+    }
+
+    #[test]
+    fn extended_header_round_trip() {
+        let header = round_trip(Header::EXTENDED_LENGTH as usize, 7, false);
+        assert_eq!(header.length, Header::EXTENDED_LENGTH as u32);
+
+        let max_id = Header::COMPRESSED_FLAG - 1;
+        let header = round_trip(u32::MAX as usize, max_id, true);
+        assert_eq!((header.length, header.id, header.compressed), (u32::MAX, max_id, true));
+    }
+
+    #[test]
+    fn header_rejects_invalid() {
+        let mut buffer = BytesMut::with_capacity(Header::extended_size());
+        assert!(matches!(
+            Header::encode(&mut buffer, 1, Header::COMPRESSED_FLAG, false),
+            Err(Error::ProtocolId(_))
+        ));
+        assert!(matches!(
+            Header::encode(&mut buffer, u32::MAX as usize + 1, 1, false),
+            Err(Error::ProtocolLength(_))
+        ));
+        assert!(matches!(
+            Header::decode(&[0xFF, 0xFF, 0, 1]),
+            Err(Error::NotEnoughBuffer(4, 8))
+        ));
+    }
+
+    #[test]
+    fn compressed_flag_in_id() {
+        let mut buffer = BytesMut::with_capacity(Header::size());
+        Header::encode(&mut buffer, 10, 0x1234, true).unwrap();
+        assert_eq!(&buffer[..], [0, 10, 0x92, 0x34]);
+
+        let header = Header::decode(&buffer).unwrap();
+        assert_eq!((header.id, header.compressed), (0x1234, true));
+    }
+
+    #[test]
+    fn compressed_round_trip() {
+        let blob = Blob { data: vec![7; 4096] };
+        let compression = Compression { threshold: 64 };
+
+        let bytes = encode_with(&blob, Some(compression)).unwrap();
+        let header = Header::decode(&bytes).unwrap();
+        assert!(header.compressed);
+        assert_eq!(header.id, 1);
+        assert!((header.length as usize) < blob.encoded_len());
+
+        let body = decompress(&bytes[Header::size()..], blob.encoded_len()).unwrap();
+        assert_eq!(Blob::decode(body).unwrap(), blob);
+    }
+
+    #[test]
+    fn uncompressed_below_threshold() {
+        let blob = Blob { data: vec![7; 16] };
+
+        let bytes = encode_with(&blob, Some(Compression { threshold: 64 })).unwrap();
+        let header = Header::decode(&bytes).unwrap();
+        assert!(!header.compressed);
+        assert_eq!(header.length as usize, blob.encoded_len());
+    }
+
+    #[test]
+    fn decompress_rejects_past_max_length() {
+        let body = lz4_flex::compress_prepend_size(&vec![0; 1 << 20]);
+
+        assert!(matches!(
+            decompress(&body, 1 << 16),
+            Err(Error::ProtocolLength(length)) if length == 1 << 20
+        ));
+        assert_eq!(decompress(&body, 1 << 20).unwrap().len(), 1 << 20);
+    }
+}
@@ -0,0 +1,67 @@
+use std::time::Instant;
+
+/// Score of misbehavior, raised by violations and decaying linearly over time.
+#[derive(Debug, Clone)]
+pub struct AbuseScore {
+    score: f32,
+    decay_per_second: f32,
+    last_update: Option<Instant>,
+}
+
+impl AbuseScore {
+    pub fn new(decay_per_second: f32) -> Self {
+        Self {
+            score: 0.0,
+            decay_per_second,
+            last_update: None,
+        }
+    }
+
+    /// Add the penalty of a violation at `now`, returning the new score.
+    pub fn add(&mut self, penalty: f32, now: Instant) -> f32 {
+        self.decay(now);
+        self.score += penalty;
+        self.score
+    }
+
+    /// The score at `now`.
+    pub fn get(&mut self, now: Instant) -> f32 {
+        self.decay(now);
+        self.score
+    }
+
+    fn decay(&mut self, now: Instant) {
+        if let Some(last_update) = self.last_update {
+            let elapsed = now.saturating_duration_since(last_update).as_secs_f32();
+            self.score = (self.score - elapsed * self.decay_per_second).max(0.0);
+        }
+
+        self.last_update = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_add() {
+        let now = Instant::now();
+        let mut score = AbuseScore::new(1.0);
+
+        assert_eq!(score.add(2.0, now), 2.0);
+        assert_eq!(score.add(3.0, now), 5.0);
+    }
+
+    #[test]
+    fn test_decay() {
+        let now = Instant::now();
+        let mut score = AbuseScore::new(2.0);
+        score.add(5.0, now);
+
+        assert_eq!(score.get(now + Duration::from_secs(1)), 3.0);
+        assert_eq!(score.get(now + Duration::from_secs(10)), 0.0);
+        assert_eq!(score.add(1.0, now + Duration::from_secs(11)), 1.0);
+    }
+}
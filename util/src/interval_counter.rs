@@ -25,6 +25,12 @@ impl IntervalCounter {
         let now = Instant::now();
         let duration = now.duration_since(self.last_tick);
 
+        self.record(duration);
+        self.last_tick = now;
+    }
+
+    /// Push a measured duration into the window, e.g. the cost of a tick.
+    pub fn record(&mut self, duration: Duration) {
         if self.window.len() == self.window_size {
             if let Some(duration) = self.window.pop_front() {
                 self.duration_total = self.duration_total.saturating_sub(duration);
@@ -33,8 +39,20 @@ impl IntervalCounter {
 
         self.window.push_back(duration);
         self.duration_total = self.duration_total.saturating_add(duration);
+    }
 
-        self.last_tick = now;
+    /// Average duration in the window.
+    pub fn average_duration(&self) -> Duration {
+        if self.window.is_empty() {
+            return Duration::ZERO;
+        }
+
+        self.duration_total / self.window.len() as u32
+    }
+
+    /// Longest duration in the window.
+    pub fn max_duration(&self) -> Duration {
+        self.window.iter().max().copied().unwrap_or_default()
     }
 
     /// Average seconds between ticks.
@@ -1,6 +1,8 @@
+pub mod abuse;
 pub mod grid;
 pub mod id;
 pub mod io;
 pub mod interval_counter;
 pub mod rate_limiter;
+pub mod rtt;
 pub mod token;
@@ -23,6 +23,12 @@ pub enum Error {
     Exceed { rate: f32, capacity: f32 },
 }
 
+impl Params {
+    pub const fn new(rate: f32, capacity: f32) -> Self {
+        Self { rate, capacity }
+    }
+}
+
 impl RateLimiter {
     pub fn new(params: Params) -> Self {
         Self {
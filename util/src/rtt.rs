@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+const ALPHA: u32 = 8; // 1/8 as in RFC 6298
+const BETA: u32 = 4; // 1/4 as in RFC 6298
+
+/// Smoothed round-trip time and jitter from RTT samples, following RFC 6298.
+#[derive(Debug, Default, Clone)]
+pub struct RttEstimator {
+    latest: Duration,
+    smoothed: Duration,
+    jitter: Duration,
+    min: Option<Duration>,
+    samples: u64,
+}
+
+impl RttEstimator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, sample: Duration) {
+        self.latest = sample;
+        self.min = Some(self.min.map_or(sample, |min| min.min(sample)));
+
+        if self.samples == 0 {
+            self.smoothed = sample;
+            self.jitter = sample / 2;
+        } else {
+            let deviation = self.smoothed.abs_diff(sample);
+            self.jitter = (self.jitter * (BETA - 1) + deviation) / BETA;
+            self.smoothed = (self.smoothed * (ALPHA - 1) + sample) / ALPHA;
+        }
+
+        self.samples += 1;
+    }
+
+    /// The last sample.
+    pub fn latest(&self) -> Duration {
+        self.latest
+    }
+
+    /// Smoothed RTT. Zero without any sample.
+    pub fn smoothed(&self) -> Duration {
+        self.smoothed
+    }
+
+    /// Smoothed mean deviation of the samples.
+    pub fn jitter(&self) -> Duration {
+        self.jitter
+    }
+
+    pub fn min(&self) -> Option<Duration> {
+        self.min
+    }
+
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_sample() {
+        let mut rtt = RttEstimator::new();
+        rtt.update(Duration::from_millis(100));
+
+        assert_eq!(rtt.smoothed(), Duration::from_millis(100));
+        assert_eq!(rtt.jitter(), Duration::from_millis(50));
+        assert_eq!(rtt.min(), Some(Duration::from_millis(100)));
+        assert_eq!(rtt.samples(), 1);
+    }
+
+    #[test]
+    fn test_smoothing() {
+        let mut rtt = RttEstimator::new();
+        rtt.update(Duration::from_millis(100));
+        rtt.update(Duration::from_millis(180));
+
+        assert_eq!(rtt.latest(), Duration::from_millis(180));
+        assert_eq!(rtt.smoothed(), Duration::from_millis(110));
+        assert_eq!(rtt.jitter(), Duration::from_millis(57) + Duration::from_micros(500));
+        assert_eq!(rtt.min(), Some(Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn test_converges() {
+        let mut rtt = RttEstimator::new();
+        rtt.update(Duration::from_millis(500));
+        for _ in 0..100 {
+            rtt.update(Duration::from_millis(40));
+        }
+
+        assert!(rtt.smoothed().abs_diff(Duration::from_millis(40)) < Duration::from_millis(1));
+        assert!(rtt.jitter() < Duration::from_millis(1));
+        assert_eq!(rtt.min(), Some(Duration::from_millis(40)));
+    }
+}